@@ -4,6 +4,7 @@ mod legacy_thread;
 mod native_agent_server;
 pub mod outline;
 mod pattern_extraction;
+mod retry;
 mod templates;
 #[cfg(test)]
 mod tests;
@@ -1247,6 +1248,9 @@ impl NativeAgentConnection {
                                     thread.update_retry_status(status, cx)
                                 })?;
                             }
+                            ThreadEvent::TurnSummary(summary) => {
+                                log::debug!("Turn summary: {:?}", summary);
+                            }
                             ThreadEvent::Stop(stop_reason) => {
                                 log::debug!("Assistant message complete: {:?}", stop_reason);
                                 return Ok(acp::PromptResponse::new(stop_reason));
@@ -1754,7 +1758,12 @@ impl acp_thread::AgentSessionRetry for NativeAgentSessionRetry {
     fn run(&self, cx: &mut App) -> Task<Result<acp::PromptResponse>> {
         self.connection
             .run_turn(self.session_id.clone(), cx, |thread, cx| {
-                thread.update(cx, |thread, cx| thread.resume(cx))
+                thread.update(cx, |thread, cx| {
+                    match thread.resume_interrupted_turn(cx)? {
+                        Some(events) => Ok(events),
+                        None => thread.resume(cx),
+                    }
+                })
             })
     }
 }
@@ -2115,15 +2124,20 @@ impl TerminalHandle for AcpTerminalHandle {
 #[cfg(test)]
 mod internal_tests {
     use std::path::Path;
+    use std::time::Duration;
 
     use super::*;
-    use acp_thread::{AgentConnection, AgentModelGroupName, AgentModelInfo, MentionUri};
+    use acp_thread::{
+        AcpThreadEvent, AgentConnection, AgentModelGroupName, AgentModelInfo, MentionUri,
+        ThreadStatus,
+    };
     use fs::FakeFs;
     use gpui::TestAppContext;
     use indoc::formatdoc;
     use language_model::fake_provider::{FakeLanguageModel, FakeLanguageModelProvider};
     use language_model::{
-        LanguageModelCompletionEvent, LanguageModelProviderId, LanguageModelProviderName,
+        LanguageModelCompletionError, LanguageModelCompletionEvent, LanguageModelProviderId,
+        LanguageModelProviderName,
     };
     use serde_json::json;
     use settings::SettingsStore;
@@ -2926,6 +2940,110 @@ mod internal_tests {
         });
     }
 
+    #[gpui::test]
+    async fn test_retrying_a_loaded_thread_resumes_its_interrupted_turn(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/", json!({ "a": {} })).await;
+        let project = Project::test(fs.clone(), [path!("/a").as_ref()], cx).await;
+        let thread_store = cx.new(|cx| ThreadStore::new(cx));
+        let agent = cx.update(|cx| {
+            NativeAgent::new(thread_store.clone(), Templates::new(), None, fs.clone(), cx)
+        });
+        let connection = Rc::new(NativeAgentConnection(agent.clone()));
+
+        let model = Arc::new(FakeLanguageModel::with_id_and_thinking(
+            "fake-corp",
+            "fake-model",
+            "Fake Model",
+            false,
+        ));
+        let provider = Arc::new(
+            FakeLanguageModelProvider::new(
+                LanguageModelProviderId::from("fake-corp".to_string()),
+                LanguageModelProviderName::from("Fake Corp".to_string()),
+            )
+            .with_models(vec![model.clone()]),
+        );
+        cx.update(|cx| {
+            LanguageModelRegistry::global(cx).update(cx, |registry, cx| {
+                registry.register_provider(provider, cx);
+            });
+        });
+        agent.update(cx, |agent, cx| agent.models.refresh_list(cx));
+
+        let acp_thread = cx
+            .update(|cx| {
+                connection.clone().new_session(
+                    project.clone(),
+                    PathList::new(&[Path::new("/a")]),
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+        let session_id = acp_thread.read_with(cx, |thread, _| thread.session_id().clone());
+        let selector = connection.model_selector(&session_id).unwrap();
+        cx.update(|cx| selector.select_model(acp::ModelId::new("fake-corp/fake-model"), cx))
+            .await
+            .unwrap();
+
+        // Close the session while the model is still responding.
+        let send = acp_thread.update(cx, |thread, cx| thread.send(vec!["Hello".into()], cx));
+        let _send = cx.foreground_executor().spawn(send);
+        cx.run_until_parked();
+        model.send_last_completion_stream_text_chunk("Let me");
+        cx.run_until_parked();
+        cx.update(|cx| connection.clone().close_session(&session_id, cx))
+            .await
+            .unwrap();
+        drop(acp_thread);
+        cx.run_until_parked();
+
+        // Loading the thread keeps the interrupted turn without sending
+        // anything to the model on its own.
+        let acp_thread = agent
+            .update(cx, |agent, cx| {
+                agent.open_thread(session_id.clone(), project.clone(), cx)
+            })
+            .await
+            .unwrap();
+        cx.run_until_parked();
+        let thread = agent.read_with(cx, |agent, _| {
+            agent.sessions.get(&session_id).unwrap().thread.clone()
+        });
+        thread.read_with(cx, |thread, _| assert!(thread.has_interrupted_turn()));
+        acp_thread.read_with(cx, |thread, _| {
+            assert_eq!(thread.status(), ThreadStatus::Idle);
+        });
+        assert!(model.pending_completions().is_empty());
+
+        // Retrying picks the turn back up and shows it as running.
+        let _retry = acp_thread.update(cx, |thread, cx| thread.retry(cx));
+        cx.run_until_parked();
+        thread.read_with(cx, |thread, _| assert!(!thread.has_interrupted_turn()));
+        acp_thread.read_with(cx, |thread, _| {
+            assert_eq!(thread.status(), ThreadStatus::Generating);
+        });
+        let request = model.pending_completions().pop().unwrap();
+        assert!(
+            request
+                .messages
+                .iter()
+                .any(|message| message.string_contents() == "Let me"),
+            "the interrupted response should be sent back to the model"
+        );
+
+        model.send_last_completion_stream_text_chunk("Done.");
+        model.end_last_completion_stream();
+        cx.run_until_parked();
+        acp_thread.read_with(cx, |thread, cx| {
+            assert_eq!(thread.status(), ThreadStatus::Idle);
+            assert!(thread.to_markdown(cx).contains("Done."));
+        });
+        thread.read_with(cx, |thread, _| assert!(thread.is_turn_complete()));
+    }
+
     #[gpui::test]
     async fn test_close_session_saves_thread(cx: &mut TestAppContext) {
         init_test(cx);
@@ -3071,6 +3189,76 @@ mod internal_tests {
         assert_eq!(*title_updated_count.borrow(), 2);
     }
 
+    #[gpui::test]
+    async fn test_completion_retries_are_reported_to_the_acp_thread(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/", json!({ "a": {} })).await;
+        let project = Project::test(fs.clone(), [path!("/a").as_ref()], cx).await;
+        let thread_store = cx.new(|cx| ThreadStore::new(cx));
+        let agent = cx.update(|cx| {
+            NativeAgent::new(thread_store.clone(), Templates::new(), None, fs.clone(), cx)
+        });
+        let connection = Rc::new(NativeAgentConnection(agent.clone()));
+
+        let acp_thread = cx
+            .update(|cx| {
+                connection
+                    .clone()
+                    .new_session(project.clone(), PathList::new(&[Path::new("")]), cx)
+            })
+            .await
+            .unwrap();
+        let session_id = acp_thread.read_with(cx, |thread, _| thread.session_id().clone());
+        let thread = agent.read_with(cx, |agent, _| {
+            agent.sessions.get(&session_id).unwrap().thread.clone()
+        });
+        let model = Arc::new(FakeLanguageModel::default());
+        thread.update(cx, |thread, cx| thread.set_model(model.clone(), cx));
+
+        let retries = Rc::new(std::cell::RefCell::new(Vec::new()));
+        cx.update(|cx| {
+            let retries = retries.clone();
+            cx.subscribe(&acp_thread, move |_, event: &AcpThreadEvent, _| {
+                if let AcpThreadEvent::Retry(status) = event {
+                    retries.borrow_mut().push(status.clone());
+                }
+            })
+            .detach();
+        });
+
+        let send = acp_thread.update(cx, |thread, cx| thread.send(vec!["Hello".into()], cx));
+        let send = cx.foreground_executor().spawn(send);
+        cx.run_until_parked();
+        model.send_last_completion_stream_error(LanguageModelCompletionError::ServerOverloaded {
+            provider: LanguageModelProviderName::new("Anthropic"),
+            retry_after: Some(Duration::from_secs(3)),
+        });
+        model.end_last_completion_stream();
+        cx.run_until_parked();
+
+        {
+            let retries = retries.borrow();
+            assert_eq!(retries.len(), 1);
+            assert_eq!(retries[0].attempt, 1);
+            assert_eq!(retries[0].max_attempts, 4);
+            assert_eq!(retries[0].duration, Duration::from_secs(3));
+        }
+        acp_thread.read_with(cx, |thread, _| {
+            assert_eq!(thread.status(), ThreadStatus::Generating);
+        });
+
+        cx.executor().advance_clock(Duration::from_secs(3));
+        cx.run_until_parked();
+        model.send_last_completion_stream_text_chunk("Hi!");
+        model.end_last_completion_stream();
+        send.await.unwrap();
+        assert_eq!(retries.borrow().len(), 1);
+        acp_thread.read_with(cx, |thread, cx| {
+            assert!(thread.to_markdown(cx).contains("Hi!"));
+        });
+    }
+
     fn thread_entries(
         thread_store: &Entity<ThreadStore>,
         cx: &mut TestAppContext,
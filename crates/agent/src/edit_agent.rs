@@ -5,23 +5,27 @@ mod evals;
 pub mod reindent;
 pub mod streaming_fuzzy_matcher;
 
-use crate::{Template, Templates};
+use crate::{
+    Template, Templates,
+    retry::{RetryStrategy, retry_with_backoff},
+};
 use action_log::ActionLog;
 use anyhow::Result;
 use create_file_parser::{CreateFileParser, CreateFileParserEvent};
 pub use edit_parser::EditFormat;
 use edit_parser::{EditParser, EditParserEvent, EditParserMetrics};
 use futures::{
-    Stream, StreamExt,
+    FutureExt, Stream, StreamExt,
     channel::mpsc::{self, UnboundedReceiver},
     pin_mut,
     stream::BoxStream,
 };
-use gpui::{AppContext, AsyncApp, Entity, Task};
+use gpui::{App, AppContext, AsyncApp, Entity, Task};
 use language::{Anchor, Buffer, BufferSnapshot, LineIndent, Point, TextBufferSnapshot};
 use language_model::{
-    CompletionIntent, LanguageModel, LanguageModelCompletionError, LanguageModelRequest,
-    LanguageModelRequestMessage, LanguageModelToolChoice, MessageContent, Role,
+    CompletionIntent, LanguageModel, LanguageModelCompletionError, LanguageModelImage,
+    LanguageModelName, LanguageModelRegistry, LanguageModelRequest, LanguageModelRequestMessage,
+    LanguageModelToolChoice, MessageContent, Role,
 };
 use project::{AgentLocation, Project};
 use reindent::{IndentDelta, Reindenter};
@@ -29,7 +33,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::{mem, ops::Range, pin::Pin, sync::Arc, task::Poll};
 use streaming_diff::{CharOperation, StreamingDiff};
-use streaming_fuzzy_matcher::StreamingFuzzyMatcher;
+use streaming_fuzzy_matcher::{MatchCosts, StreamingFuzzyMatcher};
 
 #[derive(Serialize)]
 struct CreateFilePromptTemplate {
@@ -45,6 +49,7 @@ impl Template for CreateFilePromptTemplate {
 struct EditFileXmlPromptTemplate {
     path: Option<String>,
     edit_description: String,
+    context_lines: u32,
 }
 
 impl Template for EditFileXmlPromptTemplate {
@@ -55,6 +60,7 @@ impl Template for EditFileXmlPromptTemplate {
 struct EditFileDiffFencedPromptTemplate {
     path: Option<String>,
     edit_description: String,
+    context_lines: u32,
 }
 
 impl Template for EditFileDiffFencedPromptTemplate {
@@ -66,7 +72,14 @@ pub enum EditAgentOutputEvent {
     ResolvingEditRange(Range<Anchor>),
     UnresolvedEditRange,
     AmbiguousEditRange(Vec<Range<usize>>),
-    Edited(Range<Anchor>),
+    /// The edit to these lines was rejected because `old_text` didn't include
+    /// the number of unchanged lines required by [`EditAgent::with_context_lines`].
+    InsufficientContext(Range<usize>),
+    Edited {
+        range: Range<Anchor>,
+        /// The edited range in the buffer as it was before the edit was applied.
+        original_range: Range<Point>,
+    },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
@@ -75,6 +88,28 @@ pub struct EditAgentOutput {
     pub parser_metrics: EditParserMetrics,
 }
 
+/// Returned when there is no language model available to drive an [`EditAgent`].
+#[derive(Debug, thiserror::Error)]
+#[error("No language model is available. Configure an AI provider first.")]
+pub struct NoModelAvailableError;
+
+/// Returned when images are attached to an [`EditAgent`]'s instructions but its
+/// model can't take images as input.
+#[derive(Debug, thiserror::Error)]
+#[error("{} doesn't support images. Remove the attached images or pick a model that does.", .0.0)]
+pub struct ImagesNotSupportedError(pub LanguageModelName);
+
+/// Returned when an [`EditAgent`] is asked to edit a buffer larger than its
+/// configured maximum, since the model would need to be shown all of it.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "The file is too large to edit ({size} bytes, the limit is {max_size} bytes). Edit a smaller file or split it up first."
+)]
+pub struct FileTooLargeError {
+    pub size: usize,
+    pub max_size: usize,
+}
+
 #[derive(Clone)]
 pub struct EditAgent {
     model: Arc<dyn LanguageModel>,
@@ -84,6 +119,14 @@ pub struct EditAgent {
     edit_format: EditFormat,
     thinking_allowed: bool,
     update_agent_location: bool,
+    context_lines: u32,
+    preserve_whitespace: bool,
+    match_costs: MatchCosts,
+    detect_whitespace_significance: bool,
+    images: Vec<LanguageModelImage>,
+    max_file_size: Option<usize>,
+    ordered_edits: bool,
+    retry_completions: bool,
 }
 
 impl EditAgent {
@@ -104,9 +147,131 @@ impl EditAgent {
             edit_format,
             thinking_allowed: allow_thinking,
             update_agent_location,
+            context_lines: 0,
+            preserve_whitespace: false,
+            match_costs: MatchCosts::default(),
+            detect_whitespace_significance: false,
+            images: Vec::new(),
+            max_file_size: None,
+            ordered_edits: false,
+            retry_completions: true,
         }
     }
 
+    /// Like [`Self::new`], but fails with [`NoModelAvailableError`] instead of
+    /// requiring a model, and picks the edit format that suits the model.
+    pub fn try_new(
+        model: Option<Arc<dyn LanguageModel>>,
+        project: Entity<Project>,
+        action_log: Entity<ActionLog>,
+        templates: Arc<Templates>,
+        allow_thinking: bool,
+        update_agent_location: bool,
+    ) -> Result<Self, NoModelAvailableError> {
+        let model = model.ok_or(NoModelAvailableError)?;
+        let edit_format = EditFormat::from_model(model.clone()).unwrap_or(EditFormat::XmlTags);
+        Ok(Self::new(
+            model,
+            project,
+            action_log,
+            templates,
+            edit_format,
+            allow_thinking,
+            update_agent_location,
+        ))
+    }
+
+    /// Creates an agent backed by the registry's default model, as long as its
+    /// provider is authenticated.
+    pub fn for_default_model(
+        project: Entity<Project>,
+        action_log: Entity<ActionLog>,
+        templates: Arc<Templates>,
+        allow_thinking: bool,
+        update_agent_location: bool,
+        cx: &App,
+    ) -> Result<Self, NoModelAvailableError> {
+        let model = LanguageModelRegistry::read_global(cx)
+            .default_model()
+            .filter(|configured| configured.provider.is_authenticated(cx))
+            .map(|configured| configured.model);
+        Self::try_new(
+            model,
+            project,
+            action_log,
+            templates,
+            allow_thinking,
+            update_agent_location,
+        )
+    }
+
+    /// Asks the model to reproduce `context_lines` unchanged lines around each
+    /// change, which makes edits in files with repetitive structure resolve to
+    /// a unique location at the cost of a longer response.
+    ///
+    /// Edits with fewer unchanged lines than requested are not applied, and are
+    /// reported with [`EditAgentOutputEvent::InsufficientContext`].
+    pub fn with_context_lines(mut self, context_lines: u32) -> Self {
+        self.context_lines = context_lines;
+        self
+    }
+
+    /// Overrides the weights used to locate the text being replaced.
+    pub fn with_match_costs(mut self, match_costs: MatchCosts) -> Self {
+        self.match_costs = match_costs;
+        self
+    }
+
+    /// Stops indentation from being ignored when locating the text being
+    /// replaced in files whose language treats indentation as significant,
+    /// as detected from the file's extension.
+    pub fn with_whitespace_significance_detection(mut self, enabled: bool) -> Self {
+        self.detect_whitespace_significance = enabled;
+        self
+    }
+
+    /// Keeps edits from changing whether the buffer ends with a newline and
+    /// from stripping trailing whitespace off lines the model reproduced,
+    /// both of which models tend to do and which only add noise to the diff.
+    ///
+    /// Off by default, since it also keeps the model from making those changes
+    /// on purpose, e.g. when asked to remove trailing whitespace.
+    pub fn with_whitespace_preservation(mut self, preserve_whitespace: bool) -> Self {
+        self.preserve_whitespace = preserve_whitespace;
+        self
+    }
+
+    /// Attaches images to the instructions, e.g. a mockup for the model to match.
+    /// Requests fail with [`ImagesNotSupportedError`] if the model can't take images.
+    pub fn with_images(mut self, images: Vec<LanguageModelImage>) -> Self {
+        self.images = images;
+        self
+    }
+
+    /// Makes [`Self::edit`] fail with [`FileTooLargeError`] instead of sending a
+    /// request when the buffer is larger than `max_file_size` bytes.
+    pub fn with_max_file_size(mut self, max_file_size: usize) -> Self {
+        self.max_file_size = Some(max_file_size);
+        self
+    }
+
+    /// Holds back [`EditAgentOutputEvent::Edited`] events until the model is
+    /// done and then emits them sorted by where they are in the buffer, for
+    /// callers that would rather present edits top to bottom than as they
+    /// stream in. Edits are still applied to the buffer as they arrive.
+    pub fn with_ordered_edits(mut self, ordered_edits: bool) -> Self {
+        self.ordered_edits = ordered_edits;
+        self
+    }
+
+    /// Controls whether failed completion requests are retried with backoff.
+    /// On by default; callers turn it off for models the user's plan doesn't
+    /// allow retrying, matching the thread's own completion requests.
+    pub fn with_completion_retries(mut self, retry_completions: bool) -> Self {
+        self.retry_completions = retry_completions;
+        self
+    }
+
     pub fn overwrite(
         &self,
         buffer: Entity<Buffer>,
@@ -129,7 +294,7 @@ impl EditAgent {
             }
             .render(&this.templates)?;
             let new_chunks = this
-                .request(conversation, CompletionIntent::CreateFile, prompt, cx)
+                .request(conversation, CompletionIntent::CreateFile, prompt, None, cx)
                 .await?;
 
             let (output, mut inner_events) = this.overwrite_with_chunks(buffer, new_chunks, cx);
@@ -170,8 +335,9 @@ impl EditAgent {
         output_events_tx: mpsc::UnboundedSender<EditAgentOutputEvent>,
         cx: &mut AsyncApp,
     ) -> Result<()> {
-        let buffer_id = cx.update(|cx| {
+        let (buffer_id, original_max_point) = cx.update(|cx| {
             let buffer_id = buffer.read(cx).remote_id();
+            let original_max_point = buffer.read(cx).max_point();
             if self.update_agent_location {
                 self.project.update(cx, |project, cx| {
                     project.set_agent_location(
@@ -183,14 +349,15 @@ impl EditAgent {
                     )
                 });
             }
-            buffer_id
+            (buffer_id, original_max_point)
         });
 
         let send_edit_event = || {
             output_events_tx
-                .unbounded_send(EditAgentOutputEvent::Edited(
-                    Anchor::min_max_range_for_buffer(buffer_id),
-                ))
+                .unbounded_send(EditAgentOutputEvent::Edited {
+                    range: Anchor::min_max_range_for_buffer(buffer_id),
+                    original_range: Point::zero()..original_max_point,
+                })
                 .ok()
         };
         let set_agent_location = |cx: &mut _| {
@@ -249,36 +416,121 @@ impl EditAgent {
     ) -> (
         Task<Result<EditAgentOutput>>,
         mpsc::UnboundedReceiver<EditAgentOutputEvent>,
+    ) {
+        self.edit_with_cancellation(
+            buffer,
+            edit_description,
+            conversation,
+            watch::Receiver::constant(false),
+            cx,
+        )
+    }
+
+    /// Like [`Self::edit`], but stops as soon as `cancellation_rx` reports `true`.
+    ///
+    /// Cancelling drops the model's response stream, which aborts the request,
+    /// and the returned task resolves to an error. Edits that were already
+    /// applied stay in the buffer and in the action log. An edit that was still
+    /// streaming leaves the rest of its old text untouched instead of deleting it.
+    pub fn edit_with_cancellation(
+        &self,
+        buffer: Entity<Buffer>,
+        edit_description: String,
+        conversation: &LanguageModelRequest,
+        cancellation_rx: watch::Receiver<bool>,
+        cx: &mut AsyncApp,
+    ) -> (
+        Task<Result<EditAgentOutput>>,
+        mpsc::UnboundedReceiver<EditAgentOutputEvent>,
     ) {
         let this = self.clone();
         let (events_tx, events_rx) = mpsc::unbounded();
         let conversation = conversation.clone();
-        let edit_format = self.edit_format;
         let output = cx.spawn(async move |cx| {
-            let snapshot = buffer.read_with(cx, |buffer, _| buffer.snapshot());
-            let path = cx.update(|cx| snapshot.resolve_file_path(true, cx));
-            let prompt = match edit_format {
-                EditFormat::XmlTags => EditFileXmlPromptTemplate {
-                    path,
-                    edit_description,
-                }
-                .render(&this.templates)?,
-                EditFormat::DiffFenced => EditFileDiffFencedPromptTemplate {
-                    path,
-                    edit_description,
-                }
-                .render(&this.templates)?,
-            };
-
+            this.check_file_size(&buffer, cx)?;
+            let prompt = this.edit_prompt(&buffer, edit_description, cx)?;
             let edit_chunks = this
-                .request(conversation, CompletionIntent::EditFile, prompt, cx)
+                .request(
+                    conversation,
+                    CompletionIntent::EditFile,
+                    prompt,
+                    Some(cancellation_rx.clone()),
+                    cx,
+                )
                 .await?;
+            let edit_chunks = Self::cancellable_chunks(edit_chunks, cancellation_rx);
             this.apply_edit_chunks(buffer, edit_chunks, events_tx, cx)
                 .await
         });
         (output, events_rx)
     }
 
+    /// Estimates how many tokens `edit` would send to the model for this
+    /// buffer, so callers can warn before a file is too large to fit.
+    ///
+    /// The buffer's contents are counted alongside the rendered prompt,
+    /// since the model needs to have seen them to produce edits.
+    pub fn estimate_request_tokens(
+        &self,
+        buffer: Entity<Buffer>,
+        edit_description: String,
+        conversation: &LanguageModelRequest,
+        cx: &mut AsyncApp,
+    ) -> Task<Result<u64>> {
+        let this = self.clone();
+        let conversation = conversation.clone();
+        cx.spawn(async move |cx| {
+            let prompt = this.edit_prompt(&buffer, edit_description, cx)?;
+            let text = buffer.read_with(cx, |buffer, _| buffer.text());
+            let mut request = this.build_request(conversation, CompletionIntent::EditFile, prompt);
+            request.messages.insert(
+                request.messages.len() - 1,
+                LanguageModelRequestMessage {
+                    role: Role::User,
+                    content: vec![MessageContent::Text(text)],
+                    cache: false,
+                    reasoning_details: None,
+                },
+            );
+            cx.update(|cx| this.model.count_tokens(request, cx)).await
+        })
+    }
+
+    fn check_file_size(&self, buffer: &Entity<Buffer>, cx: &mut AsyncApp) -> Result<()> {
+        let Some(max_size) = self.max_file_size else {
+            return Ok(());
+        };
+        let size = buffer.read_with(cx, |buffer, _| buffer.len());
+        if size > max_size {
+            return Err(FileTooLargeError { size, max_size }.into());
+        }
+        Ok(())
+    }
+
+    fn edit_prompt(
+        &self,
+        buffer: &Entity<Buffer>,
+        edit_description: String,
+        cx: &mut AsyncApp,
+    ) -> Result<String> {
+        let snapshot = buffer.read_with(cx, |buffer, _| buffer.snapshot());
+        let path = cx.update(|cx| snapshot.resolve_file_path(true, cx));
+        match self.edit_format {
+            EditFormat::XmlTags => EditFileXmlPromptTemplate {
+                path,
+                edit_description,
+                context_lines: self.context_lines,
+            }
+            .render(&self.templates),
+            EditFormat::DiffFenced => EditFileDiffFencedPromptTemplate {
+                path,
+                edit_description,
+                context_lines: self.context_lines,
+            }
+            .render(&self.templates),
+        }
+    }
+
     async fn apply_edit_chunks(
         &self,
         buffer: Entity<Buffer>,
@@ -291,130 +543,275 @@ impl EditAgent {
 
         let (output, edit_events) = Self::parse_edit_chunks(edit_chunks, self.edit_format, cx);
         let mut edit_events = edit_events.peekable();
-        while let Some(edit_event) = Pin::new(&mut edit_events).peek().await {
-            // Skip events until we're at the start of a new edit.
-            let Ok(EditParserEvent::OldTextChunk { .. }) = edit_event else {
-                edit_events.next().await.unwrap()?;
-                continue;
-            };
-
-            let snapshot = buffer.read_with(cx, |buffer, _| buffer.snapshot());
+        let mut held_edits = Vec::new();
+        // Held edits are reported however applying ends, since the ones that
+        // were already applied are in the buffer even if a later one failed.
+        let result = async {
+            while let Some(edit_event) = Pin::new(&mut edit_events).peek().await {
+                // Skip events until we're at the start of a new edit.
+                let Ok(EditParserEvent::OldTextChunk { .. }) = edit_event else {
+                    edit_events.next().await.unwrap()?;
+                    continue;
+                };
 
-            // Resolve the old text in the background, updating the agent
-            // location as we keep refining which range it corresponds to.
-            let (resolve_old_text, mut old_range) =
-                Self::resolve_old_text(snapshot.text.clone(), edit_events, cx);
-            while let Ok(old_range) = old_range.recv().await {
-                if let Some(old_range) = old_range {
-                    let old_range = snapshot.anchor_before(old_range.start)
-                        ..snapshot.anchor_before(old_range.end);
-                    if self.update_agent_location {
-                        self.project.update(cx, |project, cx| {
-                            project.set_agent_location(
-                                Some(AgentLocation {
-                                    buffer: buffer.downgrade(),
-                                    position: old_range.end,
-                                }),
-                                cx,
-                            );
-                        });
+                let snapshot = buffer.read_with(cx, |buffer, _| buffer.snapshot());
+
+                // Resolve the old text in the background, updating the agent
+                // location as we keep refining which range it corresponds to.
+                let match_costs = self.match_costs_for(&snapshot);
+                let (resolve_old_text, mut old_range) =
+                    Self::resolve_old_text(snapshot.text.clone(), match_costs, edit_events, cx);
+                while let Ok(old_range) = old_range.recv().await {
+                    if let Some(old_range) = old_range {
+                        let old_range = snapshot.anchor_before(old_range.start)
+                            ..snapshot.anchor_before(old_range.end);
+                        if self.update_agent_location {
+                            self.project.update(cx, |project, cx| {
+                                project.set_agent_location(
+                                    Some(AgentLocation {
+                                        buffer: buffer.downgrade(),
+                                        position: old_range.end,
+                                    }),
+                                    cx,
+                                );
+                            });
+                        }
+                        output_events
+                            .unbounded_send(EditAgentOutputEvent::ResolvingEditRange(old_range))
+                            .ok();
                     }
-                    output_events
-                        .unbounded_send(EditAgentOutputEvent::ResolvingEditRange(old_range))
-                        .ok();
                 }
-            }
 
-            let (edit_events_, mut resolved_old_text) = resolve_old_text.await?;
-            edit_events = edit_events_;
-
-            // If we can't resolve the old text, restart the loop waiting for a
-            // new edit (or for the stream to end).
-            let resolved_old_text = match resolved_old_text.len() {
-                1 => resolved_old_text.pop().unwrap(),
-                0 => {
-                    output_events
-                        .unbounded_send(EditAgentOutputEvent::UnresolvedEditRange)
-                        .ok();
-                    continue;
-                }
-                _ => {
-                    let ranges = resolved_old_text
-                        .into_iter()
-                        .map(|text| {
-                            let start_line =
-                                (snapshot.offset_to_point(text.range.start).row + 1) as usize;
-                            let end_line =
-                                (snapshot.offset_to_point(text.range.end).row + 1) as usize;
-                            start_line..end_line
-                        })
-                        .collect();
-                    output_events
-                        .unbounded_send(EditAgentOutputEvent::AmbiguousEditRange(ranges))
-                        .ok();
-                    continue;
-                }
-            };
+                let (edit_events_, mut resolved_old_text) = resolve_old_text.await?;
+                edit_events = edit_events_;
+
+                // If we can't resolve the old text, restart the loop waiting for a
+                // new edit (or for the stream to end).
+                let resolved_old_text = match resolved_old_text.len() {
+                    1 => resolved_old_text.pop().unwrap(),
+                    0 => {
+                        output_events
+                            .unbounded_send(EditAgentOutputEvent::UnresolvedEditRange)
+                            .ok();
+                        continue;
+                    }
+                    _ => {
+                        let ranges = resolved_old_text
+                            .into_iter()
+                            .map(|text| {
+                                let start_line =
+                                    (snapshot.offset_to_point(text.range.start).row + 1) as usize;
+                                let end_line =
+                                    (snapshot.offset_to_point(text.range.end).row + 1) as usize;
+                                start_line..end_line
+                            })
+                            .collect();
+                        output_events
+                            .unbounded_send(EditAgentOutputEvent::AmbiguousEditRange(ranges))
+                            .ok();
+                        continue;
+                    }
+                };
 
-            // Compute edits in the background and apply them as they become
-            // available.
-            let (compute_edits, edits) =
-                Self::compute_edits(snapshot, resolved_old_text, edit_events, cx);
-            let mut edits = edits.ready_chunks(32);
-            while let Some(edits) = edits.next().await {
-                if edits.is_empty() {
-                    continue;
-                }
+                // Compute edits in the background and apply them as they become
+                // available.
+                let original_snapshot = snapshot.text.clone();
+                let old_range = resolved_old_text.range.clone();
+                let (compute_edits, edits) = Self::compute_edits(
+                    snapshot,
+                    resolved_old_text,
+                    edit_events,
+                    self.preserve_whitespace,
+                    cx,
+                );
+                let edits = if self.context_lines > 0 {
+                    // Hold the edits back until the whole replacement is known, so
+                    // that an edit without enough context never reaches the buffer.
+                    let edits = edits.collect::<Vec<_>>().await;
+                    if !has_enough_context(
+                        &original_snapshot,
+                        &old_range,
+                        &edits,
+                        self.context_lines,
+                    ) {
+                        let start_line =
+                            (original_snapshot.offset_to_point(old_range.start).row + 1) as usize;
+                        let end_line =
+                            (original_snapshot.offset_to_point(old_range.end).row + 1) as usize;
+                        output_events
+                            .unbounded_send(EditAgentOutputEvent::InsufficientContext(
+                                start_line..end_line,
+                            ))
+                            .ok();
+                        edit_events = compute_edits.await?;
+                        continue;
+                    }
+                    futures::stream::iter(edits).boxed()
+                } else {
+                    edits.boxed()
+                };
+                let mut edits = edits.ready_chunks(32);
+                while let Some(edits) = edits.next().await {
+                    if edits.is_empty() {
+                        continue;
+                    }
 
-                // Edit the buffer and report edits to the action log as part of the
-                // same effect cycle, otherwise the edit will be reported as if the
-                // user made it.
-                let (min_edit_start, max_edit_end) = cx.update(|cx| {
-                    let (min_edit_start, max_edit_end) = buffer.update(cx, |buffer, cx| {
-                        buffer.edit(edits.iter().cloned(), None, cx);
-                        let max_edit_end = buffer
-                            .summaries_for_anchors::<Point, _>(
-                                edits.iter().map(|(range, _)| range.end),
-                            )
-                            .max()
-                            .unwrap();
-                        let min_edit_start = buffer
-                            .summaries_for_anchors::<Point, _>(
-                                edits.iter().map(|(range, _)| range.start),
-                            )
-                            .min()
-                            .unwrap();
-                        (
-                            buffer.anchor_after(min_edit_start),
-                            buffer.anchor_before(max_edit_end),
+                    let original_start = original_snapshot
+                        .summaries_for_anchors::<Point, _>(
+                            edits.iter().map(|(range, _)| range.start),
                         )
-                    });
-                    self.action_log
-                        .update(cx, |log, cx| log.buffer_edited(buffer.clone(), cx));
-                    if self.update_agent_location {
-                        self.project.update(cx, |project, cx| {
-                            project.set_agent_location(
-                                Some(AgentLocation {
-                                    buffer: buffer.downgrade(),
-                                    position: max_edit_end,
-                                }),
-                                cx,
-                            );
+                        .min()
+                        .unwrap();
+                    let original_end = original_snapshot
+                        .summaries_for_anchors::<Point, _>(edits.iter().map(|(range, _)| range.end))
+                        .max()
+                        .unwrap();
+
+                    // Edit the buffer and report edits to the action log as part of the
+                    // same effect cycle, otherwise the edit will be reported as if the
+                    // user made it.
+                    let (min_edit_start, max_edit_end) = cx.update(|cx| {
+                        let (min_edit_start, max_edit_end) = buffer.update(cx, |buffer, cx| {
+                            buffer.edit(edits.iter().cloned(), None, cx);
+                            let max_edit_end = buffer
+                                .summaries_for_anchors::<Point, _>(
+                                    edits.iter().map(|(range, _)| range.end),
+                                )
+                                .max()
+                                .unwrap();
+                            let min_edit_start = buffer
+                                .summaries_for_anchors::<Point, _>(
+                                    edits.iter().map(|(range, _)| range.start),
+                                )
+                                .min()
+                                .unwrap();
+                            (
+                                buffer.anchor_after(min_edit_start),
+                                buffer.anchor_before(max_edit_end),
+                            )
                         });
+                        self.action_log
+                            .update(cx, |log, cx| log.buffer_edited(buffer.clone(), cx));
+                        if self.update_agent_location {
+                            self.project.update(cx, |project, cx| {
+                                project.set_agent_location(
+                                    Some(AgentLocation {
+                                        buffer: buffer.downgrade(),
+                                        position: max_edit_end,
+                                    }),
+                                    cx,
+                                );
+                            });
+                        }
+                        (min_edit_start, max_edit_end)
+                    });
+                    let range = min_edit_start..max_edit_end;
+                    let original_range = original_start..original_end;
+                    if self.ordered_edits {
+                        held_edits.push((range, original_range));
+                    } else {
+                        output_events
+                            .unbounded_send(EditAgentOutputEvent::Edited {
+                                range,
+                                original_range,
+                            })
+                            .ok();
                     }
-                    (min_edit_start, max_edit_end)
-                });
+                }
+
+                edit_events = compute_edits.await?;
+                if self.preserve_whitespace {
+                    self.restore_final_newline(&buffer, &original_snapshot, cx);
+                }
+            }
+            anyhow::Ok(())
+        }
+        .await;
+
+        if !held_edits.is_empty() {
+            let snapshot = buffer.read_with(cx, |buffer, _| buffer.snapshot());
+            held_edits.sort_by(|(a, _), (b, _)| {
+                a.start
+                    .cmp(&b.start, &snapshot)
+                    .then_with(|| a.end.cmp(&b.end, &snapshot))
+            });
+            for (range, original_range) in held_edits {
                 output_events
-                    .unbounded_send(EditAgentOutputEvent::Edited(min_edit_start..max_edit_end))
+                    .unbounded_send(EditAgentOutputEvent::Edited {
+                        range,
+                        original_range,
+                    })
                     .ok();
             }
-
-            edit_events = compute_edits.await?;
         }
 
+        result?;
         output.await
     }
 
+    /// Adds or removes the buffer's final newline if an edit changed whether
+    /// the buffer ends with one.
+    fn restore_final_newline(
+        &self,
+        buffer: &Entity<Buffer>,
+        original_snapshot: &TextBufferSnapshot,
+        cx: &mut AsyncApp,
+    ) {
+        if original_snapshot.len() == 0 {
+            return;
+        }
+
+        let had_final_newline = original_snapshot
+            .reversed_chars_at(original_snapshot.len())
+            .next()
+            == Some('\n');
+        cx.update(|cx| {
+            let edited = buffer.update(cx, |buffer, cx| {
+                let len = buffer.len();
+                let has_final_newline = buffer.reversed_chars_at(len).next() == Some('\n');
+                if had_final_newline && !has_final_newline {
+                    buffer.edit([(len..len, "\n")], None, cx);
+                    true
+                } else if !had_final_newline && has_final_newline {
+                    buffer.edit([(len - 1..len, "")], None, cx);
+                    true
+                } else {
+                    false
+                }
+            });
+            if edited {
+                self.action_log
+                    .update(cx, |log, cx| log.buffer_edited(buffer.clone(), cx));
+            }
+        });
+    }
+
+    fn cancellable_chunks(
+        chunks: BoxStream<'static, Result<String, LanguageModelCompletionError>>,
+        mut cancellation_rx: watch::Receiver<bool>,
+    ) -> impl 'static + Send + Stream<Item = Result<String, LanguageModelCompletionError>> {
+        let mut chunks = Some(chunks);
+        let mut cancelled = Box::pin(async move {
+            while !*cancellation_rx.borrow() {
+                if cancellation_rx.changed().await.is_err() {
+                    futures::future::pending::<()>().await;
+                }
+            }
+        });
+        futures::stream::poll_fn(move |cx| {
+            let Some(stream) = chunks.as_mut() else {
+                return Poll::Ready(None);
+            };
+            if cancelled.poll_unpin(cx).is_ready() {
+                chunks = None;
+                return Poll::Ready(Some(Err(LanguageModelCompletionError::Other(
+                    anyhow::anyhow!("edit cancelled"),
+                ))));
+            }
+            stream.poll_next_unpin(cx)
+        })
+    }
+
     fn parse_edit_chunks(
         chunks: impl 'static + Send + Stream<Item = Result<String, LanguageModelCompletionError>>,
         edit_format: EditFormat,
@@ -488,8 +885,22 @@ impl EditAgent {
         (output, rx)
     }
 
+    fn match_costs_for(&self, snapshot: &BufferSnapshot) -> MatchCosts {
+        let is_whitespace_significant = self.detect_whitespace_significance
+            && snapshot
+                .file()
+                .and_then(|file| file.path().extension())
+                .is_some_and(MatchCosts::is_whitespace_significant_extension);
+        if is_whitespace_significant {
+            self.match_costs.whitespace_significant()
+        } else {
+            self.match_costs
+        }
+    }
+
     fn resolve_old_text<T>(
         snapshot: TextBufferSnapshot,
+        match_costs: MatchCosts,
         mut edit_events: T,
         cx: &mut AsyncApp,
     ) -> (
@@ -501,7 +912,7 @@ impl EditAgent {
     {
         let (mut old_range_tx, old_range_rx) = watch::channel(None);
         let task = cx.background_spawn(async move {
-            let mut matcher = StreamingFuzzyMatcher::new(snapshot);
+            let mut matcher = StreamingFuzzyMatcher::new(snapshot).with_costs(match_costs);
             while let Some(edit_event) = edit_events.next().await {
                 let EditParserEvent::OldTextChunk {
                     chunk,
@@ -553,6 +964,7 @@ impl EditAgent {
         snapshot: BufferSnapshot,
         resolved_old_text: ResolvedOldText,
         mut edit_events: T,
+        preserve_whitespace: bool,
         cx: &mut AsyncApp,
     ) -> (
         Task<Result<T>>,
@@ -592,6 +1004,13 @@ impl EditAgent {
                         }
                         CharOperation::Delete { bytes } => {
                             let edit_end = edit_start + bytes;
+                            if preserve_whitespace
+                                && is_trailing_whitespace(&snapshot, edit_start..edit_end)
+                            {
+                                edit_start = edit_end;
+                                continue;
+                            }
+
                             let edit_range =
                                 snapshot.anchor_after(edit_start)..snapshot.anchor_before(edit_end);
                             edit_start = edit_end;
@@ -644,11 +1063,40 @@ impl EditAgent {
 
     async fn request(
         &self,
-        mut conversation: LanguageModelRequest,
+        conversation: LanguageModelRequest,
         intent: CompletionIntent,
         prompt: String,
+        cancellation_rx: Option<watch::Receiver<bool>>,
         cx: &mut AsyncApp,
     ) -> Result<BoxStream<'static, Result<String, LanguageModelCompletionError>>> {
+        if !self.images.is_empty() && !self.model.supports_images() {
+            return Err(ImagesNotSupportedError(self.model.name()).into());
+        }
+        let request = self.build_request(conversation, intent, prompt);
+        let executor = cx.background_executor().clone();
+        let text_stream = retry_with_backoff(
+            &executor,
+            cancellation_rx,
+            |error| {
+                if self.retry_completions {
+                    RetryStrategy::for_completion_error(error)
+                } else {
+                    None
+                }
+            },
+            |_, _| {},
+            async || self.model.stream_completion_text(request.clone(), cx).await,
+        )
+        .await?;
+        Ok(text_stream.stream)
+    }
+
+    fn build_request(
+        &self,
+        mut conversation: LanguageModelRequest,
+        intent: CompletionIntent,
+        prompt: String,
+    ) -> LanguageModelRequest {
         let mut messages_iter = conversation.messages.iter_mut();
         if let Some(last_message) = messages_iter.next_back()
             && last_message.role == Role::Assistant
@@ -677,9 +1125,11 @@ impl EditAgent {
             }
         }
 
+        let mut content = vec![MessageContent::Text(prompt)];
+        content.extend(self.images.iter().cloned().map(MessageContent::Image));
         conversation.messages.push(LanguageModelRequestMessage {
             role: Role::User,
-            content: vec![MessageContent::Text(prompt)],
+            content,
             cache: false,
             reasoning_details: None,
         });
@@ -697,7 +1147,7 @@ impl EditAgent {
             tools = conversation.tools.clone();
         }
 
-        let request = LanguageModelRequest {
+        LanguageModelRequest {
             thread_id: conversation.thread_id,
             prompt_id: conversation.prompt_id,
             intent: Some(intent),
@@ -709,9 +1159,7 @@ impl EditAgent {
             thinking_allowed: self.thinking_allowed,
             thinking_effort: None,
             speed: None,
-        };
-
-        Ok(self.model.stream_completion_text(request, cx).await?.stream)
+        }
     }
 }
 
@@ -720,6 +1168,63 @@ struct ResolvedOldText {
     indent: LineIndent,
 }
 
+/// Returns whether `range` only contains spaces or tabs at the end of a line.
+fn is_trailing_whitespace(snapshot: &BufferSnapshot, range: Range<usize>) -> bool {
+    snapshot
+        .chars_at(range.end)
+        .next()
+        .is_none_or(|ch| ch == '\n')
+        && snapshot
+            .text_for_range(range)
+            .flat_map(str::chars)
+            .all(|ch| ch == ' ' || ch == '\t')
+}
+
+/// Returns whether `edits` leave at least `context_lines` unchanged lines of
+/// `old_range` on either side. Fewer lines are accepted next to the start or
+/// end of the buffer, where there is nothing more to include.
+fn has_enough_context(
+    snapshot: &TextBufferSnapshot,
+    old_range: &Range<usize>,
+    edits: &[(Range<Anchor>, Arc<str>)],
+    context_lines: u32,
+) -> bool {
+    let Some(first_edit_start) = snapshot
+        .summaries_for_anchors::<Point, _>(edits.iter().map(|(range, _)| range.start))
+        .min()
+    else {
+        return true;
+    };
+    let Some(last_edit_end) = snapshot
+        .summaries_for_anchors::<Point, _>(edits.iter().map(|(range, _)| range.end))
+        .max()
+    else {
+        return true;
+    };
+
+    // A range ending at the start of a line doesn't touch that line.
+    let last_row = |point: Point| {
+        if point.column == 0 {
+            point.row.saturating_sub(1)
+        } else {
+            point.row
+        }
+    };
+    let first_unchanged_row = if last_edit_end.column == 0 {
+        last_edit_end.row
+    } else {
+        last_edit_end.row + 1
+    };
+
+    let old_start_row = snapshot.offset_to_point(old_range.start).row;
+    let old_end_row = last_row(snapshot.offset_to_point(old_range.end));
+    let leading_lines = first_edit_start.row - old_start_row;
+    let trailing_lines = (old_end_row + 1).saturating_sub(first_unchanged_row);
+    let lines_after_edit = (last_row(snapshot.max_point()) + 1).saturating_sub(first_unchanged_row);
+    leading_lines >= context_lines.min(first_edit_start.row)
+        && trailing_lines >= context_lines.min(lines_after_edit)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -727,6 +1232,7 @@ mod tests {
     use futures::stream;
     use gpui::{AppContext, TestAppContext};
     use indoc::indoc;
+    use language::ToOffset as _;
     use language_model::fake_provider::FakeLanguageModel;
     use pretty_assertions::assert_matches;
     use project::{AgentLocation, Project};
@@ -872,6 +1378,109 @@ mod tests {
         );
     }
 
+    #[gpui::test(iterations = 100)]
+    async fn test_preserve_final_newline(cx: &mut TestAppContext, mut rng: StdRng) {
+        let agent = init_test(cx).await.with_whitespace_preservation(true);
+
+        // The model adds a final newline that the file didn't have.
+        let buffer = cx.new(|cx| Buffer::local("abc\ndef", cx));
+        let (apply, _events) = agent.edit(
+            buffer.clone(),
+            String::new(),
+            &LanguageModelRequest::default(),
+            &mut cx.to_async(),
+        );
+        cx.run_until_parked();
+        simulate_llm_output(
+            &agent,
+            "<old_text>def</old_text><new_text>DEF\n\n</new_text>",
+            &mut rng,
+            cx,
+        );
+        apply.await.unwrap();
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.snapshot().text()),
+            "abc\nDEF"
+        );
+
+        // The model drops the file's final newline.
+        let buffer = cx.new(|cx| Buffer::local("abc\ndef\n", cx));
+        let (apply, _events) = agent.edit(
+            buffer.clone(),
+            String::new(),
+            &LanguageModelRequest::default(),
+            &mut cx.to_async(),
+        );
+        cx.run_until_parked();
+        simulate_llm_output(
+            &agent,
+            "<old_text>def\n\n\n</old_text><new_text>DEF</new_text>",
+            &mut rng,
+            cx,
+        );
+        apply.await.unwrap();
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.snapshot().text()),
+            "abc\nDEF\n"
+        );
+    }
+
+    #[gpui::test(iterations = 100)]
+    async fn test_preserve_trailing_whitespace(cx: &mut TestAppContext, mut rng: StdRng) {
+        let agent = init_test(cx).await.with_whitespace_preservation(true);
+        let buffer = cx.new(|cx| Buffer::local("abc  \ndef\nghi", cx));
+        let (apply, _events) = agent.edit(
+            buffer.clone(),
+            String::new(),
+            &LanguageModelRequest::default(),
+            &mut cx.to_async(),
+        );
+        cx.run_until_parked();
+
+        simulate_llm_output(
+            &agent,
+            "<old_text>abc\ndef</old_text><new_text>abc\nDEF</new_text>",
+            &mut rng,
+            cx,
+        );
+        apply.await.unwrap();
+
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.snapshot().text()),
+            "abc  \nDEF\nghi"
+        );
+    }
+
+    #[gpui::test(iterations = 100)]
+    async fn test_whitespace_changes_apply_without_preservation(
+        cx: &mut TestAppContext,
+        mut rng: StdRng,
+    ) {
+        let agent = init_test(cx).await;
+        let buffer = cx.new(|cx| Buffer::local("abc  \ndef", cx));
+        let (apply, _events) = agent.edit(
+            buffer.clone(),
+            String::new(),
+            &LanguageModelRequest::default(),
+            &mut cx.to_async(),
+        );
+        cx.run_until_parked();
+
+        simulate_llm_output(
+            &agent,
+            // The parser drops one newline before the closing tag.
+            "<old_text>abc  \ndef</old_text><new_text>abc\ndef\n\n</new_text>",
+            &mut rng,
+            cx,
+        );
+        apply.await.unwrap();
+
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.snapshot().text()),
+            "abc\ndef\n"
+        );
+    }
+
     #[gpui::test(iterations = 100)]
     async fn test_old_text_hallucination(cx: &mut TestAppContext, mut rng: StdRng) {
         let agent = init_test(cx).await;
@@ -968,7 +1577,7 @@ mod tests {
         cx.run_until_parked();
         assert_matches!(
             drain_events(&mut events).as_slice(),
-            [EditAgentOutputEvent::Edited(_)]
+            [EditAgentOutputEvent::Edited { .. }]
         );
         assert_eq!(
             buffer.read_with(cx, |buffer, _| buffer.snapshot().text()),
@@ -1130,6 +1739,155 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_edit_events_report_original_range(cx: &mut TestAppContext) {
+        let agent = init_test(cx).await;
+        let model = agent.model.as_fake();
+        let buffer = cx.new(|cx| Buffer::local("abc\ndef\nghi\njkl", cx));
+        let (apply, mut events) = agent.edit(
+            buffer.clone(),
+            String::new(),
+            &LanguageModelRequest::default(),
+            &mut cx.to_async(),
+        );
+        cx.run_until_parked();
+
+        model.send_last_completion_stream_text_chunk("<old_text>def\nghi</old_text>");
+        model.send_last_completion_stream_text_chunk("<new_text>def\nGHI</new_text>");
+        model.end_last_completion_stream();
+        apply.await.unwrap();
+
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.snapshot().text()),
+            "abc\ndef\nGHI\njkl"
+        );
+        let original_ranges = drain_events(&mut events)
+            .into_iter()
+            .filter_map(|event| match event {
+                EditAgentOutputEvent::Edited { original_range, .. } => Some(original_range),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(!original_ranges.is_empty());
+        for original_range in original_ranges {
+            assert_eq!(original_range.start.row, 2);
+            assert_eq!(original_range.end.row, 2);
+        }
+    }
+
+    #[gpui::test]
+    async fn test_ordered_edits_are_emitted_top_to_bottom(cx: &mut TestAppContext) {
+        let agent = init_test(cx).await.with_ordered_edits(true);
+        let model = agent.model.as_fake();
+        let buffer = cx.new(|cx| Buffer::local("abc\ndef\nghi\njkl", cx));
+        let (apply, mut events) = agent.edit(
+            buffer.clone(),
+            String::new(),
+            &LanguageModelRequest::default(),
+            &mut cx.to_async(),
+        );
+        cx.run_until_parked();
+
+        model.send_last_completion_stream_text_chunk("<old_text>jkl</old_text>");
+        model.send_last_completion_stream_text_chunk("<new_text>JKL</new_text>");
+        model.send_last_completion_stream_text_chunk("<old_text>abc</old_text>");
+        model.send_last_completion_stream_text_chunk("<new_text>ABC</new_text>");
+        model.send_last_completion_stream_text_chunk("<old_text>ghi</old_text>");
+        model.send_last_completion_stream_text_chunk("<new_text>GHI</new_text>");
+        cx.run_until_parked();
+        assert!(
+            !drain_events(&mut events)
+                .iter()
+                .any(|event| matches!(event, EditAgentOutputEvent::Edited { .. })),
+            "edits should be held back until the stream ends"
+        );
+
+        model.end_last_completion_stream();
+        apply.await.unwrap();
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.snapshot().text()),
+            "ABC\ndef\nGHI\nJKL"
+        );
+
+        assert_eq!(
+            edited_texts(&buffer, &mut events, cx),
+            ["ABC", "GHI", "JKL"],
+            "edits should be reported top to bottom"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_ordered_edits_are_reported_when_the_edit_fails(cx: &mut TestAppContext) {
+        let agent = init_test(cx).await.with_ordered_edits(true);
+        let model = agent.model.as_fake();
+        let buffer = cx.new(|cx| Buffer::local("abc\ndef\nghi\njkl", cx));
+        let (mut cancellation_tx, cancellation_rx) = watch::channel(false);
+        let (apply, mut events) = agent.edit_with_cancellation(
+            buffer.clone(),
+            String::new(),
+            &LanguageModelRequest::default(),
+            cancellation_rx,
+            &mut cx.to_async(),
+        );
+        cx.run_until_parked();
+
+        model.send_last_completion_stream_text_chunk("<old_text>jkl</old_text>");
+        model.send_last_completion_stream_text_chunk("<new_text>JKL</new_text>");
+        model.send_last_completion_stream_text_chunk("<old_text>abc</old_text>");
+        model.send_last_completion_stream_text_chunk("<new_text>ABC</new_text>");
+        model.send_last_completion_stream_text_chunk("<old_text>ghi</old_text><new_text>G");
+        cx.run_until_parked();
+
+        cancellation_tx.send(true).unwrap();
+        cx.run_until_parked();
+        assert!(apply.await.is_err());
+        assert_eq!(
+            edited_texts(&buffer, &mut events, cx),
+            ["ABC", "JKL"],
+            "edits applied before the failure should still be reported in order"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_cancel_edit_mid_stream(cx: &mut TestAppContext) {
+        let agent = init_test(cx).await;
+        let model = agent.model.as_fake();
+        let buffer = cx.new(|cx| Buffer::local("abc\ndef\nghi", cx));
+        let (mut cancellation_tx, cancellation_rx) = watch::channel(false);
+        let (apply, mut events) = agent.edit_with_cancellation(
+            buffer.clone(),
+            String::new(),
+            &LanguageModelRequest::default(),
+            cancellation_rx,
+            &mut cx.to_async(),
+        );
+        cx.run_until_parked();
+
+        model.send_last_completion_stream_text_chunk("<old_text>abc</old_text>");
+        model.send_last_completion_stream_text_chunk("<new_text>ABC</new_text>");
+        model.send_last_completion_stream_text_chunk("<old_text>ghi</old_text><new_text>G");
+        cx.run_until_parked();
+        let text_before_cancel = buffer.read_with(cx, |buffer, _| buffer.snapshot().text());
+        assert!(text_before_cancel.starts_with("ABC\ndef\n"));
+        drain_events(&mut events);
+
+        cancellation_tx.send(true).unwrap();
+        cx.run_until_parked();
+        assert!(apply.await.is_err());
+        let text_after_cancel = buffer.read_with(cx, |buffer, _| buffer.snapshot().text());
+        assert_eq!(text_after_cancel, text_before_cancel);
+        assert!(
+            text_after_cancel.ends_with("ghi"),
+            "the old text of the interrupted edit should be left in place"
+        );
+        assert!(
+            !drain_events(&mut events)
+                .iter()
+                .any(|event| matches!(event, EditAgentOutputEvent::Edited { .. })),
+            "no edits should be produced after cancellation"
+        );
+    }
+
     #[gpui::test]
     async fn test_overwrite_events(cx: &mut TestAppContext) {
         let agent = init_test(cx).await;
@@ -1204,7 +1962,7 @@ mod tests {
         cx.run_until_parked();
         assert_matches!(
             drain_events(&mut events).as_slice(),
-            [EditAgentOutputEvent::Edited(_)],
+            [EditAgentOutputEvent::Edited { .. }],
         );
         assert_eq!(
             buffer.read_with(cx, |buffer, _| buffer.snapshot().text()),
@@ -1404,6 +2162,27 @@ mod tests {
         )
     }
 
+    #[gpui::test]
+    async fn test_no_model_available(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            settings::init(cx);
+            language_model::init(cx);
+        });
+
+        let project = Project::test(FakeFs::new(cx.executor()), [], cx).await;
+        let action_log = cx.new(|_| ActionLog::new(project.clone()));
+        let result = cx.update(|cx| {
+            EditAgent::for_default_model(project, action_log, Templates::new(), true, true, cx)
+        });
+        let Err(error) = result else {
+            panic!("expected construction to fail without a model");
+        };
+        assert_eq!(
+            error.to_string(),
+            "No language model is available. Configure an AI provider first."
+        );
+    }
+
     #[gpui::test(iterations = 10)]
     async fn test_non_unique_text_error(cx: &mut TestAppContext, mut rng: StdRng) {
         let agent = init_test(cx).await;
@@ -1476,6 +2255,179 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_context_lines_disambiguate_duplicate_blocks(cx: &mut TestAppContext) {
+        let text = indoc! {"
+            fn first() {
+                let x = 1;
+                x
+            }
+
+            fn second() {
+                let x = 1;
+                x
+            }
+        "};
+
+        let agent = init_test(cx).await;
+        let buffer = cx.new(|cx| Buffer::local(text, cx));
+        let (apply, mut events) = agent.edit(
+            buffer.clone(),
+            String::new(),
+            &LanguageModelRequest::default(),
+            &mut cx.to_async(),
+        );
+        cx.run_until_parked();
+        let model = agent.model.as_fake();
+        let prompt = model.pending_completions()[0]
+            .messages
+            .last()
+            .unwrap()
+            .string_contents();
+        assert!(!prompt.contains("unchanged lines"));
+        model.send_last_completion_stream_text_chunk(indoc! {"
+            <old_text>
+                let x = 1;
+            </old_text>
+            <new_text>
+                let x = 2;
+            </new_text>
+        "});
+        model.end_last_completion_stream();
+        apply.await.unwrap();
+        assert_eq!(buffer.read_with(cx, |buffer, _| buffer.text()), text);
+        assert!(
+            drain_events(&mut events)
+                .iter()
+                .any(|event| matches!(event, EditAgentOutputEvent::AmbiguousEditRange(_)))
+        );
+
+        let agent = init_test(cx).await.with_context_lines(3);
+        let buffer = cx.new(|cx| Buffer::local(text, cx));
+        let (apply, _events) = agent.edit(
+            buffer.clone(),
+            String::new(),
+            &LanguageModelRequest::default(),
+            &mut cx.to_async(),
+        );
+        cx.run_until_parked();
+        let model = agent.model.as_fake();
+        let prompt = model.pending_completions()[0]
+            .messages
+            .last()
+            .unwrap()
+            .string_contents();
+        assert!(prompt.contains("Include exactly 3 unchanged lines"));
+        model.send_last_completion_stream_text_chunk(indoc! {"
+            <old_text>
+            }
+
+            fn second() {
+                let x = 1;
+                x
+            }
+            </old_text>
+            <new_text>
+            }
+
+            fn second() {
+                let x = 2;
+                x
+            }
+            </new_text>
+        "});
+        model.end_last_completion_stream();
+        apply.await.unwrap();
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.text()),
+            indoc! {"
+                fn first() {
+                    let x = 1;
+                    x
+                }
+
+                fn second() {
+                    let x = 2;
+                    x
+                }
+            "}
+        );
+    }
+
+    #[gpui::test]
+    async fn test_edits_without_enough_context_are_rejected(cx: &mut TestAppContext) {
+        let agent = init_test(cx).await.with_context_lines(1);
+        let buffer = cx.new(|cx| {
+            Buffer::local(
+                indoc! {"
+                    one
+                    two
+                    three
+                    four
+                    five
+                "},
+                cx,
+            )
+        });
+        let (apply, mut events) = agent.edit(
+            buffer.clone(),
+            String::new(),
+            &LanguageModelRequest::default(),
+            &mut cx.to_async(),
+        );
+        cx.run_until_parked();
+
+        let model = agent.model.as_fake();
+        model.send_last_completion_stream_text_chunk(indoc! {"
+            <old_text>
+            three
+            </old_text>
+            <new_text>
+            THREE
+            </new_text>
+            <old_text>
+            one
+            two
+            three
+            </old_text>
+            <new_text>
+            one
+            TWO
+            three
+            </new_text>
+            <old_text>
+            four
+            five
+            </old_text>
+            <new_text>
+            four
+            FIVE
+            </new_text>
+        "});
+        model.end_last_completion_stream();
+        apply.await.unwrap();
+
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.text()),
+            indoc! {"
+                one
+                TWO
+                three
+                four
+                FIVE
+            "},
+            "only the edit missing a line of context on each side should be skipped"
+        );
+        let rejected = drain_events(&mut events)
+            .into_iter()
+            .filter_map(|event| match event {
+                EditAgentOutputEvent::InsufficientContext(lines) => Some(lines),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(rejected, vec![3..3]);
+    }
+
     #[gpui::test]
     async fn test_thinking_allowed_forwarded_to_request(cx: &mut TestAppContext) {
         let agent = init_test_with_thinking(cx, false).await;
@@ -1515,6 +2467,135 @@ mod tests {
         agent.model.as_fake().end_last_completion_stream();
     }
 
+    #[gpui::test]
+    async fn test_oversized_buffers_are_not_sent_to_the_model(cx: &mut TestAppContext) {
+        let agent = init_test(cx).await.with_max_file_size(1024);
+        let buffer = cx.new(|cx| Buffer::local("abc\n".repeat(1024), cx));
+        let (apply, _events) = agent.edit(
+            buffer.clone(),
+            "Rename abc to xyz".into(),
+            &LanguageModelRequest::default(),
+            &mut cx.to_async(),
+        );
+        let error = apply.await.unwrap_err();
+        let Some(error) = error.downcast_ref::<FileTooLargeError>() else {
+            panic!("expected FileTooLargeError, got {error:?}");
+        };
+        assert_eq!((error.size, error.max_size), (4096, 1024));
+        assert!(agent.model.as_fake().pending_completions().is_empty());
+
+        // Buffers within the limit are edited as usual.
+        buffer.update(cx, |buffer, cx| buffer.set_text("abc\n", cx));
+        let (_apply, _events) = agent.edit(
+            buffer,
+            "Rename abc to xyz".into(),
+            &LanguageModelRequest::default(),
+            &mut cx.to_async(),
+        );
+        cx.run_until_parked();
+        assert_eq!(agent.model.as_fake().pending_completions().len(), 1);
+        agent.model.as_fake().end_last_completion_stream();
+    }
+
+    #[gpui::test]
+    async fn test_images_are_attached_to_instructions(cx: &mut TestAppContext) {
+        let image = LanguageModelImage {
+            source: "aW1hZ2U=".into(),
+            size: None,
+        };
+        let agent = init_test(cx).await.with_images(vec![image.clone()]);
+        agent.model.as_fake().set_supports_images(true);
+        let buffer = cx.new(|cx| Buffer::local("hello\n", cx));
+        let (_apply, _events) = agent.edit(
+            buffer,
+            "Make it match the mockup".into(),
+            &LanguageModelRequest::default(),
+            &mut cx.to_async(),
+        );
+        cx.run_until_parked();
+
+        let pending = agent.model.as_fake().pending_completions();
+        assert_eq!(pending.len(), 1);
+        let instructions = pending[0].messages.last().unwrap();
+        assert_eq!(instructions.role, Role::User);
+        assert!(matches!(instructions.content[0], MessageContent::Text(_)));
+        assert_eq!(instructions.content[1..], [MessageContent::Image(image)]);
+        agent.model.as_fake().end_last_completion_stream();
+
+        // Models that can't take images fail before sending anything.
+        agent.model.as_fake().set_supports_images(false);
+        let buffer = cx.new(|cx| Buffer::local("hello\n", cx));
+        let (apply, _events) = agent.edit(
+            buffer,
+            "Make it match the mockup".into(),
+            &LanguageModelRequest::default(),
+            &mut cx.to_async(),
+        );
+        let error = apply.await.unwrap_err();
+        assert!(error.downcast_ref::<ImagesNotSupportedError>().is_some());
+        assert_eq!(
+            error.to_string(),
+            "Fake doesn't support images. Remove the attached images or pick a model that does."
+        );
+        assert!(agent.model.as_fake().pending_completions().is_empty());
+    }
+
+    #[gpui::test]
+    async fn test_estimate_request_tokens(cx: &mut TestAppContext) {
+        let agent = init_test(cx).await;
+        let small_buffer = cx.new(|cx| Buffer::local("fn main() {}\n", cx));
+        let large_buffer = cx.new(|cx| Buffer::local("fn main() {}\n".repeat(1000), cx));
+
+        let small_estimate = agent
+            .estimate_request_tokens(
+                small_buffer,
+                "Rename main".into(),
+                &LanguageModelRequest::default(),
+                &mut cx.to_async(),
+            )
+            .await
+            .unwrap();
+        let large_estimate = agent
+            .estimate_request_tokens(
+                large_buffer.clone(),
+                "Rename main".into(),
+                &LanguageModelRequest::default(),
+                &mut cx.to_async(),
+            )
+            .await
+            .unwrap();
+        assert!(
+            large_estimate > small_estimate,
+            "expected {large_estimate} to exceed {small_estimate}"
+        );
+
+        // The fake model counts one token per four bytes, so the estimate
+        // should land close to the size of the file plus the prompt.
+        let file_tokens = large_buffer.read_with(cx, |buffer, _| buffer.len()) as u64 / 4;
+        assert!(large_estimate >= file_tokens);
+        assert!(large_estimate < file_tokens * 2);
+    }
+
+    /// Returns the current text of each range reported as edited, in the order
+    /// the edits were reported.
+    fn edited_texts(
+        buffer: &Entity<Buffer>,
+        events: &mut UnboundedReceiver<EditAgentOutputEvent>,
+        cx: &mut TestAppContext,
+    ) -> Vec<String> {
+        buffer.read_with(cx, |buffer, _| {
+            drain_events(events)
+                .into_iter()
+                .filter_map(|event| match event {
+                    EditAgentOutputEvent::Edited { range, .. } => {
+                        Some(buffer.text_for_range(range).collect())
+                    }
+                    _ => None,
+                })
+                .collect()
+        })
+    }
+
     fn drain_events(
         stream: &mut UnboundedReceiver<EditAgentOutputEvent>,
     ) -> Vec<EditAgentOutputEvent> {
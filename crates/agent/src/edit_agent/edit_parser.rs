@@ -24,6 +24,12 @@ const END_TAGS: [&str; 6] = [
     SONNET_PARAMETER_INVOKE_2,
     SONNET_PARAMETER_INVOKE_3,
 ];
+/// Tags that open a text block, paired with the tag that closes it. When these
+/// appear inside a block's text, they and their end tags are treated as content.
+const NESTABLE_TAGS: [(&str, &str); 2] = [
+    ("<old_text", OLD_TEXT_END_TAG),
+    ("<new_text", NEW_TEXT_END_TAG),
+];
 
 #[derive(Debug)]
 pub enum EditParserEvent {
@@ -111,6 +117,8 @@ pub trait EditFormatParser: Send + std::fmt::Debug {
 pub struct XmlEditParser {
     state: XmlParserState,
     buffer: String,
+    /// End tags expected for blocks opened literally inside the current text.
+    nested_end_tags: Vec<&'static str>,
     metrics: EditParserMetrics,
 }
 
@@ -126,6 +134,9 @@ enum XmlParserState {
 pub struct DiffFencedEditParser {
     state: DiffParserState,
     buffer: String,
+    /// SEARCH/REPLACE blocks opened literally inside the current text, and
+    /// whether each of them has reached its separator yet.
+    nested_blocks: Vec<bool>,
     metrics: EditParserMetrics,
 }
 
@@ -146,24 +157,61 @@ impl XmlEditParser {
         XmlEditParser {
             state: XmlParserState::Pending,
             buffer: String::new(),
+            nested_end_tags: Vec::new(),
             metrics: EditParserMetrics::default(),
         }
     }
 
-    fn find_end_tag(&self) -> Option<Range<usize>> {
-        let (tag, start_ix) = END_TAGS
-            .iter()
-            .flat_map(|tag| Some((tag, self.buffer.find(tag)?)))
-            .min_by_key(|(_, ix)| *ix)?;
-        Some(start_ix..start_ix + tag.len())
+    /// Finds the tag that ends the current text block, skipping over blocks
+    /// that are opened and closed inside the text itself (e.g. when editing a
+    /// document that contains example edits). A literal start tag that is never
+    /// closed doesn't swallow the real end tag: any end tag that no nested block
+    /// is waiting for ends the current text block. Also returns the end tags
+    /// still expected for nested blocks that are open at the end of the buffer.
+    fn find_end_tag(&self) -> (Option<Range<usize>>, Vec<&'static str>) {
+        let mut nested_end_tags = self.nested_end_tags.clone();
+        let mut offset = 0;
+        loop {
+            let rest = &self.buffer[offset..];
+            let start_tag = NESTABLE_TAGS
+                .iter()
+                .flat_map(|(start_tag, end_tag)| Some((rest.find(start_tag)?, start_tag, *end_tag)))
+                .min_by_key(|(ix, _, _)| *ix);
+            let end_tag = END_TAGS
+                .iter()
+                .flat_map(|tag| Some((rest.find(tag)?, *tag)))
+                .min_by_key(|(ix, _)| *ix);
+
+            match (start_tag, end_tag) {
+                (Some((start_ix, start_tag, expected_end_tag)), end_tag)
+                    if end_tag.is_none_or(|(end_ix, _)| start_ix < end_ix) =>
+                {
+                    nested_end_tags.push(expected_end_tag);
+                    offset += start_ix + start_tag.len();
+                }
+                (_, Some((end_ix, end_tag))) => {
+                    let end_ix = offset + end_ix;
+                    let Some(nested_ix) = nested_end_tags.iter().rposition(|tag| *tag == end_tag)
+                    else {
+                        return (Some(end_ix..end_ix + end_tag.len()), Vec::new());
+                    };
+                    // Blocks opened after the one this tag closes were never
+                    // closed themselves, so their start tags were literal text.
+                    nested_end_tags.truncate(nested_ix);
+                    offset = end_ix + end_tag.len();
+                }
+                _ => return (None, nested_end_tags),
+            }
+        }
     }
 
     fn ends_with_tag_prefix(&self) -> bool {
-        let mut end_prefixes = END_TAGS
+        let mut tag_prefixes = END_TAGS
             .iter()
+            .chain(NESTABLE_TAGS.iter().map(|(start_tag, _)| start_tag))
             .flat_map(|tag| (1..tag.len()).map(move |i| &tag[..i]))
             .chain(["\n"]);
-        end_prefixes.any(|prefix| self.buffer.ends_with(&prefix))
+        tag_prefixes.any(|prefix| self.buffer.ends_with(&prefix))
     }
 
     fn parse_line_hint(&self, tag: &str) -> Option<u32> {
@@ -212,7 +260,9 @@ impl EditFormatParser for XmlEditParser {
                     }
 
                     let line_hint = *line_hint;
-                    if let Some(tag_range) = self.find_end_tag() {
+                    let (end_tag_range, nested_end_tags) = self.find_end_tag();
+                    if let Some(tag_range) = end_tag_range {
+                        self.nested_end_tags.clear();
                         let mut chunk = self.buffer[..tag_range.start].to_string();
                         if chunk.ends_with('\n') {
                             chunk.pop();
@@ -232,6 +282,7 @@ impl EditFormatParser for XmlEditParser {
                         });
                     } else {
                         if !self.ends_with_tag_prefix() {
+                            self.nested_end_tags = nested_end_tags;
                             edit_events.push(EditParserEvent::OldTextChunk {
                                 chunk: mem::take(&mut self.buffer),
                                 done: false,
@@ -257,7 +308,9 @@ impl EditFormatParser for XmlEditParser {
                         *start = false;
                     }
 
-                    if let Some(tag_range) = self.find_end_tag() {
+                    let (end_tag_range, nested_end_tags) = self.find_end_tag();
+                    if let Some(tag_range) = end_tag_range {
+                        self.nested_end_tags.clear();
                         let mut chunk = self.buffer[..tag_range.start].to_string();
                         if chunk.ends_with('\n') {
                             chunk.pop();
@@ -273,6 +326,7 @@ impl EditFormatParser for XmlEditParser {
                         edit_events.push(EditParserEvent::NewTextChunk { chunk, done: true });
                     } else {
                         if !self.ends_with_tag_prefix() {
+                            self.nested_end_tags = nested_end_tags;
                             edit_events.push(EditParserEvent::NewTextChunk {
                                 chunk: mem::take(&mut self.buffer),
                                 done: false,
@@ -296,12 +350,51 @@ impl DiffFencedEditParser {
         DiffFencedEditParser {
             state: DiffParserState::Pending,
             buffer: String::new(),
+            nested_blocks: Vec::new(),
             metrics: EditParserMetrics::default(),
         }
     }
 
+    /// Finds `marker`, skipping over SEARCH/REPLACE blocks that appear inside
+    /// the text itself. A SEARCH marker that hasn't reached its separator when a
+    /// REPLACE marker shows up was literal text, so it doesn't swallow the real
+    /// REPLACE marker. Also returns the blocks still open at the end of the buffer.
+    fn find_marker(&self, marker: &str) -> (Option<usize>, Vec<bool>) {
+        let mut nested_blocks = self.nested_blocks.clone();
+        let mut offset = 0;
+        loop {
+            let rest = &self.buffer[offset..];
+            let Some((ix, found)) = [SEARCH_MARKER, SEPARATOR_MARKER, REPLACE_MARKER]
+                .into_iter()
+                .flat_map(|candidate| Some((rest.find(candidate)?, candidate)))
+                .min_by_key(|(ix, _)| *ix)
+            else {
+                return (None, nested_blocks);
+            };
+
+            if found == REPLACE_MARKER {
+                while nested_blocks.last() == Some(&false) {
+                    nested_blocks.pop();
+                }
+            }
+
+            if nested_blocks.is_empty() && found == marker {
+                return (Some(offset + ix), nested_blocks);
+            } else if found == SEARCH_MARKER {
+                nested_blocks.push(false);
+            } else if found == SEPARATOR_MARKER {
+                if let Some(separated) = nested_blocks.last_mut() {
+                    *separated = true;
+                }
+            } else {
+                nested_blocks.pop();
+            }
+            offset += ix + found.len();
+        }
+    }
+
     fn ends_with_diff_marker_prefix(&self) -> bool {
-        let diff_markers = [SEPARATOR_MARKER, REPLACE_MARKER];
+        let diff_markers = [SEARCH_MARKER, SEPARATOR_MARKER, REPLACE_MARKER];
         let mut diff_prefixes = diff_markers
             .iter()
             .flat_map(|marker| (1..marker.len()).map(move |i| &marker[..i]))
@@ -356,7 +449,8 @@ impl EditFormatParser for DiffFencedEditParser {
                     }
 
                     let line_hint = *line_hint;
-                    if let Some(separator_pos) = self.buffer.find(SEPARATOR_MARKER) {
+                    let (separator_pos, nested_blocks) = self.find_marker(SEPARATOR_MARKER);
+                    if let Some(separator_pos) = separator_pos {
                         let mut chunk = self.buffer[..separator_pos].to_string();
                         if chunk.ends_with('\n') {
                             chunk.pop();
@@ -365,6 +459,7 @@ impl EditFormatParser for DiffFencedEditParser {
                         let separator_end = separator_pos + SEPARATOR_MARKER.len();
                         if let Some(newline_pos) = self.buffer[separator_end..].find('\n') {
                             self.buffer.drain(..separator_end + newline_pos + 1);
+                            self.nested_blocks.clear();
                             self.state = DiffParserState::WithinReplace { start: true };
                             edit_events.push(EditParserEvent::OldTextChunk {
                                 chunk,
@@ -376,6 +471,7 @@ impl EditFormatParser for DiffFencedEditParser {
                         }
                     } else {
                         if !self.ends_with_diff_marker_prefix() {
+                            self.nested_blocks = nested_blocks;
                             edit_events.push(EditParserEvent::OldTextChunk {
                                 chunk: mem::take(&mut self.buffer),
                                 done: false,
@@ -393,7 +489,9 @@ impl EditFormatParser for DiffFencedEditParser {
                         *start = false;
                     }
 
-                    if let Some(replace_pos) = self.buffer.find(REPLACE_MARKER) {
+                    let (replace_pos, nested_blocks) = self.find_marker(REPLACE_MARKER);
+                    if let Some(replace_pos) = replace_pos {
+                        self.nested_blocks.clear();
                         let mut chunk = self.buffer[..replace_pos].to_string();
                         if chunk.ends_with('\n') {
                             chunk.pop();
@@ -410,6 +508,7 @@ impl EditFormatParser for DiffFencedEditParser {
                         edit_events.push(EditParserEvent::NewTextChunk { chunk, done: true });
                     } else {
                         if !self.ends_with_diff_marker_prefix() {
+                            self.nested_blocks = nested_blocks;
                             edit_events.push(EditParserEvent::NewTextChunk {
                                 chunk: mem::take(&mut self.buffer),
                                 done: false,
@@ -620,6 +719,101 @@ mod tests {
         );
     }
 
+    #[gpui::test(iterations = 1000)]
+    fn test_xml_new_text_with_literal_edit_tags(mut rng: StdRng) {
+        let mut parser = EditParser::new(EditFormat::XmlTags);
+        assert_eq!(
+            parse_random_chunks(
+                indoc! {"
+                    <old_text>
+                    Edits look like this:
+                    </old_text>
+                    <new_text>
+                    Edits look like this:
+                    ```
+                    <old_text>
+                    // ... existing code ...
+                    </old_text>
+                    <new_text>
+                    // ... existing code ...
+                    fn main() {}
+                    </new_text>
+                    ```
+                    </new_text>
+                "},
+                &mut parser,
+                &mut rng
+            ),
+            vec![Edit {
+                old_text: "Edits look like this:".to_string(),
+                new_text: indoc! {"
+                    Edits look like this:
+                    ```
+                    <old_text>
+                    // ... existing code ...
+                    </old_text>
+                    <new_text>
+                    // ... existing code ...
+                    fn main() {}
+                    </new_text>
+                    ```"}
+                .to_string(),
+                line_hint: None,
+            }]
+        );
+        assert_eq!(
+            parser.finish(),
+            EditParserMetrics {
+                tags: 2,
+                mismatched_tags: 0
+            }
+        );
+    }
+
+    #[gpui::test(iterations = 1000)]
+    fn test_xml_new_text_with_unbalanced_literal_tag(mut rng: StdRng) {
+        let mut parser = EditParser::new(EditFormat::XmlTags);
+        assert_eq!(
+            parse_random_chunks(
+                indoc! {"
+                    <old_text>
+                    Edits start with:
+                    </old_text>
+                    <new_text>
+                    Edits start with `<old_text`.
+                    </new_text>
+                    <old_text>
+                    fn main() {}
+                    </old_text>
+                    <new_text>
+                    fn main() { run() }
+                    </new_text>
+                "},
+                &mut parser,
+                &mut rng
+            ),
+            vec![
+                Edit {
+                    old_text: "Edits start with:".to_string(),
+                    new_text: "Edits start with `<old_text`.".to_string(),
+                    line_hint: None,
+                },
+                Edit {
+                    old_text: "fn main() {}".to_string(),
+                    new_text: "fn main() { run() }".to_string(),
+                    line_hint: None,
+                },
+            ]
+        );
+        assert_eq!(
+            parser.finish(),
+            EditParserMetrics {
+                tags: 4,
+                mismatched_tags: 0
+            }
+        );
+    }
+
     #[gpui::test(iterations = 1000)]
     fn test_xml_empty_old_and_new_text(mut rng: StdRng) {
         let mut parser = EditParser::new(EditFormat::XmlTags);
@@ -814,6 +1008,93 @@ mod tests {
         );
     }
 
+    #[gpui::test(iterations = 100)]
+    fn test_diff_fenced_new_text_with_literal_markers(mut rng: StdRng) {
+        let mut parser = EditParser::new(EditFormat::DiffFenced);
+        assert_eq!(
+            parse_random_chunks(
+                indoc! {"
+                    <<<<<<< SEARCH
+                    Edits look like this:
+                    =======
+                    Edits look like this:
+                    <<<<<<< SEARCH
+                    // ... existing code ...
+                    =======
+                    // ... existing code ...
+                    fn main() {}
+                    >>>>>>> REPLACE
+                    >>>>>>> REPLACE
+                "},
+                &mut parser,
+                &mut rng
+            ),
+            vec![Edit {
+                old_text: "Edits look like this:".to_string(),
+                new_text: indoc! {"
+                    Edits look like this:
+                    <<<<<<< SEARCH
+                    // ... existing code ...
+                    =======
+                    // ... existing code ...
+                    fn main() {}
+                    >>>>>>> REPLACE"}
+                .to_string(),
+                line_hint: None,
+            }]
+        );
+        assert_eq!(
+            parser.finish(),
+            EditParserMetrics {
+                tags: 0,
+                mismatched_tags: 0
+            }
+        );
+    }
+
+    #[gpui::test(iterations = 100)]
+    fn test_diff_fenced_new_text_with_unbalanced_literal_marker(mut rng: StdRng) {
+        let mut parser = EditParser::new(EditFormat::DiffFenced);
+        assert_eq!(
+            parse_random_chunks(
+                indoc! {"
+                    <<<<<<< SEARCH
+                    Edits start with:
+                    =======
+                    Edits start with:
+                    <<<<<<< SEARCH
+                    >>>>>>> REPLACE
+                    <<<<<<< SEARCH
+                    fn main() {}
+                    =======
+                    fn main() { run() }
+                    >>>>>>> REPLACE
+                "},
+                &mut parser,
+                &mut rng
+            ),
+            vec![
+                Edit {
+                    old_text: "Edits start with:".to_string(),
+                    new_text: "Edits start with:\n<<<<<<< SEARCH".to_string(),
+                    line_hint: None,
+                },
+                Edit {
+                    old_text: "fn main() {}".to_string(),
+                    new_text: "fn main() { run() }".to_string(),
+                    line_hint: None,
+                },
+            ]
+        );
+        assert_eq!(
+            parser.finish(),
+            EditParserMetrics {
+                tags: 0,
+                mismatched_tags: 0
+            }
+        );
+    }
+
     #[gpui::test(iterations = 100)]
     fn test_diff_fenced_multiple_edits(mut rng: StdRng) {
         let mut parser = EditParser::new(EditFormat::DiffFenced);
@@ -1037,6 +1318,111 @@ mod tests {
         assert_eq!(edits[0].new_text, "new");
     }
 
+    #[gpui::test(iterations = 1000)]
+    fn test_random_input_never_panics(mut rng: StdRng) {
+        const FRAGMENTS: &[&str] = &[
+            "<old_text>",
+            "<old_text line=12>",
+            "</old_text>",
+            "<new_text>",
+            "</new_text>",
+            "</edits>",
+            "</parameter>",
+            "</invoke>",
+            "<<<<<<< SEARCH",
+            "<<<<<<< SEARCH line=7",
+            "=======",
+            ">>>>>>> REPLACE",
+            "```",
+            "<",
+            ">",
+            "=",
+            "/",
+            "\n",
+            " ",
+            "\t",
+            "line=",
+            "é",
+            "🦀",
+            "fn main() {}",
+        ];
+
+        for format in [EditFormat::XmlTags, EditFormat::DiffFenced] {
+            let fragment_count = rng.random_range(0..100);
+            let input = (0..fragment_count)
+                .map(|_| *FRAGMENTS.choose(&mut rng).unwrap())
+                .collect::<String>();
+
+            // Every emitted chunk is a slice of the input, so the parser can
+            // never produce more text than it was given.
+            let mut parser = EditParser::new(format);
+            let mut emitted_len = 0;
+            for chunk in random_chunks(&input, &mut rng) {
+                for event in parser.push(chunk) {
+                    match event {
+                        EditParserEvent::OldTextChunk { chunk, .. }
+                        | EditParserEvent::NewTextChunk { chunk, .. } => {
+                            emitted_len += chunk.len();
+                        }
+                    }
+                }
+            }
+            assert!(
+                emitted_len <= input.len(),
+                "{format:?} parser emitted {emitted_len} bytes for {} bytes of input: {input:?}",
+                input.len()
+            );
+            parser.finish();
+        }
+    }
+
+    #[gpui::test(iterations = 1000)]
+    fn test_split_edits_match_unsplit(mut rng: StdRng) {
+        const WORDS: &[&str] = &["fn", "let", "x", "foo()", "{", "}", ";", "é", "🦀"];
+
+        let random_text = |rng: &mut StdRng| {
+            let line_count = rng.random_range(1..5);
+            (0..line_count)
+                .map(|_| {
+                    let indent = " ".repeat(rng.random_range(0..8));
+                    let word_count = rng.random_range(1..5);
+                    let words = (0..word_count)
+                        .map(|_| *WORDS.choose(rng).unwrap())
+                        .collect::<Vec<_>>();
+                    format!("{indent}{}", words.join(" "))
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        let edit_count = rng.random_range(1..5);
+        let edits = (0..edit_count)
+            .map(|_| (random_text(&mut rng), random_text(&mut rng)))
+            .collect::<Vec<_>>();
+
+        for format in [EditFormat::XmlTags, EditFormat::DiffFenced] {
+            let input = edits
+                .iter()
+                .map(|(old_text, new_text)| match format {
+                    EditFormat::XmlTags => format!(
+                        "<old_text>\n{old_text}\n</old_text>\n<new_text>\n{new_text}\n</new_text>\n"
+                    ),
+                    EditFormat::DiffFenced => format!(
+                        "<<<<<<< SEARCH\n{old_text}\n=======\n{new_text}\n>>>>>>> REPLACE\n"
+                    ),
+                })
+                .collect::<String>();
+
+            let unsplit = parse_chunks([input.as_str()], &mut EditParser::new(format));
+            let split = parse_chunks(
+                random_chunks(&input, &mut rng),
+                &mut EditParser::new(format),
+            );
+            assert_eq!(split, unsplit, "{format:?} input: {input:?}");
+            assert_eq!(unsplit.len(), edits.len(), "{format:?} input: {input:?}");
+        }
+    }
+
     #[derive(Default, Debug, PartialEq, Eq)]
     struct Edit {
         old_text: String,
@@ -1050,13 +1436,51 @@ mod tests {
         chunk_indices.sort();
         chunk_indices.push(input.len());
 
+        let mut chunks = Vec::new();
+        let mut last_ix = 0;
+        for chunk_ix in chunk_indices {
+            chunks.push(&input[last_ix..chunk_ix]);
+            last_ix = chunk_ix;
+        }
+        parse_chunks(chunks, parser)
+    }
+
+    /// Splits `input` at random char boundaries, possibly not at all.
+    fn random_chunks<'a>(input: &'a str, rng: &mut StdRng) -> Vec<&'a str> {
+        let boundaries = input
+            .char_indices()
+            .skip(1)
+            .map(|(ix, _)| ix)
+            .collect::<Vec<_>>();
+        let chunk_count = rng.random_range(0..=cmp::min(boundaries.len(), 50));
+        let mut chunk_indices = boundaries
+            .choose_multiple(rng, chunk_count)
+            .copied()
+            .collect::<Vec<_>>();
+        chunk_indices.sort();
+        chunk_indices.push(input.len());
+
+        let mut last_ix = 0;
+        chunk_indices
+            .into_iter()
+            .map(|chunk_ix| {
+                let chunk = &input[last_ix..chunk_ix];
+                last_ix = chunk_ix;
+                chunk
+            })
+            .collect()
+    }
+
+    fn parse_chunks<'a>(
+        chunks: impl IntoIterator<Item = &'a str>,
+        parser: &mut EditParser,
+    ) -> Vec<Edit> {
         let mut old_text = Some(String::new());
         let mut new_text = None;
         let mut pending_edit = Edit::default();
         let mut edits = Vec::new();
-        let mut last_ix = 0;
-        for chunk_ix in chunk_indices {
-            for event in parser.push(&input[last_ix..chunk_ix]) {
+        for chunk in chunks {
+            for event in parser.push(chunk) {
                 match event {
                     EditParserEvent::OldTextChunk {
                         chunk,
@@ -1081,7 +1505,6 @@ mod tests {
                     }
                 }
             }
-            last_ix = chunk_ix;
         }
 
         if new_text.is_some() {
@@ -5,6 +5,52 @@ const REPLACEMENT_COST: u32 = 1;
 const INSERTION_COST: u32 = 3;
 const DELETION_COST: u32 = 10;
 
+/// File extensions of languages in which indentation carries meaning.
+const WHITESPACE_SIGNIFICANT_EXTENSIONS: &[&str] = &[
+    "coffee", "haml", "nim", "pug", "py", "pyi", "pyw", "sass", "yaml", "yml",
+];
+
+/// Weights used when aligning the lines of a query with the lines of a buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MatchCosts {
+    /// Cost of pairing a query line with a buffer line that is only similar to it.
+    pub replacement: u32,
+    /// Cost of skipping a buffer line that doesn't appear in the query.
+    pub insertion: u32,
+    /// Cost of skipping a query line that doesn't appear in the buffer.
+    pub deletion: u32,
+    /// Whether lines that only differ in their indentation match for free.
+    pub ignore_indentation: bool,
+}
+
+impl Default for MatchCosts {
+    fn default() -> Self {
+        Self {
+            replacement: REPLACEMENT_COST,
+            insertion: INSERTION_COST,
+            deletion: DELETION_COST,
+            ignore_indentation: true,
+        }
+    }
+}
+
+impl MatchCosts {
+    /// Returns these costs with differences in indentation charged like any
+    /// other replacement, for languages where indentation is significant.
+    pub fn whitespace_significant(self) -> Self {
+        Self {
+            ignore_indentation: false,
+            ..self
+        }
+    }
+
+    pub fn is_whitespace_significant_extension(extension: &str) -> bool {
+        WHITESPACE_SIGNIFICANT_EXTENSIONS
+            .iter()
+            .any(|significant| extension.eq_ignore_ascii_case(significant))
+    }
+}
+
 /// A streaming fuzzy matcher that can process text chunks incrementally
 /// and return the best match found so far at each step.
 pub struct StreamingFuzzyMatcher {
@@ -14,6 +60,7 @@ pub struct StreamingFuzzyMatcher {
     incomplete_line: String,
     matches: Vec<Range<usize>>,
     matrix: SearchMatrix,
+    costs: MatchCosts,
 }
 
 impl StreamingFuzzyMatcher {
@@ -26,9 +73,15 @@ impl StreamingFuzzyMatcher {
             incomplete_line: String::new(),
             matches: Vec::new(),
             matrix: SearchMatrix::new(buffer_line_count + 1),
+            costs: MatchCosts::default(),
         }
     }
 
+    pub fn with_costs(mut self, costs: MatchCosts) -> Self {
+        self.costs = costs;
+        self
+    }
+
     /// Returns the query lines.
     pub fn query_lines(&self) -> &[String] {
         &self.query_lines
@@ -101,9 +154,10 @@ impl StreamingFuzzyMatcher {
         self.matrix.resize_rows(new_query_line_count + 1);
 
         // Process only the new query lines
+        let costs = self.costs;
         for row in old_query_line_count..new_query_line_count {
-            let query_line = self.query_lines[row].trim();
-            let leading_deletion_cost = (row + 1) as u32 * DELETION_COST;
+            let (query_indent, query_line) = split_indentation(&self.query_lines[row]);
+            let leading_deletion_cost = (row + 1) as u32 * costs.deletion;
 
             self.matrix.set(
                 row + 1,
@@ -114,34 +168,35 @@ impl StreamingFuzzyMatcher {
             let mut buffer_lines = self.snapshot.as_rope().chunks().lines();
             let mut col = 0;
             while let Some(buffer_line) = buffer_lines.next() {
-                let buffer_line = buffer_line.trim();
+                let (buffer_indent, buffer_line) = split_indentation(buffer_line);
                 let up = SearchState::new(
                     self.matrix
                         .get(row, col + 1)
                         .cost
-                        .saturating_add(DELETION_COST),
+                        .saturating_add(costs.deletion),
                     SearchDirection::Up,
                 );
                 let left = SearchState::new(
                     self.matrix
                         .get(row + 1, col)
                         .cost
-                        .saturating_add(INSERTION_COST),
+                        .saturating_add(costs.insertion),
                     SearchDirection::Left,
                 );
-                let diagonal = SearchState::new(
-                    if query_line == buffer_line {
-                        self.matrix.get(row, col).cost
-                    } else if fuzzy_eq(query_line, buffer_line) {
-                        self.matrix.get(row, col).cost + REPLACEMENT_COST
-                    } else {
-                        self.matrix
-                            .get(row, col)
-                            .cost
-                            .saturating_add(DELETION_COST + INSERTION_COST)
-                    },
-                    SearchDirection::Diagonal,
-                );
+                let mut diagonal_cost = if query_line == buffer_line {
+                    self.matrix.get(row, col).cost
+                } else if fuzzy_eq(query_line, buffer_line) {
+                    self.matrix.get(row, col).cost + costs.replacement
+                } else {
+                    self.matrix
+                        .get(row, col)
+                        .cost
+                        .saturating_add(costs.deletion + costs.insertion)
+                };
+                if !costs.ignore_indentation && query_indent != buffer_indent {
+                    diagonal_cost = diagonal_cost.saturating_add(costs.replacement);
+                }
+                let diagonal = SearchState::new(diagonal_cost, SearchDirection::Diagonal);
                 self.matrix
                     .set(row + 1, col + 1, up.min(left).min(diagonal));
                 col += 1;
@@ -243,6 +298,12 @@ impl StreamingFuzzyMatcher {
     }
 }
 
+/// Splits a line into its leading whitespace and its trimmed content.
+fn split_indentation(line: &str) -> (&str, &str) {
+    let content = line.trim_start();
+    (&line[..line.len() - content.len()], content.trim_end())
+}
+
 fn fuzzy_eq(left: &str, right: &str) -> bool {
     const THRESHOLD: f64 = 0.8;
 
@@ -333,6 +394,37 @@ mod tests {
         assert_eq!(finish(finder), None);
     }
 
+    #[test]
+    fn test_whitespace_significant_costs() {
+        let text = indoc! {"
+            def outer():
+                if ready:
+                    return 1
+            if ready:
+                return 1
+        "};
+        let buffer = TextBuffer::new(ReplicaId::LOCAL, BufferId::new(1).unwrap(), text);
+        let snapshot = buffer.snapshot();
+        let query = "if ready:\n    return 1";
+
+        // Ignoring indentation, both blocks match equally well.
+        let mut matcher = StreamingFuzzyMatcher::new(snapshot.clone());
+        matcher.push(query, None);
+        assert_eq!(matcher.finish().len(), 2);
+
+        let mut matcher = StreamingFuzzyMatcher::new(snapshot.clone())
+            .with_costs(MatchCosts::default().whitespace_significant());
+        matcher.push(query, None);
+        let matches = matcher.finish();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(
+            snapshot
+                .text_for_range(matches[0].clone())
+                .collect::<String>(),
+            query
+        );
+    }
+
     #[test]
     fn test_streaming_exact_match() {
         let buffer = TextBuffer::new(
@@ -0,0 +1,377 @@
+use std::time::Duration;
+
+use futures::{FutureExt as _, future};
+use gpui::BackgroundExecutor;
+use language_model::LanguageModelCompletionError;
+
+pub(crate) const MAX_RETRY_ATTEMPTS: u8 = 4;
+pub(crate) const BASE_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub(crate) enum RetryStrategy {
+    ExponentialBackoff {
+        initial_delay: Duration,
+        max_attempts: u8,
+    },
+    Fixed {
+        delay: Duration,
+        max_attempts: u8,
+    },
+}
+
+impl RetryStrategy {
+    /// Picks how to retry a failed completion request, or `None` if retrying
+    /// won't help.
+    pub(crate) fn for_completion_error(error: &LanguageModelCompletionError) -> Option<Self> {
+        use LanguageModelCompletionError::*;
+        use http_client::StatusCode;
+
+        // General strategy here:
+        // - If retrying won't help (e.g. invalid API key or payload too large), return None so we don't retry at all.
+        // - If it's a time-based issue (e.g. server overloaded, rate limit exceeded), retry up to 4 times with exponential backoff.
+        // - If it's an issue that *might* be fixed by retrying (e.g. internal server error), retry up to 3 times.
+        match error {
+            HttpResponseError {
+                status_code: StatusCode::TOO_MANY_REQUESTS,
+                ..
+            } => Some(Self::ExponentialBackoff {
+                initial_delay: BASE_RETRY_DELAY,
+                max_attempts: MAX_RETRY_ATTEMPTS,
+            }),
+            ServerOverloaded { retry_after, .. } | RateLimitExceeded { retry_after, .. } => {
+                Some(Self::Fixed {
+                    delay: retry_after.unwrap_or(BASE_RETRY_DELAY),
+                    max_attempts: MAX_RETRY_ATTEMPTS,
+                })
+            }
+            UpstreamProviderError {
+                status,
+                retry_after,
+                ..
+            } => match *status {
+                StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => {
+                    Some(Self::Fixed {
+                        delay: retry_after.unwrap_or(BASE_RETRY_DELAY),
+                        max_attempts: MAX_RETRY_ATTEMPTS,
+                    })
+                }
+                StatusCode::INTERNAL_SERVER_ERROR => Some(Self::Fixed {
+                    delay: retry_after.unwrap_or(BASE_RETRY_DELAY),
+                    // Internal Server Error could be anything, retry up to 3 times.
+                    max_attempts: 3,
+                }),
+                status => {
+                    // There is no StatusCode variant for the unofficial HTTP 529 ("The service is overloaded"),
+                    // but we frequently get them in practice. See https://http.dev/529
+                    if status.as_u16() == 529 {
+                        Some(Self::Fixed {
+                            delay: retry_after.unwrap_or(BASE_RETRY_DELAY),
+                            max_attempts: MAX_RETRY_ATTEMPTS,
+                        })
+                    } else {
+                        Some(Self::Fixed {
+                            delay: retry_after.unwrap_or(BASE_RETRY_DELAY),
+                            max_attempts: 2,
+                        })
+                    }
+                }
+            },
+            ApiInternalServerError { .. } => Some(Self::Fixed {
+                delay: BASE_RETRY_DELAY,
+                max_attempts: 3,
+            }),
+            ApiReadResponseError { .. }
+            | HttpSend { .. }
+            | DeserializeResponse { .. }
+            | BadRequestFormat { .. } => Some(Self::Fixed {
+                delay: BASE_RETRY_DELAY,
+                max_attempts: 3,
+            }),
+            // Retrying these errors definitely shouldn't help.
+            HttpResponseError {
+                status_code:
+                    StatusCode::PAYLOAD_TOO_LARGE | StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED,
+                ..
+            }
+            | AuthenticationError { .. }
+            | PermissionError { .. }
+            | NoApiKey { .. }
+            | ApiEndpointNotFound { .. }
+            | PromptTooLarge { .. } => None,
+            // These errors might be transient, so retry them
+            SerializeRequest { .. } | BuildRequestBody { .. } | StreamEndedUnexpectedly { .. } => {
+                Some(Self::Fixed {
+                    delay: BASE_RETRY_DELAY,
+                    max_attempts: 1,
+                })
+            }
+            // Retry all other 4xx and 5xx errors once.
+            HttpResponseError { status_code, .. }
+                if status_code.is_client_error() || status_code.is_server_error() =>
+            {
+                Some(Self::Fixed {
+                    delay: BASE_RETRY_DELAY,
+                    max_attempts: 3,
+                })
+            }
+            Other(err) if err.is::<language_model::PaymentRequiredError>() => {
+                // Retrying won't help for Payment Required errors.
+                None
+            }
+            // Conservatively assume that any other errors are non-retryable
+            HttpResponseError { .. } | Other(..) => Some(Self::Fixed {
+                delay: BASE_RETRY_DELAY,
+                max_attempts: 2,
+            }),
+        }
+    }
+
+    pub(crate) fn max_attempts(&self) -> u8 {
+        match self {
+            Self::ExponentialBackoff { max_attempts, .. } | Self::Fixed { max_attempts, .. } => {
+                *max_attempts
+            }
+        }
+    }
+
+    /// Returns how long to wait before the given retry, where `attempt` starts
+    /// at 1, or `None` once the strategy has run out of attempts.
+    pub(crate) fn delay_for_attempt(&self, attempt: u8) -> Option<Duration> {
+        if attempt == 0 || attempt > self.max_attempts() {
+            return None;
+        }
+
+        Some(match self {
+            Self::ExponentialBackoff { initial_delay, .. } => {
+                let delay_secs = initial_delay.as_secs() * 2u64.pow((attempt - 1) as u32);
+                Duration::from_secs(delay_secs)
+            }
+            Self::Fixed { delay, .. } => *delay,
+        })
+    }
+}
+
+/// A retry that [`retry_with_backoff`] is about to wait for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RetryAttempt {
+    /// The number of the upcoming retry, starting at 1.
+    pub attempt: u8,
+    pub max_attempts: u8,
+    pub delay: Duration,
+}
+
+/// Runs `request` until it succeeds, waiting between attempts according to the
+/// strategy `strategy_for` picks for each error. `on_retry` is called with the
+/// error before each wait, so callers can report the upcoming retry.
+///
+/// Errors for which `strategy_for` returns `None`, or that occur after the
+/// strategy's attempts are exhausted, are returned as is. So is the last error
+/// when `cancellation_rx` reports `true` during a wait.
+pub(crate) async fn retry_with_backoff<T, E>(
+    executor: &BackgroundExecutor,
+    mut cancellation_rx: Option<watch::Receiver<bool>>,
+    strategy_for: impl Fn(&E) -> Option<RetryStrategy>,
+    mut on_retry: impl FnMut(&E, RetryAttempt),
+    mut request: impl AsyncFnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    let mut attempt = 0;
+    loop {
+        let error = match request().await {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+
+        attempt += 1;
+        let Some((strategy, delay)) = strategy_for(&error).and_then(|strategy| {
+            let delay = strategy.delay_for_attempt(attempt)?;
+            Some((strategy, delay))
+        }) else {
+            return Err(error);
+        };
+        log::debug!("Retry attempt {attempt} with delay {delay:?}");
+        on_retry(
+            &error,
+            RetryAttempt {
+                attempt,
+                max_attempts: strategy.max_attempts(),
+                delay,
+            },
+        );
+
+        let timer = executor.timer(delay);
+        match cancellation_rx.as_mut() {
+            Some(cancellation_rx) => {
+                let cancelled = async {
+                    while !*cancellation_rx.borrow() {
+                        if cancellation_rx.changed().await.is_err() {
+                            future::pending::<()>().await;
+                        }
+                    }
+                };
+                futures::select! {
+                    _ = timer.fuse() => {}
+                    _ = cancelled.fuse() => {
+                        log::debug!("Cancelled during retry delay");
+                        return Err(error);
+                    }
+                }
+            }
+            None => timer.await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use parking_lot::Mutex;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicU8, Ordering},
+    };
+
+    #[derive(Debug, PartialEq)]
+    enum TestError {
+        Transient,
+        Fatal,
+    }
+
+    fn strategy_for(error: &TestError) -> Option<RetryStrategy> {
+        match error {
+            TestError::Transient => Some(RetryStrategy::Fixed {
+                delay: Duration::from_secs(1),
+                max_attempts: 3,
+            }),
+            TestError::Fatal => None,
+        }
+    }
+
+    async fn run(
+        cx: &mut TestAppContext,
+        outcomes: impl Fn(u8) -> Result<u8, TestError> + Send + 'static,
+    ) -> (Result<u8, TestError>, u8) {
+        let executor = cx.executor();
+        let calls = Arc::new(AtomicU8::new(0));
+        let task = executor.spawn({
+            let executor = executor.clone();
+            let calls = calls.clone();
+            async move {
+                retry_with_backoff(
+                    &executor,
+                    None,
+                    strategy_for,
+                    |_, _| {},
+                    async || outcomes(calls.fetch_add(1, Ordering::SeqCst) + 1),
+                )
+                .await
+            }
+        });
+        for _ in 0..10 {
+            executor.advance_clock(Duration::from_secs(1));
+            executor.run_until_parked();
+        }
+        (task.await, calls.load(Ordering::SeqCst))
+    }
+
+    #[gpui::test]
+    async fn test_retry_succeeds_after_failures(cx: &mut TestAppContext) {
+        let (result, calls) = run(cx, |call| {
+            if call < 3 {
+                Err(TestError::Transient)
+            } else {
+                Ok(call)
+            }
+        })
+        .await;
+        assert_eq!(result, Ok(3));
+        assert_eq!(calls, 3);
+    }
+
+    #[gpui::test]
+    async fn test_retry_gives_up_after_max_attempts(cx: &mut TestAppContext) {
+        let (result, calls) = run(cx, |_| Err(TestError::Transient)).await;
+        assert_eq!(result, Err(TestError::Transient));
+        // The initial request plus three retries.
+        assert_eq!(calls, 4);
+    }
+
+    #[gpui::test]
+    async fn test_retry_passes_through_non_retryable_errors(cx: &mut TestAppContext) {
+        let (result, calls) = run(cx, |call| {
+            if call == 1 {
+                Err(TestError::Fatal)
+            } else {
+                Ok(call)
+            }
+        })
+        .await;
+        assert_eq!(result, Err(TestError::Fatal));
+        assert_eq!(calls, 1);
+    }
+
+    #[gpui::test]
+    async fn test_retry_reports_attempts_and_stops_when_cancelled(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let (mut cancellation_tx, cancellation_rx) = watch::channel(false);
+        let retries = Arc::new(Mutex::new(Vec::new()));
+        let calls = Arc::new(AtomicU8::new(0));
+        let task = executor.spawn({
+            let executor = executor.clone();
+            let retries = retries.clone();
+            let calls = calls.clone();
+            async move {
+                retry_with_backoff(
+                    &executor,
+                    Some(cancellation_rx),
+                    strategy_for,
+                    |_, attempt| retries.lock().push(attempt),
+                    async || {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        Err::<(), _>(TestError::Transient)
+                    },
+                )
+                .await
+            }
+        });
+
+        executor.run_until_parked();
+        executor.advance_clock(Duration::from_secs(1));
+        executor.run_until_parked();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            *retries.lock(),
+            vec![
+                RetryAttempt {
+                    attempt: 1,
+                    max_attempts: 3,
+                    delay: Duration::from_secs(1),
+                },
+                RetryAttempt {
+                    attempt: 2,
+                    max_attempts: 3,
+                    delay: Duration::from_secs(1),
+                },
+            ]
+        );
+
+        // Cancelling during the delay returns the last error without waiting
+        // for the timer or sending another request.
+        cancellation_tx.send(true).unwrap();
+        executor.run_until_parked();
+        assert_eq!(task.await, Err(TestError::Transient));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_exponential_backoff_delays() {
+        let strategy = RetryStrategy::ExponentialBackoff {
+            initial_delay: BASE_RETRY_DELAY,
+            max_attempts: 3,
+        };
+        assert_eq!(strategy.delay_for_attempt(1), Some(Duration::from_secs(5)));
+        assert_eq!(strategy.delay_for_attempt(2), Some(Duration::from_secs(10)));
+        assert_eq!(strategy.delay_for_attempt(3), Some(Duration::from_secs(20)));
+        assert_eq!(strategy.delay_for_attempt(4), None);
+    }
+}
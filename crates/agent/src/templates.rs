@@ -4,21 +4,77 @@ use handlebars::Handlebars;
 use rust_embed::RustEmbed;
 use serde::Serialize;
 use std::sync::Arc;
+#[cfg(debug_assertions)]
+use std::path::{Path, PathBuf};
 
 #[derive(RustEmbed)]
 #[folder = "src/templates"]
 #[include = "*.hbs"]
 struct Assets;
 
-pub struct Templates(Handlebars<'static>);
+/// In debug builds, setting this to a directory of `.hbs` files makes the
+/// agent read its prompt templates from there on every render, so prompt
+/// changes can be tried out without recompiling.
+#[cfg(debug_assertions)]
+const TEMPLATES_DIR_ENV_VAR: &str = "ZED_AGENT_TEMPLATES_DIR";
+
+pub struct Templates {
+    handlebars: Handlebars<'static>,
+    #[cfg(debug_assertions)]
+    reload_dir: Option<PathBuf>,
+}
 
 impl Templates {
     pub fn new() -> Arc<Self> {
+        #[cfg(debug_assertions)]
+        if let Some(dir) = std::env::var_os(TEMPLATES_DIR_ENV_VAR) {
+            log::info!(
+                "Reading agent prompt templates from {}",
+                Path::new(&dir).display()
+            );
+            return Self::reloading_from(dir.into());
+        }
+
+        Arc::new(Self {
+            handlebars: Self::handlebars(),
+            #[cfg(debug_assertions)]
+            reload_dir: None,
+        })
+    }
+
+    /// Reads templates from `dir` each time they are rendered, falling back to
+    /// the embedded template when `dir` doesn't contain one with the same name.
+    #[cfg(debug_assertions)]
+    pub fn reloading_from(dir: PathBuf) -> Arc<Self> {
+        Arc::new(Self {
+            handlebars: Self::handlebars(),
+            reload_dir: Some(dir),
+        })
+    }
+
+    fn handlebars() -> Handlebars<'static> {
         let mut handlebars = Handlebars::new();
         handlebars.set_strict_mode(true);
         handlebars.register_helper("contains", Box::new(contains));
         handlebars.register_embed_templates::<Assets>().unwrap();
-        Arc::new(Self(handlebars))
+        handlebars
+    }
+
+    fn render(&self, template_name: &str, data: &impl Serialize) -> Result<String> {
+        #[cfg(debug_assertions)]
+        if let Some(dir) = self.reload_dir.as_ref() {
+            let path = dir.join(template_name);
+            match std::fs::read_to_string(&path) {
+                Ok(source) => return Ok(self.handlebars.render_template(&source, data)?),
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+                Err(error) => {
+                    return Err(anyhow::Error::new(error)
+                        .context(format!("failed to read template {}", path.display())));
+                }
+            }
+        }
+
+        Ok(self.handlebars.render(template_name, data)?)
     }
 }
 
@@ -29,7 +85,7 @@ pub trait Template: Sized {
     where
         Self: Serialize + Sized,
     {
-        Ok(templates.0.render(Self::TEMPLATE_NAME, self)?)
+        templates.render(Self::TEMPLATE_NAME, self)
     }
 }
 
@@ -45,6 +101,97 @@ impl Template for SystemPromptTemplate<'_> {
     const TEMPLATE_NAME: &'static str = "system_prompt.hbs";
 }
 
+/// How important a fragment of the system prompt is. When the system prompt is
+/// over budget, lower priority fragments are truncated first, and `High` ones
+/// are never truncated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SystemPromptPriority {
+    Low,
+    Normal,
+    High,
+}
+
+/// A piece of variable-length text that gets rendered into the system prompt.
+pub struct SystemPromptFragment<'a> {
+    pub priority: SystemPromptPriority,
+    pub text: &'a mut String,
+}
+
+/// Rough number of bytes per token, used to estimate the size of the system
+/// prompt without asking the model to count tokens.
+const BYTES_PER_TOKEN: usize = 4;
+const TRUNCATION_MARKER: &str = "\n[Truncated to fit in the context window]";
+
+impl SystemPromptTemplate<'_> {
+    /// Renders the system prompt, truncating lower-priority fragments of the
+    /// project context until the estimated size fits within `max_tokens`. The
+    /// template's own instructions are always kept intact.
+    pub fn render_within_budget(&self, templates: &Templates, max_tokens: u64) -> Result<String> {
+        let system_prompt = self.render(templates)?;
+        let max_bytes = usize::try_from(max_tokens)
+            .unwrap_or(usize::MAX)
+            .saturating_mul(BYTES_PER_TOKEN);
+        if system_prompt.len() <= max_bytes {
+            return Ok(system_prompt);
+        }
+
+        let mut project = self.project.clone();
+        let mut excess = system_prompt.len() - max_bytes;
+        log::warn!("System prompt is {excess} bytes over budget, truncating project context");
+
+        let mut fragments = project_context_fragments(&mut project);
+        fragments
+            .sort_by_key(|fragment| (fragment.priority, std::cmp::Reverse(fragment.text.len())));
+        for fragment in fragments {
+            if excess == 0 || fragment.priority == SystemPromptPriority::High {
+                break;
+            }
+            excess = excess.saturating_sub(truncate_fragment(fragment.text, excess));
+        }
+
+        SystemPromptTemplate {
+            project: &project,
+            available_tools: self.available_tools.clone(),
+            model_name: self.model_name.clone(),
+        }
+        .render(templates)
+    }
+}
+
+fn project_context_fragments(
+    project: &mut prompt_store::ProjectContext,
+) -> Vec<SystemPromptFragment<'_>> {
+    let rules_files = project
+        .worktrees
+        .iter_mut()
+        .filter_map(|worktree| worktree.rules_file.as_mut())
+        .map(|rules_file| SystemPromptFragment {
+            priority: SystemPromptPriority::Low,
+            text: &mut rules_file.text,
+        });
+    let user_rules = project
+        .user_rules
+        .iter_mut()
+        .map(|user_rules| SystemPromptFragment {
+            priority: SystemPromptPriority::Normal,
+            text: &mut user_rules.contents,
+        });
+    rules_files.chain(user_rules).collect()
+}
+
+/// Shortens `text` by roughly `excess` bytes, marking where it was cut, and
+/// returns how many bytes were actually removed.
+fn truncate_fragment(text: &mut String, excess: usize) -> usize {
+    let original_len = text.len();
+    let mut new_len = original_len.saturating_sub(excess + TRUNCATION_MARKER.len());
+    while !text.is_char_boundary(new_len) {
+        new_len -= 1;
+    }
+    text.truncate(new_len);
+    text.push_str(TRUNCATION_MARKER);
+    original_len.saturating_sub(text.len())
+}
+
 /// Handlebars helper for checking if an item is in a list
 fn contains(
     h: &handlebars::Helper,
@@ -88,4 +235,78 @@ mod tests {
         assert!(!rendered.contains("## Planning"));
         assert!(rendered.contains("test-model"));
     }
+
+    #[test]
+    fn test_system_prompt_budget_truncates_low_priority_fragments() {
+        let mut project = prompt_store::ProjectContext::default();
+        project.worktrees.push(prompt_store::WorktreeContext {
+            root_name: "project".into(),
+            abs_path: std::path::Path::new("/project").into(),
+            rules_file: Some(prompt_store::RulesFileContext {
+                path_in_worktree: util::rel_path::rel_path("AGENTS.md").into(),
+                text: "project rule\n".repeat(10_000),
+                project_entry_id: 0,
+            }),
+        });
+        project.has_rules = true;
+        project.user_rules.push(prompt_store::UserRulesContext {
+            uuid: prompt_store::UserPromptId::new(),
+            title: None,
+            contents: "Always answer in haiku.".into(),
+        });
+        project.has_user_rules = true;
+
+        let template = SystemPromptTemplate {
+            project: &project,
+            available_tools: vec!["echo".into()],
+            model_name: Some("test-model".to_string()),
+        };
+        let templates = Templates::new();
+        let unbounded = template.render(&templates).unwrap();
+        let max_tokens = 8_000;
+        assert!(unbounded.len() > max_tokens * BYTES_PER_TOKEN);
+
+        let rendered = template
+            .render_within_budget(&templates, max_tokens as u64)
+            .unwrap();
+        assert!(rendered.len() <= max_tokens * BYTES_PER_TOKEN);
+        assert!(rendered.contains(TRUNCATION_MARKER));
+        assert!(rendered.contains("project rule"));
+        assert!(rendered.contains("Always answer in haiku."));
+        assert!(rendered.contains("## Tool Use"));
+
+        assert_eq!(
+            template.render_within_budget(&templates, u64::MAX).unwrap(),
+            unbounded
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_reloading_templates_picks_up_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let templates = Templates::reloading_from(dir.path().to_path_buf());
+        let project = prompt_store::ProjectContext::default();
+        let template = SystemPromptTemplate {
+            project: &project,
+            available_tools: vec!["echo".into()],
+            model_name: Some("test-model".to_string()),
+        };
+
+        let rendered = template.render(&templates).unwrap();
+        assert!(
+            rendered.contains("## Fixing Diagnostics"),
+            "should fall back to the embedded template"
+        );
+
+        let template_path = dir.path().join(SystemPromptTemplate::TEMPLATE_NAME);
+        std::fs::write(&template_path, "You are {{model_name}}.").unwrap();
+        assert_eq!(template.render(&templates).unwrap(), "You are test-model.");
+
+        std::fs::write(&template_path, "You are still {{model_name}}.").unwrap();
+        assert_eq!(
+            template.render(&templates).unwrap(),
+            "You are still test-model."
+        );
+    }
 }
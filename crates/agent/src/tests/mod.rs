@@ -977,6 +977,369 @@ async fn test_tool_hallucination(cx: &mut TestAppContext) {
     assert_eq!(update.fields.status, Some(acp::ToolCallStatus::Failed));
 }
 
+#[gpui::test]
+async fn test_tool_output_violating_its_schema_is_reported(cx: &mut TestAppContext) {
+    assert!(EchoTool.erase().output_schema().is_none());
+
+    let tool = MisreportingTool.erase();
+    assert!(tool.output_schema().is_some());
+    let (event_stream, _rx) = ToolCallEventStream::test();
+    let result = cx
+        .update(|cx| {
+            tool.run(
+                ToolInput::resolved(MisreportingToolInput {}),
+                event_stream,
+                cx,
+            )
+        })
+        .await;
+    let Err(output) = result else {
+        panic!("expected the schema violation to be reported");
+    };
+    let message = output.raw_output.as_str().unwrap_or_default();
+    assert!(
+        message.starts_with("Tool output doesn't match its schema"),
+        "unexpected error: {message}"
+    );
+}
+
+#[gpui::test]
+async fn test_sync_tool_completes_without_spawning(cx: &mut TestAppContext) {
+    let (event_stream, _rx) = ToolCallEventStream::test();
+    let task = cx.update(|cx| {
+        AddTool.erase().run(
+            ToolInput::resolved(AddToolInput { a: 2, b: 3 }),
+            event_stream,
+            cx,
+        )
+    });
+    // The result is available without running the executor.
+    let Some(Ok(output)) = task.now_or_never() else {
+        panic!("expected the sync tool to complete immediately");
+    };
+    assert_eq!(output.raw_output, json!("5"));
+
+    // Tools without a sync path still run as a task.
+    let (event_stream, _rx) = ToolCallEventStream::test();
+    let task = cx.update(|cx| {
+        EchoTool.erase().run(
+            ToolInput::resolved(EchoToolInput { text: "hi".into() }),
+            event_stream,
+            cx,
+        )
+    });
+    let Ok(output) = task.await else {
+        panic!("expected the echo tool to succeed");
+    };
+    assert_eq!(output.raw_output, json!("hi"));
+}
+
+#[gpui::test]
+async fn test_restricted_tools_are_hidden_and_refused(cx: &mut TestAppContext) {
+    let ThreadTest { model, thread, .. } = setup(cx, TestModel::Fake).await;
+    let fake_model = model.as_fake();
+
+    let mut events = thread
+        .update(cx, |thread, cx| {
+            thread.add_tool(EchoTool);
+            thread.add_tool(DelayTool);
+            thread.set_tool_restrictions(
+                ToolRestrictions {
+                    allowed: None,
+                    denied: [SharedString::from(EchoTool::NAME)].into_iter().collect(),
+                },
+                cx,
+            );
+            thread.send(UserMessageId::new(), ["Echo 'hi'"], cx)
+        })
+        .unwrap();
+    cx.run_until_parked();
+
+    let completion = fake_model.pending_completions().pop().unwrap();
+    let tool_names = completion
+        .tools
+        .iter()
+        .map(|tool| tool.name.as_str())
+        .collect::<Vec<_>>();
+    assert!(tool_names.contains(&DelayTool::NAME));
+    assert!(!tool_names.contains(&EchoTool::NAME));
+
+    // The model calls the denied tool anyway.
+    fake_model.send_last_completion_stream_event(LanguageModelCompletionEvent::ToolUse(
+        LanguageModelToolUse {
+            id: "tool_id_1".into(),
+            name: EchoTool::NAME.into(),
+            raw_input: json!({"text": "hi"}).to_string(),
+            input: json!({"text": "hi"}),
+            is_input_complete: true,
+            thought_signature: None,
+        },
+    ));
+    fake_model.end_last_completion_stream();
+
+    let tool_call = expect_tool_call(&mut events).await;
+    assert_eq!(tool_call.title, "Echo");
+    let update = expect_tool_call_update_fields(&mut events).await;
+    assert_eq!(update.fields.status, Some(acp::ToolCallStatus::Failed));
+    cx.run_until_parked();
+
+    let completion = fake_model.pending_completions().pop().unwrap();
+    let tool_result = completion
+        .messages
+        .last()
+        .unwrap()
+        .content
+        .iter()
+        .find_map(|content| match content {
+            MessageContent::ToolResult(tool_result) => Some(tool_result.clone()),
+            _ => None,
+        })
+        .unwrap();
+    assert!(tool_result.is_error);
+    assert_eq!(
+        tool_result.content,
+        "The echo tool is not allowed in this context".into()
+    );
+}
+
+#[gpui::test]
+async fn test_turn_event_sequence(cx: &mut TestAppContext) {
+    let ThreadTest { model, thread, .. } = setup(cx, TestModel::Fake).await;
+    let fake_model = model.as_fake();
+
+    let events = thread
+        .update(cx, |thread, cx| {
+            thread.add_tool(EchoTool);
+            thread.send(UserMessageId::new(), ["Echo 'hi'"], cx)
+        })
+        .unwrap();
+    cx.run_until_parked();
+
+    fake_model.send_last_completion_stream_text_chunk("Let me ");
+    fake_model.send_last_completion_stream_text_chunk("echo that.");
+    fake_model.send_last_completion_stream_event(LanguageModelCompletionEvent::ToolUse(
+        LanguageModelToolUse {
+            id: "tool_id_1".into(),
+            name: EchoTool::NAME.into(),
+            raw_input: json!({"text": "hi"}).to_string(),
+            input: json!({"text": "hi"}),
+            is_input_complete: true,
+            thought_signature: None,
+        },
+    ));
+    fake_model
+        .send_last_completion_stream_event(LanguageModelCompletionEvent::Stop(StopReason::ToolUse));
+    fake_model.end_last_completion_stream();
+    cx.run_until_parked();
+
+    fake_model.send_last_completion_stream_text_chunk("Done.");
+    fake_model
+        .send_last_completion_stream_event(LanguageModelCompletionEvent::Stop(StopReason::EndTurn));
+    fake_model.end_last_completion_stream();
+
+    assert_eq!(
+        turn_events(events.collect().await),
+        vec![
+            TurnEvent::Text("Let me echo that.".into()),
+            TurnEvent::ToolCall("Echo".into()),
+            TurnEvent::Text("Done.".into()),
+            TurnEvent::Stop(acp::StopReason::EndTurn),
+        ]
+    );
+}
+
+#[gpui::test]
+async fn test_scripted_tool_call_round_trip(cx: &mut TestAppContext) {
+    let ThreadTest { model, thread, .. } = setup(cx, TestModel::Fake).await;
+    let fake_model = model.as_fake();
+
+    let echo_tool_use = LanguageModelToolUse {
+        id: "tool_id_1".into(),
+        name: EchoTool::NAME.into(),
+        raw_input: json!({"text": "hi"}).to_string(),
+        input: json!({"text": "hi"}),
+        is_input_complete: true,
+        thought_signature: None,
+    };
+    fake_model.push_scripted_completion([
+        LanguageModelCompletionEvent::ToolUse(echo_tool_use.clone()),
+        LanguageModelCompletionEvent::Stop(StopReason::ToolUse),
+    ]);
+    fake_model.push_scripted_completion([
+        LanguageModelCompletionEvent::Text("The tool said hi.".into()),
+        LanguageModelCompletionEvent::Stop(StopReason::EndTurn),
+    ]);
+
+    let events = thread
+        .update(cx, |thread, cx| {
+            thread.add_tool(EchoTool);
+            thread.send(UserMessageId::new(), ["Echo 'hi'"], cx)
+        })
+        .unwrap()
+        .collect()
+        .await;
+
+    assert_eq!(
+        turn_events(events),
+        vec![
+            TurnEvent::ToolCall("Echo".into()),
+            TurnEvent::Text("The tool said hi.".into()),
+            TurnEvent::Stop(acp::StopReason::EndTurn),
+        ]
+    );
+    assert_eq!(fake_model.completion_count(), 0);
+
+    let request = thread
+        .read_with(cx, |thread, cx| {
+            thread.build_completion_request(CompletionIntent::UserPrompt, cx)
+        })
+        .unwrap();
+    assert!(
+        request.messages.iter().any(|message| {
+            message.content.contains(&MessageContent::ToolResult(LanguageModelToolResult {
+                tool_use_id: echo_tool_use.id.clone(),
+                tool_name: echo_tool_use.name.clone(),
+                is_error: false,
+                content: "hi".into(),
+                output: Some("hi".into()),
+            }))
+        }),
+        "the tool result should have been sent back to the model"
+    );
+}
+
+#[gpui::test]
+async fn test_interleaved_text_and_tool_uses_build_well_formed_messages(cx: &mut TestAppContext) {
+    let ThreadTest { model, thread, .. } = setup(cx, TestModel::Fake).await;
+    let fake_model = model.as_fake();
+
+    let echo_tool_use = |id: &str, text: &str| LanguageModelToolUse {
+        id: id.into(),
+        name: EchoTool::NAME.into(),
+        raw_input: json!({ "text": text }).to_string(),
+        input: json!({ "text": text }),
+        is_input_complete: true,
+        thought_signature: None,
+    };
+    fake_model.push_scripted_completion([
+        LanguageModelCompletionEvent::Text("First, ".into()),
+        LanguageModelCompletionEvent::ToolUse(echo_tool_use("tool_1", "a")),
+        LanguageModelCompletionEvent::Text("then ".into()),
+        LanguageModelCompletionEvent::ToolUse(echo_tool_use("tool_2", "b")),
+        LanguageModelCompletionEvent::Stop(StopReason::ToolUse),
+    ]);
+    fake_model.push_scripted_completion([
+        LanguageModelCompletionEvent::Text("Done.".into()),
+        LanguageModelCompletionEvent::Stop(StopReason::EndTurn),
+    ]);
+
+    thread
+        .update(cx, |thread, cx| {
+            thread.add_tool(EchoTool);
+            thread.send(UserMessageId::new(), ["Echo twice"], cx)
+        })
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+
+    let request = thread
+        .read_with(cx, |thread, cx| {
+            thread.build_completion_request(CompletionIntent::UserPrompt, cx)
+        })
+        .unwrap();
+    let messages = request
+        .messages
+        .iter()
+        .skip_while(|message| message.role != Role::Assistant)
+        .map(|message| {
+            let content = message
+                .content
+                .iter()
+                .map(|content| match content {
+                    MessageContent::Text(text) => format!("text: {text}"),
+                    MessageContent::ToolUse(tool_use) => format!("tool use: {}", tool_use.id),
+                    MessageContent::ToolResult(tool_result) => {
+                        format!("tool result: {}", tool_result.tool_use_id)
+                    }
+                    content => format!("{content:?}"),
+                })
+                .collect::<Vec<_>>();
+            (message.role, content)
+        })
+        .collect::<Vec<_>>();
+
+    // Both tool uses stay in the assistant message that requested them, and their
+    // results directly follow it, before the next assistant message.
+    assert_eq!(
+        messages,
+        vec![
+            (
+                Role::Assistant,
+                vec![
+                    "text: First, ".to_string(),
+                    "tool use: tool_1".to_string(),
+                    "text: then ".to_string(),
+                    "tool use: tool_2".to_string(),
+                ]
+            ),
+            (
+                Role::User,
+                vec![
+                    "tool result: tool_1".to_string(),
+                    "tool result: tool_2".to_string(),
+                ]
+            ),
+            (Role::Assistant, vec!["text: Done.".to_string()]),
+        ]
+    );
+}
+
+#[test]
+fn test_tool_results_follow_their_tool_uses() {
+    let tool_use = |id: &str| LanguageModelToolUse {
+        id: id.into(),
+        name: EchoTool::NAME.into(),
+        raw_input: "{}".into(),
+        input: json!({}),
+        is_input_complete: true,
+        thought_signature: None,
+    };
+    let tool_result = |id: &str| LanguageModelToolResult {
+        tool_use_id: id.into(),
+        tool_name: EchoTool::NAME.into(),
+        is_error: false,
+        content: id.into(),
+        output: None,
+    };
+    let mut message = AgentMessage {
+        content: vec![
+            AgentMessageContent::ToolUse(tool_use("a")),
+            AgentMessageContent::ToolUse(tool_use("b")),
+        ],
+        ..Default::default()
+    };
+
+    // Results are recorded in the order the tools finish in...
+    assert!(message.insert_tool_result(tool_result("b")));
+    assert!(message.insert_tool_result(tool_result("a")));
+    // ...and only for tool uses that belong to the message.
+    assert!(!message.insert_tool_result(tool_result("c")));
+
+    let request = message.to_request();
+    assert_eq!(request.len(), 2);
+    assert_eq!(request[0].role, Role::Assistant);
+    assert_eq!(request[1].role, Role::User);
+    let result_ids = request[1]
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            MessageContent::ToolResult(tool_result) => Some(tool_result.tool_use_id.to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(result_ids, ["a", "b"]);
+}
+
 async fn expect_tool_call(events: &mut UnboundedReceiver<Result<ThreadEvent>>) -> acp::ToolCall {
     let event = events
         .next()
@@ -2713,6 +3076,288 @@ async fn test_in_progress_send_canceled_by_next_send(cx: &mut TestAppContext) {
     assert_eq!(stop_events(events_2), vec![acp::StopReason::EndTurn]);
 }
 
+#[gpui::test]
+async fn test_cancel_handle_cancels_turn_from_another_task(cx: &mut TestAppContext) {
+    let ThreadTest { model, thread, .. } = setup(cx, TestModel::Fake).await;
+    let fake_model = model.as_fake();
+
+    let (events, cancel_handle) = thread
+        .update(cx, |thread, cx| {
+            thread.send_with_cancel_handle(UserMessageId::new(), ["Hello"], cx)
+        })
+        .unwrap();
+    cx.run_until_parked();
+    fake_model.send_last_completion_stream_text_chunk("Partial");
+    cx.run_until_parked();
+
+    cx.executor()
+        .spawn(async move { cancel_handle.cancel() })
+        .await;
+    cx.run_until_parked();
+
+    let events = events.collect::<Vec<_>>().await;
+    assert_eq!(stop_events(events), vec![acp::StopReason::Cancelled]);
+    thread.read_with(cx, |thread, _cx| {
+        assert!(thread.is_turn_complete());
+        let message = thread.last_received_or_pending_message().unwrap();
+        let agent_message = message.as_agent_message().unwrap();
+        assert_eq!(
+            agent_message.content,
+            vec![AgentMessageContent::Text("Partial".to_string())]
+        );
+    });
+
+    // A handle for a turn that already ended must not cancel the next one.
+    let (events, stale_handle) = thread
+        .update(cx, |thread, cx| {
+            thread.send_with_cancel_handle(UserMessageId::new(), ["Hello again"], cx)
+        })
+        .unwrap();
+    cx.run_until_parked();
+    fake_model.send_last_completion_stream_text_chunk("Done");
+    fake_model
+        .send_last_completion_stream_event(LanguageModelCompletionEvent::Stop(StopReason::EndTurn));
+    fake_model.end_last_completion_stream();
+    let events = events.collect::<Vec<_>>().await;
+    assert_eq!(stop_events(events), vec![acp::StopReason::EndTurn]);
+    stale_handle.cancel();
+    cx.run_until_parked();
+    thread.read_with(cx, |thread, _cx| assert!(thread.is_turn_complete()));
+}
+
+#[gpui::test]
+async fn test_dropping_thread_mid_turn_cancels_it(cx: &mut TestAppContext) {
+    let ThreadTest { model, thread, .. } = setup(cx, TestModel::Fake).await;
+    let fake_model = model.as_fake();
+
+    thread.update(cx, |thread, _| thread.add_tool(DelayTool));
+    let events = thread
+        .update(cx, |thread, cx| {
+            thread.send(UserMessageId::new(), ["Wait a second"], cx)
+        })
+        .unwrap();
+    cx.run_until_parked();
+    fake_model.send_last_completion_stream_event(LanguageModelCompletionEvent::ToolUse(
+        LanguageModelToolUse {
+            id: "delay_1".into(),
+            name: DelayTool::NAME.into(),
+            raw_input: json!({"ms": 1000}).to_string(),
+            input: json!({"ms": 1000}),
+            is_input_complete: true,
+            thought_signature: None,
+        },
+    ));
+    fake_model
+        .send_last_completion_stream_event(LanguageModelCompletionEvent::Stop(StopReason::ToolUse));
+    fake_model.end_last_completion_stream();
+    cx.run_until_parked();
+
+    drop(thread);
+    cx.update(|_| {});
+    cx.executor().advance_clock(Duration::from_secs(2));
+    cx.run_until_parked();
+
+    let events = events.collect::<Vec<_>>().await;
+    assert_eq!(stop_events(events), vec![acp::StopReason::Cancelled]);
+    assert_eq!(
+        fake_model.completion_count(),
+        0,
+        "the tool result shouldn't be sent back to the model"
+    );
+}
+
+#[gpui::test]
+async fn test_turn_summary_precedes_the_stop_event(cx: &mut TestAppContext) {
+    let ThreadTest { model, thread, .. } = setup(cx, TestModel::Fake).await;
+    let fake_model = model.as_fake();
+
+    thread.update(cx, |thread, _| thread.add_tool(EchoTool));
+    let events = thread
+        .update(cx, |thread, cx| {
+            thread.send(UserMessageId::new(), ["Echo hi"], cx)
+        })
+        .unwrap();
+    cx.run_until_parked();
+    let tool_request_usage = language_model::TokenUsage {
+        input_tokens: 100,
+        output_tokens: 20,
+        cache_creation_input_tokens: 0,
+        cache_read_input_tokens: 0,
+    };
+    fake_model.send_last_completion_stream_event(LanguageModelCompletionEvent::ToolUse(
+        LanguageModelToolUse {
+            id: "echo_1".into(),
+            name: EchoTool::NAME.into(),
+            raw_input: json!({"text": "hi"}).to_string(),
+            input: json!({"text": "hi"}),
+            is_input_complete: true,
+            thought_signature: None,
+        },
+    ));
+    fake_model.send_last_completion_stream_event(LanguageModelCompletionEvent::UsageUpdate(
+        tool_request_usage,
+    ));
+    fake_model
+        .send_last_completion_stream_event(LanguageModelCompletionEvent::Stop(StopReason::ToolUse));
+    fake_model.end_last_completion_stream();
+    cx.run_until_parked();
+
+    let final_request_usage = language_model::TokenUsage {
+        input_tokens: 120,
+        output_tokens: 8,
+        cache_creation_input_tokens: 0,
+        cache_read_input_tokens: 0,
+    };
+    fake_model.send_last_completion_stream_text_chunk("Done");
+    fake_model.send_last_completion_stream_event(LanguageModelCompletionEvent::UsageUpdate(
+        final_request_usage,
+    ));
+    fake_model
+        .send_last_completion_stream_event(LanguageModelCompletionEvent::Stop(StopReason::EndTurn));
+    fake_model.end_last_completion_stream();
+
+    let mut events = events.collect::<Vec<_>>().await;
+    assert!(
+        matches!(
+            events.pop(),
+            Some(Ok(ThreadEvent::Stop(acp::StopReason::EndTurn)))
+        ),
+        "the turn should still end with its stop event"
+    );
+    let Some(Ok(ThreadEvent::TurnSummary(summary))) = events.pop() else {
+        panic!("expected the summary right before the stop event");
+    };
+    assert_eq!(summary.stop_reason, acp::StopReason::EndTurn);
+    assert_eq!(summary.tool_calls, 1);
+    assert_eq!(
+        summary.token_usage,
+        tool_request_usage + final_request_usage,
+        "the usage of every request in the turn should be summed"
+    );
+}
+
+#[gpui::test]
+async fn test_resume_interrupted_turn(cx: &mut TestAppContext) {
+    let ThreadTest {
+        model,
+        thread,
+        project_context,
+        context_server_store,
+        ..
+    } = setup(cx, TestModel::Fake).await;
+    let fake_model = model.as_fake();
+
+    // Save the thread while the model is still responding.
+    let _events = thread
+        .update(cx, |thread, cx| {
+            thread.send(UserMessageId::new(), ["Use some tools"], cx)
+        })
+        .unwrap();
+    cx.run_until_parked();
+    fake_model.send_last_completion_stream_text_chunk("Let me check.");
+    cx.run_until_parked();
+    let mut db_thread = thread.read_with(cx, |thread, cx| thread.to_db(cx)).await;
+    let interrupted_turn = db_thread.interrupted_turn.as_ref().unwrap();
+    assert_eq!(
+        interrupted_turn.pending_message.as_ref().unwrap().content,
+        vec![AgentMessageContent::Text("Let me check.".into())]
+    );
+
+    // Simulate the model having requested two tools when Zed went away, one of
+    // which is idempotent and one of which isn't.
+    let echo_tool_use = LanguageModelToolUse {
+        id: "echo_1".into(),
+        name: EchoTool::NAME.into(),
+        raw_input: json!({"text": "hi"}).to_string(),
+        input: json!({"text": "hi"}),
+        is_input_complete: true,
+        thought_signature: None,
+    };
+    let infinite_tool_use = LanguageModelToolUse {
+        id: "infinite_1".into(),
+        name: InfiniteTool::NAME.into(),
+        raw_input: json!({}).to_string(),
+        input: json!({}),
+        is_input_complete: true,
+        thought_signature: None,
+    };
+    db_thread.interrupted_turn = Some(InterruptedTurn {
+        pending_message: Some(AgentMessage {
+            content: vec![
+                AgentMessageContent::Text("Let me check.".into()),
+                AgentMessageContent::ToolUse(echo_tool_use.clone()),
+                AgentMessageContent::ToolUse(infinite_tool_use.clone()),
+            ],
+            ..Default::default()
+        }),
+    });
+
+    let project = thread.read_with(cx, |thread, _| thread.project().clone());
+    let restored_thread = cx.new(|cx| {
+        let context_server_registry =
+            cx.new(|cx| ContextServerRegistry::new(context_server_store.clone(), cx));
+        let mut thread = Thread::from_db(
+            acp::SessionId::new(Arc::<str>::from("restored")),
+            db_thread,
+            project,
+            project_context.clone(),
+            context_server_registry,
+            Templates::new(),
+            cx,
+        );
+        thread.set_model(model.clone(), cx);
+        thread.add_tool(EchoTool);
+        thread.add_tool(InfiniteTool);
+        thread
+    });
+    assert!(restored_thread.read_with(cx, |thread, _| thread.has_interrupted_turn()));
+
+    let events = restored_thread
+        .update(cx, |thread, cx| thread.resume_interrupted_turn(cx))
+        .unwrap()
+        .unwrap();
+    cx.run_until_parked();
+
+    let completion = fake_model.pending_completions().pop().unwrap();
+    let tool_results = completion
+        .messages
+        .last()
+        .unwrap()
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            MessageContent::ToolResult(tool_result) => Some((
+                tool_result.tool_use_id.clone(),
+                tool_result.is_error,
+                tool_result.content.clone(),
+            )),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tool_results,
+        vec![
+            (echo_tool_use.id.clone(), false, "hi".into()),
+            (
+                infinite_tool_use.id.clone(),
+                true,
+                crate::thread::TOOL_INTERRUPTED_MESSAGE.into()
+            ),
+        ]
+    );
+
+    fake_model.send_last_completion_stream_text_chunk("All done.");
+    fake_model
+        .send_last_completion_stream_event(LanguageModelCompletionEvent::Stop(StopReason::EndTurn));
+    fake_model.end_last_completion_stream();
+    let events = events.collect::<Vec<_>>().await;
+    assert_eq!(stop_events(events), vec![acp::StopReason::EndTurn]);
+    restored_thread.read_with(cx, |thread, _| {
+        assert!(!thread.has_interrupted_turn());
+        assert!(thread.is_turn_complete());
+    });
+}
+
 #[gpui::test]
 async fn test_retry_cancelled_promptly_on_new_send(cx: &mut TestAppContext) {
     // Regression test: when a completion fails with a retryable error (e.g. upstream 500),
@@ -3754,7 +4399,7 @@ async fn test_send_max_retries_exceeded(cx: &mut TestAppContext) {
         .unwrap();
     cx.run_until_parked();
 
-    for _ in 0..crate::thread::MAX_RETRY_ATTEMPTS + 1 {
+    for _ in 0..crate::retry::MAX_RETRY_ATTEMPTS + 1 {
         fake_model.send_last_completion_stream_error(
             LanguageModelCompletionError::ServerOverloaded {
                 provider: LanguageModelProviderName::new("Anthropic"),
@@ -3781,9 +4426,9 @@ async fn test_send_max_retries_exceeded(cx: &mut TestAppContext) {
 
     assert_eq!(
         retry_events.len(),
-        crate::thread::MAX_RETRY_ATTEMPTS as usize
+        crate::retry::MAX_RETRY_ATTEMPTS as usize
     );
-    for i in 0..crate::thread::MAX_RETRY_ATTEMPTS as usize {
+    for i in 0..crate::retry::MAX_RETRY_ATTEMPTS as usize {
         assert_eq!(retry_events[i].attempt, i + 1);
     }
     assert_eq!(errors.len(), 1);
@@ -4025,6 +4670,39 @@ fn stop_events(result_events: Vec<Result<ThreadEvent>>) -> Vec<acp::StopReason>
         .collect()
 }
 
+/// The parts of a turn that tests usually care about, in the order they were emitted.
+#[derive(Debug, PartialEq)]
+enum TurnEvent {
+    Text(String),
+    ToolCall(String),
+    Stop(acp::StopReason),
+}
+
+/// Reduces a turn's events to its text, tool calls and stop reason.
+///
+/// Adjacent text chunks are merged, so assertions don't depend on how the
+/// model happened to chunk its output.
+fn turn_events(result_events: Vec<Result<ThreadEvent>>) -> Vec<TurnEvent> {
+    let mut turn_events = Vec::new();
+    for event in result_events {
+        match event.unwrap() {
+            ThreadEvent::AgentText(text) => {
+                if let Some(TurnEvent::Text(last_text)) = turn_events.last_mut() {
+                    last_text.push_str(&text);
+                } else {
+                    turn_events.push(TurnEvent::Text(text));
+                }
+            }
+            ThreadEvent::ToolCall(tool_call) => {
+                turn_events.push(TurnEvent::ToolCall(tool_call.title));
+            }
+            ThreadEvent::Stop(stop_reason) => turn_events.push(TurnEvent::Stop(stop_reason)),
+            _ => {}
+        }
+    }
+    turn_events
+}
+
 struct ThreadTest {
     model: Arc<dyn LanguageModel>,
     thread: Entity<Thread>,
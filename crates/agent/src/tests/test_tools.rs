@@ -206,6 +206,10 @@ impl AgentTool for EchoTool {
         acp::ToolKind::Other
     }
 
+    fn is_idempotent() -> bool {
+        true
+    }
+
     fn initial_title(
         &self,
         _input: Result<Self::Input, serde_json::Value>,
@@ -500,3 +504,100 @@ impl AgentTool for WordListTool {
         })
     }
 }
+
+#[derive(JsonSchema, Serialize, Deserialize)]
+pub struct WordCount {
+    pub count: usize,
+}
+
+#[derive(JsonSchema, Serialize, Deserialize)]
+pub struct MisreportingToolInput {}
+
+/// A tool that declares a word count as its output but returns plain text,
+/// used to test that outputs are validated against their schema.
+pub struct MisreportingTool;
+
+impl AgentTool for MisreportingTool {
+    type Input = MisreportingToolInput;
+    type Output = String;
+
+    const NAME: &'static str = "misreporting";
+
+    fn kind() -> acp::ToolKind {
+        acp::ToolKind::Other
+    }
+
+    fn initial_title(
+        &self,
+        _input: Result<Self::Input, serde_json::Value>,
+        _cx: &mut App,
+    ) -> SharedString {
+        "Count words".into()
+    }
+
+    fn output_schema() -> Option<schemars::Schema> {
+        Some(schemars::schema_for!(WordCount))
+    }
+
+    fn run(
+        self: Arc<Self>,
+        _input: ToolInput<Self::Input>,
+        _event_stream: ToolCallEventStream,
+        _cx: &mut App,
+    ) -> Task<Result<String, String>> {
+        Task::ready(Ok("three words here".to_string()))
+    }
+}
+
+/// A tool that adds two numbers without awaiting anything, used to test the
+/// synchronous tool path.
+#[derive(JsonSchema, Serialize, Deserialize)]
+pub struct AddToolInput {
+    pub a: i64,
+    pub b: i64,
+}
+
+pub struct AddTool;
+
+impl AgentTool for AddTool {
+    type Input = AddToolInput;
+    type Output = String;
+
+    const NAME: &'static str = "add";
+
+    fn kind() -> acp::ToolKind {
+        acp::ToolKind::Other
+    }
+
+    fn initial_title(
+        &self,
+        _input: Result<Self::Input, serde_json::Value>,
+        _cx: &mut App,
+    ) -> SharedString {
+        "Add numbers".into()
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: ToolInput<Self::Input>,
+        _event_stream: ToolCallEventStream,
+        cx: &mut App,
+    ) -> Task<Result<String, String>> {
+        cx.spawn(async move |_cx| {
+            let input = input
+                .recv()
+                .await
+                .map_err(|e| format!("Failed to receive tool input: {e}"))?;
+            Ok((input.a + input.b).to_string())
+        })
+    }
+
+    fn run_sync(
+        &self,
+        input: &Self::Input,
+        _event_stream: &ToolCallEventStream,
+        _cx: &mut App,
+    ) -> Option<Result<String, String>> {
+        Some(Ok((input.a + input.b).to_string()))
+    }
+}
@@ -1,3 +1,4 @@
+use crate::retry::{RetryStrategy, retry_with_backoff};
 use crate::{
     ContextServerRegistry, CopyPathTool, CreateDirectoryTool, DbLanguageModel, DbThread,
     DeletePathTool, DiagnosticsTool, EditFileTool, FetchTool, FindPathTool, GrepTool,
@@ -19,7 +20,6 @@ use agent_settings::{
 use anyhow::{Context as _, Result, anyhow};
 use chrono::{DateTime, Utc};
 use client::UserStore;
-use cloud_api_types::Plan;
 use collections::{HashMap, HashSet, IndexMap};
 use fs::Fs;
 use futures::{
@@ -48,22 +48,48 @@ use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use settings::{LanguageModelSelection, Settings, ToolPermissionMode, update_settings_file};
 use std::{
-    collections::BTreeMap,
-    marker::PhantomData,
-    ops::RangeInclusive,
-    path::Path,
-    rc::Rc,
-    sync::Arc,
-    time::{Duration, Instant},
+    cell::Cell, collections::BTreeMap, marker::PhantomData, ops::RangeInclusive, path::Path,
+    rc::Rc, sync::Arc, time::Instant,
 };
-use std::{fmt::Write, path::PathBuf};
+use std::{fmt::Write, path::PathBuf, time::Duration};
 use util::{ResultExt, debug_panic, markdown::MarkdownCodeBlock, paths::PathStyle};
 use uuid::Uuid;
 
 const TOOL_CANCELED_MESSAGE: &str = "Tool canceled by user";
+pub(crate) const TOOL_INTERRUPTED_MESSAGE: &str = "Tool call was interrupted before it finished and may have partially run. Check its effects before retrying it.";
 pub const MAX_TOOL_NAME_LENGTH: usize = 64;
 pub const MAX_SUBAGENT_DEPTH: u8 = 1;
 
+/// A turn that was still running when the thread was saved, e.g. because Zed
+/// quit or crashed before the model finished responding.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InterruptedTurn {
+    /// The agent message that was being streamed, including tool calls that
+    /// hadn't produced a result yet.
+    pub pending_message: Option<AgentMessage>,
+}
+
+/// Limits which of a thread's tools the model may use, on top of what the agent
+/// profile enables. Disallowed tools are left out of completion requests, and
+/// calls the model makes to them anyway are refused.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ToolRestrictions {
+    /// When set, only these tools are allowed.
+    pub allowed: Option<HashSet<SharedString>>,
+    /// Tools that are never allowed, even if they are also in `allowed`.
+    pub denied: HashSet<SharedString>,
+}
+
+impl ToolRestrictions {
+    pub fn allows(&self, tool_name: &str) -> bool {
+        !self.denied.contains(tool_name)
+            && self
+                .allowed
+                .as_ref()
+                .is_none_or(|allowed| allowed.contains(tool_name))
+    }
+}
+
 /// Context passed to a subagent thread for lifecycle management
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SubagentContext {
@@ -92,21 +118,6 @@ impl std::fmt::Display for PromptId {
     }
 }
 
-pub(crate) const MAX_RETRY_ATTEMPTS: u8 = 4;
-pub(crate) const BASE_RETRY_DELAY: Duration = Duration::from_secs(5);
-
-#[derive(Debug, Clone)]
-enum RetryStrategy {
-    ExponentialBackoff {
-        initial_delay: Duration,
-        max_attempts: u8,
-    },
-    Fixed {
-        delay: Duration,
-        max_attempts: u8,
-    },
-}
-
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Message {
     User(UserMessage),
@@ -570,7 +581,13 @@ impl AgentMessage {
             reasoning_details: None,
         };
 
-        for tool_result in self.tool_results.values() {
+        // Results follow in the order the tools were requested, regardless of the
+        // order they finished in, and only for tool uses this message made.
+        let tool_results = self.content.iter().filter_map(|content| match content {
+            AgentMessageContent::ToolUse(tool_use) => self.tool_results.get(&tool_use.id),
+            _ => None,
+        });
+        for tool_result in tool_results {
             let mut tool_result = tool_result.clone();
             // Surprisingly, the API fails if we return an empty string here.
             // It thinks we are sending a tool use without a tool result.
@@ -591,6 +608,24 @@ impl AgentMessage {
         }
         messages
     }
+
+    /// Records the result of one of this message's tool uses.
+    ///
+    /// Returns `false` without recording anything if the tool use isn't part of
+    /// this message, since models reject results that don't directly follow the
+    /// assistant message that requested them.
+    pub fn insert_tool_result(&mut self, tool_result: LanguageModelToolResult) -> bool {
+        let has_tool_use = self.content.iter().any(|content| match content {
+            AgentMessageContent::ToolUse(tool_use) => tool_use.id == tool_result.tool_use_id,
+            _ => false,
+        });
+        if !has_tool_use {
+            return false;
+        }
+        self.tool_results
+            .insert(tool_result.tool_use_id.clone(), tool_result);
+        true
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -663,6 +698,21 @@ pub enum ThreadEvent {
     SubagentSpawned(acp::SessionId),
     Retry(acp_thread::RetryStatus),
     Stop(acp::StopReason),
+    /// Sent right before the [`ThreadEvent::Stop`] of a turn that ran to
+    /// completion, since consumers stop reading once they see the stop.
+    TurnSummary(TurnSummary),
+}
+
+/// How a completed turn went, e.g. for rendering a "done in 3.2s, 4 tool calls"
+/// footer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnSummary {
+    pub stop_reason: acp::StopReason,
+    pub tool_calls: usize,
+    /// The token usage of every request made during the turn, as far as the
+    /// model reported it.
+    pub token_usage: language_model::TokenUsage,
+    pub duration: Duration,
 }
 
 #[derive(Debug)]
@@ -921,6 +971,14 @@ enum CompletionError {
     Other(#[from] anyhow::Error),
 }
 
+/// How a single completion request within a turn ended.
+enum RequestOutcome {
+    Cancelled,
+    EndTurn,
+    /// The model called tools, whose results have to be sent back to it.
+    ToolResults,
+}
+
 pub struct Thread {
     id: acp::SessionId,
     prompt_id: PromptId,
@@ -954,6 +1012,7 @@ pub struct Thread {
     thinking_enabled: bool,
     thinking_effort: Option<String>,
     speed: Option<Speed>,
+    tool_restrictions: ToolRestrictions,
     prompt_capabilities_tx: watch::Sender<acp::PromptCapabilities>,
     pub(crate) prompt_capabilities_rx: watch::Receiver<acp::PromptCapabilities>,
     pub(crate) project: Entity<Project>,
@@ -967,6 +1026,9 @@ pub struct Thread {
     ui_scroll_position: Option<gpui::ListOffset>,
     /// Weak references to running subagent threads for cancellation propagation
     running_subagents: Vec<WeakEntity<Thread>>,
+    /// A turn that was running when this thread was last saved and that can be
+    /// picked up again with [`Thread::resume_interrupted_turn`].
+    interrupted_turn: Option<InterruptedTurn>,
 }
 
 impl Thread {
@@ -1087,6 +1149,8 @@ impl Thread {
             draft_prompt: None,
             ui_scroll_position: None,
             running_subagents: Vec::new(),
+            interrupted_turn: None,
+            tool_restrictions: ToolRestrictions::default(),
         }
     }
 
@@ -1101,6 +1165,7 @@ impl Thread {
         self.thinking_effort = parent.thinking_effort.clone();
         self.summarization_model = parent.summarization_model.clone();
         self.profile_id = parent.profile_id.clone();
+        self.tool_restrictions = parent.tool_restrictions.clone();
     }
 
     pub fn id(&self) -> &acp::SessionId {
@@ -1319,6 +1384,8 @@ impl Thread {
                 offset_in_item: gpui::px(sp.offset_in_item),
             }),
             running_subagents: Vec::new(),
+            interrupted_turn: db_thread.interrupted_turn,
+            tool_restrictions: ToolRestrictions::default(),
         }
     }
 
@@ -1349,6 +1416,13 @@ impl Thread {
                     offset_in_item: lo.offset_in_item.as_f32(),
                 }
             }),
+            interrupted_turn: self
+                .running_turn
+                .as_ref()
+                .map(|_| InterruptedTurn {
+                    pending_message: self.pending_message.clone(),
+                })
+                .or_else(|| self.interrupted_turn.clone()),
         };
 
         cx.background_spawn(async move {
@@ -1497,6 +1571,26 @@ impl Thread {
         cx.notify();
     }
 
+    pub fn tool_restrictions(&self) -> &ToolRestrictions {
+        &self.tool_restrictions
+    }
+
+    pub fn set_tool_restrictions(
+        &mut self,
+        tool_restrictions: ToolRestrictions,
+        cx: &mut Context<Self>,
+    ) {
+        for subagent in &self.running_subagents {
+            subagent
+                .update(cx, |thread, cx| {
+                    thread.set_tool_restrictions(tool_restrictions.clone(), cx)
+                })
+                .ok();
+        }
+        self.tool_restrictions = tool_restrictions;
+        cx.notify();
+    }
+
     pub fn last_message(&self) -> Option<&Message> {
         self.messages.last()
     }
@@ -1638,6 +1732,9 @@ impl Thread {
 
         self.request_token_usage
             .insert(last_user_message.id.clone(), update);
+        if let Some(turn) = self.running_turn.as_mut() {
+            turn.current_request_token_usage = update;
+        }
         cx.emit(TokenUsageUpdated(self.latest_token_usage()));
         cx.notify();
     }
@@ -1666,6 +1763,33 @@ impl Thread {
         Ok(())
     }
 
+    /// Summarizes the turn that was started by the user message at `message_ix`.
+    fn turn_summary(
+        &self,
+        message_ix: usize,
+        stop_reason: acp::StopReason,
+        duration: Duration,
+    ) -> TurnSummary {
+        let tool_calls = self
+            .messages
+            .iter()
+            .skip(message_ix)
+            .filter_map(|message| message.as_agent_message())
+            .flat_map(|message| &message.content)
+            .filter(|content| matches!(content, AgentMessageContent::ToolUse(_)))
+            .count();
+        TurnSummary {
+            stop_reason,
+            tool_calls,
+            token_usage: self
+                .running_turn
+                .as_ref()
+                .map(RunningTurn::token_usage)
+                .unwrap_or_default(),
+            duration,
+        }
+    }
+
     pub fn latest_request_token_usage(&self) -> Option<language_model::TokenUsage> {
         let last_user_message = self.last_user_message()?;
         let tokens = self.request_token_usage.get(&last_user_message.id)?;
@@ -1746,6 +1870,31 @@ impl Thread {
         self.run_turn(cx)
     }
 
+    pub fn has_interrupted_turn(&self) -> bool {
+        self.interrupted_turn.is_some()
+    }
+
+    /// Continues a turn that was still running when the thread was saved.
+    ///
+    /// Tool calls that hadn't finished are run again if their tool is idempotent.
+    /// Other tools may already have had side effects, so their calls are reported
+    /// to the model as interrupted instead. Returns `None` if there was no
+    /// interrupted turn.
+    pub fn resume_interrupted_turn(
+        &mut self,
+        cx: &mut Context<Self>,
+    ) -> Result<Option<mpsc::UnboundedReceiver<Result<ThreadEvent>>>> {
+        self.model().context("No language model configured")?;
+        let Some(interrupted_turn) = self.interrupted_turn.take() else {
+            return Ok(None);
+        };
+
+        log::info!("Resuming interrupted turn");
+        self.advance_prompt_id();
+        self.start_turn(interrupted_turn.pending_message, cx)
+            .map(Some)
+    }
+
     /// Sending a message results in the model streaming a response, which could include tool calls.
     /// After calling tools, the model will stops and waits for any outstanding tool calls to be completed and their results sent.
     /// The returned channel will report all the occurrences in which the model stops before erroring or ending its turn.
@@ -1768,6 +1917,37 @@ impl Thread {
         self.send_existing(cx)
     }
 
+    /// Like [`Self::send`], but also returns a [`CancelHandle`] that can stop the
+    /// resulting turn without access to the thread.
+    pub fn send_with_cancel_handle<T>(
+        &mut self,
+        id: UserMessageId,
+        content: impl IntoIterator<Item = T>,
+        cx: &mut Context<Self>,
+    ) -> Result<(mpsc::UnboundedReceiver<Result<ThreadEvent>>, CancelHandle)>
+    where
+        T: Into<UserMessageContent>,
+    {
+        let events = self.send(id, content, cx)?;
+        Ok((events, self.cancel_handle(cx)))
+    }
+
+    /// Returns a handle that cancels the currently running turn. The handle does
+    /// nothing if there is no running turn, or once that turn has ended.
+    pub fn cancel_handle(&mut self, cx: &mut Context<Self>) -> CancelHandle {
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        if let Some(running_turn) = self.running_turn.as_mut() {
+            running_turn
+                .cancel_handle_tasks
+                .push(cx.spawn(async move |this, cx| {
+                    if cancel_rx.await.is_ok() {
+                        this.update(cx, |this, cx| this.cancel(cx).detach()).ok();
+                    }
+                }));
+        }
+        CancelHandle(cancel_tx)
+    }
+
     pub fn send_existing(
         &mut self,
         cx: &mut Context<Self>,
@@ -1821,12 +2001,21 @@ impl Thread {
     fn run_turn(
         &mut self,
         cx: &mut Context<Self>,
+    ) -> Result<mpsc::UnboundedReceiver<Result<ThreadEvent>>> {
+        self.start_turn(None, cx)
+    }
+
+    fn start_turn(
+        &mut self,
+        interrupted_message: Option<AgentMessage>,
+        cx: &mut Context<Self>,
     ) -> Result<mpsc::UnboundedReceiver<Result<ThreadEvent>>> {
         // Flush the old pending message synchronously before cancelling,
         // to avoid a race where the detached cancel task might flush the NEW
         // turn's pending message instead of the old one.
         self.flush_pending_message(cx);
         self.cancel(cx).detach();
+        self.interrupted_turn = None;
 
         let (events_tx, events_rx) = mpsc::unbounded::<Result<ThreadEvent>>();
         let event_stream = ThreadEventStream(events_tx);
@@ -1838,12 +2027,27 @@ impl Thread {
             tools: self.enabled_tools(cx),
             cancellation_tx,
             streaming_tool_inputs: HashMap::default(),
+            cancel_handle_tasks: Vec::new(),
+            finished_requests_token_usage: TokenUsage::default(),
+            current_request_token_usage: TokenUsage::default(),
             _task: cx.spawn(async move |this, cx| {
                 log::debug!("Starting agent turn execution");
+                let started_at = cx.background_executor().now();
 
-                let turn_result =
-                    Self::run_turn_internal(&this, &event_stream, cancellation_rx.clone(), cx)
-                        .await;
+                let turn_result = async {
+                    if let Some(message) = interrupted_message {
+                        Self::finish_interrupted_tool_calls(
+                            &this,
+                            message,
+                            &event_stream,
+                            cancellation_rx.clone(),
+                            cx,
+                        )
+                        .await?;
+                    }
+                    Self::run_turn_internal(&this, &event_stream, cancellation_rx.clone(), cx).await
+                }
+                .await;
 
                 // Check if we were cancelled - if so, cancel() already took running_turn
                 // and we shouldn't touch it (it might be a NEW turn now)
@@ -1855,26 +2059,35 @@ impl Thread {
 
                 _ = this.update(cx, |this, cx| this.flush_pending_message(cx));
 
-                match turn_result {
+                let stop_reason = match turn_result {
                     Ok(()) => {
                         log::debug!("Turn execution completed");
-                        event_stream.send_stop(acp::StopReason::EndTurn);
+                        Some(acp::StopReason::EndTurn)
                     }
                     Err(error) => {
                         log::error!("Turn execution failed: {:?}", error);
                         match error.downcast::<CompletionError>() {
                             Ok(CompletionError::Refusal) => {
-                                event_stream.send_stop(acp::StopReason::Refusal);
                                 _ = this.update(cx, |this, _| this.messages.truncate(message_ix));
+                                Some(acp::StopReason::Refusal)
                             }
-                            Ok(CompletionError::MaxTokens) => {
-                                event_stream.send_stop(acp::StopReason::MaxTokens);
-                            }
+                            Ok(CompletionError::MaxTokens) => Some(acp::StopReason::MaxTokens),
                             Ok(CompletionError::Other(error)) | Err(error) => {
                                 event_stream.send_error(error);
+                                None
                             }
                         }
                     }
+                };
+
+                if let Some(stop_reason) = stop_reason {
+                    let duration = cx.background_executor().now() - started_at;
+                    if let Ok(summary) = this.read_with(cx, |this, _| {
+                        this.turn_summary(message_ix, stop_reason, duration)
+                    }) {
+                        event_stream.send_turn_summary(summary);
+                    }
+                    event_stream.send_stop(stop_reason);
                 }
 
                 _ = this.update(cx, |this, _| this.running_turn.take());
@@ -1883,211 +2096,321 @@ impl Thread {
         Ok(events_rx)
     }
 
+    /// Restores the pending message of an interrupted turn and supplies a result
+    /// for each of its tool calls that didn't finish before the interruption.
+    async fn finish_interrupted_tool_calls(
+        this: &WeakEntity<Self>,
+        mut message: AgentMessage,
+        event_stream: &ThreadEventStream,
+        cancellation_rx: watch::Receiver<bool>,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        let tool_results = this.update(cx, |this, cx| {
+            let mut unfinished_tool_uses = Vec::new();
+            message.content.retain(|content| match content {
+                AgentMessageContent::ToolUse(tool_use)
+                    if !message.tool_results.contains_key(&tool_use.id) =>
+                {
+                    unfinished_tool_uses.push(tool_use.clone());
+                    false
+                }
+                _ => true,
+            });
+            this.pending_message = Some(message);
+
+            unfinished_tool_uses
+                .into_iter()
+                .filter_map(|tool_use| {
+                    let can_rerun = tool_use.is_input_complete
+                        && this
+                            .tool(tool_use.name.as_ref())
+                            .is_some_and(|tool| tool.is_idempotent());
+                    if can_rerun {
+                        return this.handle_tool_use_event(
+                            tool_use,
+                            event_stream,
+                            cancellation_rx.clone(),
+                            cx,
+                        );
+                    }
+
+                    this.pending_message()
+                        .content
+                        .push(AgentMessageContent::ToolUse(tool_use.clone()));
+                    Some(Task::ready(LanguageModelToolResult {
+                        tool_use_id: tool_use.id,
+                        tool_name: tool_use.name,
+                        is_error: true,
+                        content: LanguageModelToolResultContent::Text(
+                            TOOL_INTERRUPTED_MESSAGE.into(),
+                        ),
+                        output: None,
+                    }))
+                })
+                .collect::<Vec<_>>()
+        })?;
+
+        for tool_result in tool_results {
+            let tool_result = tool_result.await;
+            Self::process_tool_result(this, event_stream, cx, tool_result)?;
+        }
+
+        this.update(cx, |this, cx| this.flush_pending_message(cx))
+    }
+
     async fn run_turn_internal(
         this: &WeakEntity<Self>,
         event_stream: &ThreadEventStream,
-        mut cancellation_rx: watch::Receiver<bool>,
+        cancellation_rx: watch::Receiver<bool>,
         cx: &mut AsyncApp,
     ) -> Result<()> {
-        let mut attempt = 0;
         let mut intent = CompletionIntent::UserPrompt;
         loop {
-            // Re-read the model and refresh tools on each iteration so that
-            // mid-turn changes (e.g. the user switches model, toggles tools,
-            // or changes profile) take effect between tool-call rounds.
-            let (model, request) = this.update(cx, |this, cx| {
-                let model = this.model.clone().context("No language model configured")?;
-                this.refresh_turn_tools(cx);
-                let request = this.build_completion_request(intent, cx)?;
-                anyhow::Ok((model, request))
-            })??;
-
-            telemetry::event!(
-                "Agent Thread Completion",
-                thread_id = this.read_with(cx, |this, _| this.id.to_string())?,
-                parent_thread_id = this.read_with(cx, |this, _| this
-                    .parent_thread_id()
-                    .map(|id| id.to_string()))?,
-                prompt_id = this.read_with(cx, |this, _| this.prompt_id.to_string())?,
-                model = model.telemetry_id(),
-                model_provider = model.provider_id().to_string(),
-                attempt
-            );
-
-            log::debug!("Calling model.stream_completion, attempt {}", attempt);
-
-            let (mut events, mut error) = match model.stream_completion(request, cx).await {
-                Ok(events) => (events.fuse(), None),
-                Err(err) => (stream::empty().boxed().fuse(), Some(err)),
-            };
-            let mut tool_results: FuturesUnordered<Task<LanguageModelToolResult>> =
-                FuturesUnordered::new();
-            let mut early_tool_results: Vec<LanguageModelToolResult> = Vec::new();
-            let mut cancelled = false;
-            loop {
-                // Race between getting the first event, tool completion, and cancellation.
-                let first_event = futures::select! {
-                    event = events.next().fuse() => event,
-                    tool_result = futures::StreamExt::select_next_some(&mut tool_results) => {
-                        let is_error = tool_result.is_error;
-                        let is_still_streaming = this
-                            .read_with(cx, |this, _cx| {
-                                this.running_turn
-                                    .as_ref()
-                                    .and_then(|turn| turn.streaming_tool_inputs.get(&tool_result.tool_use_id))
-                                    .map_or(false, |inputs| !inputs.has_received_final())
-                            })
-                            .unwrap_or(false);
-
-                        early_tool_results.push(tool_result);
-
-                        // Only break if the tool errored and we are still
-                        // streaming the input of the tool. If the tool errored
-                        // but we are no longer streaming its input (i.e. there
-                        // are parallel tool calls) we want to continue
-                        // processing those tool inputs.
-                        if is_error && is_still_streaming {
-                            break;
-                        }
-                        continue;
+            let executor = cx.background_executor().clone();
+            let can_retry = Cell::new(false);
+            let mut attempt = 0;
+            let outcome = retry_with_backoff(
+                &executor,
+                Some(cancellation_rx.clone()),
+                |error: &anyhow::Error| {
+                    if !can_retry.get() {
+                        return None;
                     }
-                    _ = cancellation_rx.changed().fuse() => {
-                        if *cancellation_rx.borrow() {
-                            cancelled = true;
-                            break;
+                    error
+                        .downcast_ref::<LanguageModelCompletionError>()
+                        .and_then(RetryStrategy::for_completion_error)
+                },
+                |error, retry| {
+                    event_stream.send_retry(acp_thread::RetryStatus {
+                        last_error: error.to_string().into(),
+                        attempt: retry.attempt as usize,
+                        max_attempts: retry.max_attempts as usize,
+                        started_at: Instant::now(),
+                        duration: retry.delay,
+                    });
+                },
+                async || -> Result<RequestOutcome> {
+                    can_retry.set(this.update(cx, |this, cx| {
+                        if attempt > 0
+                            && let Some(Message::Agent(message)) = this.messages.last()
+                            && message.tool_results.is_empty()
+                        {
+                            intent = CompletionIntent::UserPrompt;
+                            this.messages.push(Message::Resume);
                         }
-                        continue;
+                        this.can_retry_completions(cx)
+                    })?);
+                    let outcome = Self::run_completion_request(
+                        this,
+                        event_stream,
+                        intent,
+                        attempt,
+                        cancellation_rx.clone(),
+                        cx,
+                    )
+                    .await;
+                    attempt += 1;
+                    outcome
+                },
+            )
+            .await?;
+
+            match outcome {
+                RequestOutcome::Cancelled | RequestOutcome::EndTurn => return Ok(()),
+                RequestOutcome::ToolResults => {
+                    let has_queued = this.update(cx, |this, _| this.has_queued_message())?;
+                    if has_queued {
+                        log::debug!("Queued message found, ending turn at message boundary");
+                        return Ok(());
                     }
-                };
-                let Some(first_event) = first_event else {
-                    break;
-                };
-
-                // Collect all immediately available events to process as a batch
-                let mut batch = vec![first_event];
-                while let Some(event) = events.next().now_or_never().flatten() {
-                    batch.push(event);
+                    intent = CompletionIntent::ToolResults;
                 }
+            }
+        }
+    }
 
-                // Process the batch in a single update
-                let batch_result = this.update(cx, |this, cx| {
-                    let mut batch_tool_results = Vec::new();
-                    let mut batch_error = None;
-
-                    for event in batch {
-                        log::trace!("Received completion event: {:?}", event);
-                        match event {
-                            Ok(event) => {
-                                match this.handle_completion_event(
-                                    event,
-                                    event_stream,
-                                    cancellation_rx.clone(),
-                                    cx,
-                                ) {
-                                    Ok(Some(task)) => batch_tool_results.push(task),
-                                    Ok(None) => {}
-                                    Err(err) => {
-                                        batch_error = Some(err);
-                                        break;
-                                    }
-                                }
-                            }
-                            Err(err) => {
-                                batch_error = Some(err.into());
-                                break;
-                            }
-                        }
-                    }
+    /// Sends a single completion request and runs the tools it calls. Errors
+    /// that may go away when the request is sent again are returned as
+    /// [`LanguageModelCompletionError`]s, after the partial response has been
+    /// kept in the thread.
+    async fn run_completion_request(
+        this: &WeakEntity<Self>,
+        event_stream: &ThreadEventStream,
+        intent: CompletionIntent,
+        attempt: u8,
+        mut cancellation_rx: watch::Receiver<bool>,
+        cx: &mut AsyncApp,
+    ) -> Result<RequestOutcome> {
+        // Re-read the model and refresh tools for each request so that
+        // mid-turn changes (e.g. the user switches model, toggles tools,
+        // or changes profile) take effect between tool-call rounds.
+        let (model, request) = this.update(cx, |this, cx| {
+            let model = this.model.clone().context("No language model configured")?;
+            if let Some(turn) = this.running_turn.as_mut() {
+                turn.start_request();
+            }
+            this.refresh_turn_tools(cx);
+            let request = this.build_completion_request(intent, cx)?;
+            anyhow::Ok((model, request))
+        })??;
+
+        telemetry::event!(
+            "Agent Thread Completion",
+            thread_id = this.read_with(cx, |this, _| this.id.to_string())?,
+            parent_thread_id = this.read_with(cx, |this, _| this
+                .parent_thread_id()
+                .map(|id| id.to_string()))?,
+            prompt_id = this.read_with(cx, |this, _| this.prompt_id.to_string())?,
+            model = model.telemetry_id(),
+            model_provider = model.provider_id().to_string(),
+            attempt
+        );
 
-                    cx.notify();
-                    (batch_tool_results, batch_error)
-                })?;
+        log::debug!("Calling model.stream_completion, attempt {}", attempt);
 
-                tool_results.extend(batch_result.0);
-                if let Some(err) = batch_result.1 {
-                    error = Some(err.downcast()?);
-                    break;
-                }
-            }
+        let (mut events, mut error) = match model.stream_completion(request, cx).await {
+            Ok(events) => (events.fuse(), None),
+            Err(err) => (stream::empty().boxed().fuse(), Some(err)),
+        };
+        let mut tool_results: FuturesUnordered<Task<LanguageModelToolResult>> =
+            FuturesUnordered::new();
+        let mut early_tool_results: Vec<LanguageModelToolResult> = Vec::new();
+        let mut cancelled = false;
+        loop {
+            // Race between getting the first event, tool completion, and cancellation.
+            let first_event = futures::select! {
+                event = events.next().fuse() => event,
+                tool_result = futures::StreamExt::select_next_some(&mut tool_results) => {
+                    let is_error = tool_result.is_error;
+                    let is_still_streaming = this
+                        .read_with(cx, |this, _cx| {
+                            this.running_turn
+                                .as_ref()
+                                .and_then(|turn| turn.streaming_tool_inputs.get(&tool_result.tool_use_id))
+                                .map_or(false, |inputs| !inputs.has_received_final())
+                        })
+                        .unwrap_or(false);
+
+                    early_tool_results.push(tool_result);
 
-            // Drop the stream to release the rate limit permit before tool execution.
-            // The stream holds a semaphore guard that limits concurrent requests.
-            // Without this, the permit would be held during potentially long-running
-            // tool execution, which could cause deadlocks when tools spawn subagents
-            // that need their own permits.
-            drop(events);
-
-            // Drop streaming tool input senders that never received their final input.
-            // This prevents deadlock when the LLM stream ends (e.g. because of an error)
-            // before sending a tool use with `is_input_complete: true`.
-            this.update(cx, |this, _cx| {
-                if let Some(running_turn) = this.running_turn.as_mut() {
-                    if running_turn.streaming_tool_inputs.is_empty() {
-                        return;
+                    // Only break if the tool errored and we are still
+                    // streaming the input of the tool. If the tool errored
+                    // but we are no longer streaming its input (i.e. there
+                    // are parallel tool calls) we want to continue
+                    // processing those tool inputs.
+                    if is_error && is_still_streaming {
+                        break;
                     }
-                    log::warn!("Dropping partial tool inputs because the stream ended");
-                    running_turn.streaming_tool_inputs.drain();
+                    continue;
                 }
-            })?;
-
-            let end_turn = tool_results.is_empty() && early_tool_results.is_empty();
+                _ = cancellation_rx.changed().fuse() => {
+                    if *cancellation_rx.borrow() {
+                        cancelled = true;
+                        break;
+                    }
+                    continue;
+                }
+            };
+            let Some(first_event) = first_event else {
+                break;
+            };
 
-            for tool_result in early_tool_results {
-                Self::process_tool_result(this, event_stream, cx, tool_result)?;
-            }
-            while let Some(tool_result) = tool_results.next().await {
-                Self::process_tool_result(this, event_stream, cx, tool_result)?;
+            // Collect all immediately available events to process as a batch
+            let mut batch = vec![first_event];
+            while let Some(event) = events.next().now_or_never().flatten() {
+                batch.push(event);
             }
 
-            this.update(cx, |this, cx| {
-                this.flush_pending_message(cx);
-                if this.title.is_none() && this.pending_title_generation.is_none() {
-                    this.generate_title(cx);
+            // Process the batch in a single update
+            let batch_result = this.update(cx, |this, cx| {
+                let mut batch_tool_results = Vec::new();
+                let mut batch_error = None;
+
+                for event in batch {
+                    log::trace!("Received completion event: {:?}", event);
+                    match event {
+                        Ok(event) => {
+                            match this.handle_completion_event(
+                                event,
+                                event_stream,
+                                cancellation_rx.clone(),
+                                cx,
+                            ) {
+                                Ok(Some(task)) => batch_tool_results.push(task),
+                                Ok(None) => {}
+                                Err(err) => {
+                                    batch_error = Some(err);
+                                    break;
+                                }
+                            }
+                        }
+                        Err(err) => {
+                            batch_error = Some(err.into());
+                            break;
+                        }
+                    }
                 }
+
+                cx.notify();
+                (batch_tool_results, batch_error)
             })?;
 
-            if cancelled {
-                log::debug!("Turn cancelled by user, exiting");
-                return Ok(());
+            tool_results.extend(batch_result.0);
+            if let Some(err) = batch_result.1 {
+                error = Some(err.downcast()?);
+                break;
             }
+        }
 
-            if let Some(error) = error {
-                attempt += 1;
-                let retry = this.update(cx, |this, cx| {
-                    let user_store = this.user_store.read(cx);
-                    this.handle_completion_error(error, attempt, user_store.plan())
-                })??;
-                let timer = cx.background_executor().timer(retry.duration);
-                event_stream.send_retry(retry);
-                futures::select! {
-                    _ = timer.fuse() => {}
-                    _ = cancellation_rx.changed().fuse() => {
-                        if *cancellation_rx.borrow() {
-                            log::debug!("Turn cancelled during retry delay, exiting");
-                            return Ok(());
-                        }
-                    }
-                }
-                this.update(cx, |this, _cx| {
-                    if let Some(Message::Agent(message)) = this.messages.last() {
-                        if message.tool_results.is_empty() {
-                            intent = CompletionIntent::UserPrompt;
-                            this.messages.push(Message::Resume);
-                        }
-                    }
-                })?;
-            } else if end_turn {
-                return Ok(());
-            } else {
-                let has_queued = this.update(cx, |this, _| this.has_queued_message())?;
-                if has_queued {
-                    log::debug!("Queued message found, ending turn at message boundary");
-                    return Ok(());
+        // Drop the stream to release the rate limit permit before tool execution.
+        // The stream holds a semaphore guard that limits concurrent requests.
+        // Without this, the permit would be held during potentially long-running
+        // tool execution, which could cause deadlocks when tools spawn subagents
+        // that need their own permits.
+        drop(events);
+
+        // Drop streaming tool input senders that never received their final input.
+        // This prevents deadlock when the LLM stream ends (e.g. because of an error)
+        // before sending a tool use with `is_input_complete: true`.
+        this.update(cx, |this, _cx| {
+            if let Some(running_turn) = this.running_turn.as_mut() {
+                if running_turn.streaming_tool_inputs.is_empty() {
+                    return;
                 }
-                intent = CompletionIntent::ToolResults;
-                attempt = 0;
+                log::warn!("Dropping partial tool inputs because the stream ended");
+                running_turn.streaming_tool_inputs.drain();
             }
+        })?;
+
+        let end_turn = tool_results.is_empty() && early_tool_results.is_empty();
+
+        for tool_result in early_tool_results {
+            Self::process_tool_result(this, event_stream, cx, tool_result)?;
+        }
+        while let Some(tool_result) = tool_results.next().await {
+            Self::process_tool_result(this, event_stream, cx, tool_result)?;
+        }
+
+        this.update(cx, |this, cx| {
+            this.flush_pending_message(cx);
+            if this.title.is_none() && this.pending_title_generation.is_none() {
+                this.generate_title(cx);
+            }
+        })?;
+
+        if cancelled {
+            log::debug!("Turn cancelled by user, exiting");
+            return Ok(RequestOutcome::Cancelled);
+        }
+
+        if let Some(error) = error {
+            return Err(error.into());
         }
+        Ok(if end_turn {
+            RequestOutcome::EndTurn
+        } else {
+            RequestOutcome::ToolResults
+        })
     }
 
     fn process_tool_result(
@@ -2110,62 +2433,23 @@ impl Thread {
             None,
         );
         this.update(cx, |this, _cx| {
-            this.pending_message()
-                .tool_results
-                .insert(tool_result.tool_use_id.clone(), tool_result)
+            let tool_use_id = tool_result.tool_use_id.clone();
+            if !this.pending_message().insert_tool_result(tool_result) {
+                log::error!(
+                    "Dropping result for tool use {tool_use_id} missing from the pending message"
+                );
+            }
         })?;
         Ok(())
     }
 
-    fn handle_completion_error(
-        &mut self,
-        error: LanguageModelCompletionError,
-        attempt: u8,
-        plan: Option<Plan>,
-    ) -> Result<acp_thread::RetryStatus> {
+    /// Whether failed completion requests may be retried automatically. Zed's
+    /// hosted models are only retried for users with a plan.
+    pub(crate) fn can_retry_completions(&self, cx: &App) -> bool {
         let Some(model) = self.model.as_ref() else {
-            return Err(anyhow!(error));
-        };
-
-        let auto_retry = if model.provider_id() == ZED_CLOUD_PROVIDER_ID {
-            plan.is_some()
-        } else {
-            true
-        };
-
-        if !auto_retry {
-            return Err(anyhow!(error));
-        }
-
-        let Some(strategy) = Self::retry_strategy_for(&error) else {
-            return Err(anyhow!(error));
+            return false;
         };
-
-        let max_attempts = match &strategy {
-            RetryStrategy::ExponentialBackoff { max_attempts, .. } => *max_attempts,
-            RetryStrategy::Fixed { max_attempts, .. } => *max_attempts,
-        };
-
-        if attempt > max_attempts {
-            return Err(anyhow!(error));
-        }
-
-        let delay = match &strategy {
-            RetryStrategy::ExponentialBackoff { initial_delay, .. } => {
-                let delay_secs = initial_delay.as_secs() * 2u64.pow((attempt - 1) as u32);
-                Duration::from_secs(delay_secs)
-            }
-            RetryStrategy::Fixed { delay, .. } => *delay,
-        };
-        log::debug!("Retry attempt {attempt} with delay {delay:?}");
-
-        Ok(acp_thread::RetryStatus {
-            last_error: error.to_string().into(),
-            attempt: attempt as usize,
-            max_attempts: max_attempts as usize,
-            started_at: Instant::now(),
-            duration: delay,
-        })
+        model.provider_id() != ZED_CLOUD_PROVIDER_ID || self.user_store.read(cx).plan().is_some()
     }
 
     /// A helper method that's called on every streamed completion event.
@@ -2307,6 +2591,20 @@ impl Thread {
 
         self.send_or_update_tool_use(&tool_use, title, kind, event_stream);
 
+        if !self.tool_restrictions.allows(&tool_use.name) {
+            if !tool_use.is_input_complete {
+                return None;
+            }
+            let content = format!("The {} tool is not allowed in this context", tool_use.name);
+            return Some(Task::ready(LanguageModelToolResult {
+                content: LanguageModelToolResultContent::Text(Arc::from(content)),
+                tool_use_id: tool_use.id,
+                tool_name: tool_use.name,
+                is_error: true,
+                output: None,
+            }));
+        }
+
         let Some(tool) = tool else {
             let content = format!("No tool named {} exists", tool_use.name);
             return Some(Task::ready(LanguageModelToolResult {
@@ -2747,6 +3045,10 @@ impl Thread {
             turn.tools
                 .iter()
                 .filter_map(|(tool_name, tool)| {
+                    if !self.tool_restrictions.allows(tool_name) {
+                        log::trace!("Excluding restricted tool: {}", tool_name);
+                        return None;
+                    }
                     log::trace!("Including tool: {}", tool_name);
                     Some(LanguageModelRequestTool {
                         name: tool_name.to_string(),
@@ -2960,12 +3262,17 @@ impl Thread {
             self.messages.len()
         );
 
+        // Keep the system prompt from crowding out the conversation.
+        let system_prompt_budget = self
+            .model
+            .as_ref()
+            .map_or(u64::MAX, |model| model.max_token_count() / 4);
         let system_prompt = SystemPromptTemplate {
             project: self.project_context.read(cx),
             available_tools,
             model_name: self.model.as_ref().map(|m| m.name().0.to_string()),
         }
-        .render(&self.templates)
+        .render_within_budget(&self.templates, system_prompt_budget)
         .context("failed to build system prompt")
         .expect("Invalid template");
         let mut messages = vec![LanguageModelRequestMessage {
@@ -3014,110 +3321,6 @@ impl Thread {
     fn advance_prompt_id(&mut self) {
         self.prompt_id = PromptId::new();
     }
-
-    fn retry_strategy_for(error: &LanguageModelCompletionError) -> Option<RetryStrategy> {
-        use LanguageModelCompletionError::*;
-        use http_client::StatusCode;
-
-        // General strategy here:
-        // - If retrying won't help (e.g. invalid API key or payload too large), return None so we don't retry at all.
-        // - If it's a time-based issue (e.g. server overloaded, rate limit exceeded), retry up to 4 times with exponential backoff.
-        // - If it's an issue that *might* be fixed by retrying (e.g. internal server error), retry up to 3 times.
-        match error {
-            HttpResponseError {
-                status_code: StatusCode::TOO_MANY_REQUESTS,
-                ..
-            } => Some(RetryStrategy::ExponentialBackoff {
-                initial_delay: BASE_RETRY_DELAY,
-                max_attempts: MAX_RETRY_ATTEMPTS,
-            }),
-            ServerOverloaded { retry_after, .. } | RateLimitExceeded { retry_after, .. } => {
-                Some(RetryStrategy::Fixed {
-                    delay: retry_after.unwrap_or(BASE_RETRY_DELAY),
-                    max_attempts: MAX_RETRY_ATTEMPTS,
-                })
-            }
-            UpstreamProviderError {
-                status,
-                retry_after,
-                ..
-            } => match *status {
-                StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => {
-                    Some(RetryStrategy::Fixed {
-                        delay: retry_after.unwrap_or(BASE_RETRY_DELAY),
-                        max_attempts: MAX_RETRY_ATTEMPTS,
-                    })
-                }
-                StatusCode::INTERNAL_SERVER_ERROR => Some(RetryStrategy::Fixed {
-                    delay: retry_after.unwrap_or(BASE_RETRY_DELAY),
-                    // Internal Server Error could be anything, retry up to 3 times.
-                    max_attempts: 3,
-                }),
-                status => {
-                    // There is no StatusCode variant for the unofficial HTTP 529 ("The service is overloaded"),
-                    // but we frequently get them in practice. See https://http.dev/529
-                    if status.as_u16() == 529 {
-                        Some(RetryStrategy::Fixed {
-                            delay: retry_after.unwrap_or(BASE_RETRY_DELAY),
-                            max_attempts: MAX_RETRY_ATTEMPTS,
-                        })
-                    } else {
-                        Some(RetryStrategy::Fixed {
-                            delay: retry_after.unwrap_or(BASE_RETRY_DELAY),
-                            max_attempts: 2,
-                        })
-                    }
-                }
-            },
-            ApiInternalServerError { .. } => Some(RetryStrategy::Fixed {
-                delay: BASE_RETRY_DELAY,
-                max_attempts: 3,
-            }),
-            ApiReadResponseError { .. }
-            | HttpSend { .. }
-            | DeserializeResponse { .. }
-            | BadRequestFormat { .. } => Some(RetryStrategy::Fixed {
-                delay: BASE_RETRY_DELAY,
-                max_attempts: 3,
-            }),
-            // Retrying these errors definitely shouldn't help.
-            HttpResponseError {
-                status_code:
-                    StatusCode::PAYLOAD_TOO_LARGE | StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED,
-                ..
-            }
-            | AuthenticationError { .. }
-            | PermissionError { .. }
-            | NoApiKey { .. }
-            | ApiEndpointNotFound { .. }
-            | PromptTooLarge { .. } => None,
-            // These errors might be transient, so retry them
-            SerializeRequest { .. } | BuildRequestBody { .. } | StreamEndedUnexpectedly { .. } => {
-                Some(RetryStrategy::Fixed {
-                    delay: BASE_RETRY_DELAY,
-                    max_attempts: 1,
-                })
-            }
-            // Retry all other 4xx and 5xx errors once.
-            HttpResponseError { status_code, .. }
-                if status_code.is_client_error() || status_code.is_server_error() =>
-            {
-                Some(RetryStrategy::Fixed {
-                    delay: BASE_RETRY_DELAY,
-                    max_attempts: 3,
-                })
-            }
-            Other(err) if err.is::<language_model::PaymentRequiredError>() => {
-                // Retrying won't help for Payment Required errors.
-                None
-            }
-            // Conservatively assume that any other errors are non-retryable
-            HttpResponseError { .. } | Other(..) => Some(RetryStrategy::Fixed {
-                delay: BASE_RETRY_DELAY,
-                max_attempts: 2,
-            }),
-        }
-    }
 }
 
 struct RunningTurn {
@@ -3137,9 +3340,27 @@ struct RunningTurn {
     /// Senders for tools that support input streaming and have already been
     /// started but are still receiving input from the LLM.
     streaming_tool_inputs: HashMap<LanguageModelToolUseId, ToolInputSender>,
+    /// Tasks waiting on the [`CancelHandle`]s handed out for this turn. They are
+    /// dropped with the turn, which makes any outstanding handles inert.
+    cancel_handle_tasks: Vec<Task<()>>,
+    /// The token usage of the turn's requests that have already finished.
+    finished_requests_token_usage: TokenUsage,
+    /// The token usage reported so far by the turn's current request.
+    current_request_token_usage: TokenUsage,
 }
 
 impl RunningTurn {
+    /// Counts the current request's token usage as finished, before the next
+    /// request of the turn starts reporting its own.
+    fn start_request(&mut self) {
+        self.finished_requests_token_usage = self.finished_requests_token_usage
+            + std::mem::take(&mut self.current_request_token_usage);
+    }
+
+    fn token_usage(&self) -> TokenUsage {
+        self.finished_requests_token_usage + self.current_request_token_usage
+    }
+
     fn cancel(mut self) -> Task<()> {
         log::debug!("Cancelling in progress turn");
         self.cancellation_tx.send(true).ok();
@@ -3148,6 +3369,30 @@ impl RunningTurn {
     }
 }
 
+impl Drop for Thread {
+    fn drop(&mut self) {
+        // Dropping the turn's task already stops it. Cancelling first also
+        // signals tools watching for cancellation and ends the turn's event
+        // stream with a cancelled stop instead of just closing it.
+        if let Some(running_turn) = self.running_turn.take() {
+            drop(running_turn.cancel());
+        }
+    }
+}
+
+/// Cancels a running turn without holding on to the [`Thread`].
+///
+/// Cancelling through the handle is equivalent to calling [`Thread::cancel`]:
+/// the turn's event stream ends with a cancelled stop event and whatever the
+/// model has streamed so far is kept in the thread.
+pub struct CancelHandle(oneshot::Sender<()>);
+
+impl CancelHandle {
+    pub fn cancel(self) {
+        self.0.send(()).ok();
+    }
+}
+
 pub struct TokenUsageUpdated(pub Option<acp_thread::TokenUsage>);
 
 impl EventEmitter<TokenUsageUpdated> for Thread {}
@@ -3231,6 +3476,26 @@ impl<T: DeserializeOwned> ToolInput<T> {
         })
     }
 
+    /// Takes the complete input if it has already been received, as it is for tools
+    /// that don't stream their input. Otherwise, payloads that were already received
+    /// are left in place, but later ones are dropped, so this must not be used while
+    /// input is still streaming in.
+    fn take_ready(&mut self) -> Option<serde_json::Value> {
+        match self.rx.try_next() {
+            Ok(Some(ToolInputPayload::Full(value))) => Some(value),
+            Ok(Some(payload)) => {
+                let (tx, rx) = mpsc::unbounded();
+                tx.unbounded_send(payload).ok();
+                while let Ok(Some(payload)) = self.rx.try_next() {
+                    tx.unbounded_send(payload).ok();
+                }
+                self.rx = rx;
+                None
+            }
+            Ok(None) | Err(_) => None,
+        }
+    }
+
     fn cast<U: DeserializeOwned>(self) -> ToolInput<U> {
         ToolInput {
             rx: self.rx,
@@ -3320,11 +3585,23 @@ where
         language_model::tool_schema::root_schema_for::<Self::Input>(format)
     }
 
+    /// Returns the JSON schema that the tool's successful output must match, if it
+    /// declares one. Outputs that don't match are reported to the model as errors.
+    fn output_schema() -> Option<Schema> {
+        None
+    }
+
     /// Returns whether the tool supports streaming of tool use parameters.
     fn supports_input_streaming() -> bool {
         false
     }
 
+    /// Returns whether running the tool again with the same input is harmless.
+    /// Interrupted calls to idempotent tools are re-run when resuming a turn.
+    fn is_idempotent() -> bool {
+        false
+    }
+
     /// Some tools rely on a provider for the underlying billing or other reasons.
     /// Allow the tool to check if they are compatible, or should be filtered out.
     fn supports_provider(_provider: &LanguageModelProviderId) -> bool {
@@ -3345,6 +3622,20 @@ where
         cx: &mut App,
     ) -> Task<Result<Self::Output, Self::Output>>;
 
+    /// Runs the tool to completion without spawning a task, for cheap tools that
+    /// don't need to await anything, such as pure computations.
+    ///
+    /// Only called once the whole input is available. Returning `None`, which is
+    /// the default, runs the tool through [`AgentTool::run`] instead.
+    fn run_sync(
+        &self,
+        _input: &Self::Input,
+        _event_stream: &ToolCallEventStream,
+        _cx: &mut App,
+    ) -> Option<Result<Self::Output, Self::Output>> {
+        None
+    }
+
     /// Emits events for a previous execution of the tool.
     fn replay(
         &self,
@@ -3385,9 +3676,16 @@ pub trait AnyAgentTool {
     fn kind(&self) -> acp::ToolKind;
     fn initial_title(&self, input: serde_json::Value, _cx: &mut App) -> SharedString;
     fn input_schema(&self, format: LanguageModelToolSchemaFormat) -> Result<serde_json::Value>;
+    /// See [`AgentTool::output_schema`].
+    fn output_schema(&self) -> Option<serde_json::Value> {
+        None
+    }
     fn supports_input_streaming(&self) -> bool {
         false
     }
+    fn is_idempotent(&self) -> bool {
+        false
+    }
     fn supports_provider(&self, _provider: &LanguageModelProviderId) -> bool {
         true
     }
@@ -3427,6 +3725,10 @@ where
         T::supports_input_streaming()
     }
 
+    fn is_idempotent(&self) -> bool {
+        T::is_idempotent()
+    }
+
     fn initial_title(&self, input: serde_json::Value, _cx: &mut App) -> SharedString {
         let parsed_input = serde_json::from_value(input.clone()).map_err(|_| input);
         self.0.initial_title(parsed_input, _cx)
@@ -3438,39 +3740,35 @@ where
         Ok(json)
     }
 
+    fn output_schema(&self) -> Option<serde_json::Value> {
+        T::output_schema().map(|schema| schema.to_value())
+    }
+
     fn supports_provider(&self, provider: &LanguageModelProviderId) -> bool {
         T::supports_provider(provider)
     }
 
     fn run(
         self: Arc<Self>,
-        input: ToolInput<serde_json::Value>,
+        mut input: ToolInput<serde_json::Value>,
         event_stream: ToolCallEventStream,
         cx: &mut App,
     ) -> Task<Result<AgentToolOutput, AgentToolOutput>> {
+        let output_schema = self.output_schema();
+        if !T::supports_input_streaming()
+            && let Some(value) = input.take_ready()
+        {
+            if let Ok(parsed_input) = serde_json::from_value(value.clone())
+                && let Some(result) = self.0.run_sync(&parsed_input, &event_stream, cx)
+            {
+                return Task::ready(into_agent_tool_output::<T>(result, output_schema));
+            }
+            input = ToolInput::ready(value);
+        }
+
         let tool_input: ToolInput<T::Input> = input.cast();
         let task = self.0.clone().run(tool_input, event_stream, cx);
-        cx.spawn(async move |_cx| match task.await {
-            Ok(output) => {
-                let raw_output = serde_json::to_value(&output).map_err(|e| {
-                    AgentToolOutput::from_error(format!("Failed to serialize tool output: {e}"))
-                })?;
-                Ok(AgentToolOutput {
-                    llm_output: output.into(),
-                    raw_output,
-                })
-            }
-            Err(error_output) => {
-                let raw_output = serde_json::to_value(&error_output).unwrap_or_else(|e| {
-                    log::error!("Failed to serialize tool error output: {e}");
-                    serde_json::Value::Null
-                });
-                Err(AgentToolOutput {
-                    llm_output: error_output.into(),
-                    raw_output,
-                })
-            }
-        })
+        cx.spawn(async move |_cx| into_agent_tool_output::<T>(task.await, output_schema))
     }
 
     fn replay(
@@ -3486,6 +3784,54 @@ where
     }
 }
 
+fn into_agent_tool_output<T: AgentTool>(
+    result: Result<T::Output, T::Output>,
+    output_schema: Option<serde_json::Value>,
+) -> Result<AgentToolOutput, AgentToolOutput> {
+    match result {
+        Ok(output) => {
+            let raw_output = serde_json::to_value(&output).map_err(|e| {
+                AgentToolOutput::from_error(format!("Failed to serialize tool output: {e}"))
+            })?;
+            if let Some(output_schema) = output_schema
+                && let Err(error) = validate_tool_output(&output_schema, &raw_output)
+            {
+                log::error!("Tool {} returned invalid output: {error:#}", T::NAME);
+                return Err(AgentToolOutput::from_error(format!(
+                    "Tool output doesn't match its schema: {error:#}"
+                )));
+            }
+            Ok(AgentToolOutput {
+                llm_output: output.into(),
+                raw_output,
+            })
+        }
+        Err(error_output) => {
+            let raw_output = serde_json::to_value(&error_output).unwrap_or_else(|e| {
+                log::error!("Failed to serialize tool error output: {e}");
+                serde_json::Value::Null
+            });
+            Err(AgentToolOutput {
+                llm_output: error_output.into(),
+                raw_output,
+            })
+        }
+    }
+}
+
+fn validate_tool_output(schema: &serde_json::Value, output: &serde_json::Value) -> Result<()> {
+    let validator = jsonschema::validator_for(schema).context("Invalid tool output schema")?;
+    let errors = validator
+        .iter_errors(output)
+        .map(|error| error.to_string())
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(errors.join("; ")))
+    }
+}
+
 #[derive(Clone)]
 struct ThreadEventStream(mpsc::UnboundedSender<Result<ThreadEvent>>);
 
@@ -3567,6 +3913,12 @@ impl ThreadEventStream {
         self.0.unbounded_send(Ok(ThreadEvent::Stop(reason))).ok();
     }
 
+    fn send_turn_summary(&self, summary: TurnSummary) {
+        self.0
+            .unbounded_send(Ok(ThreadEvent::TurnSummary(summary)))
+            .ok();
+    }
+
     fn send_canceled(&self) {
         self.0
             .unbounded_send(Ok(ThreadEvent::Stop(acp::StopReason::Cancelled)))
@@ -3,7 +3,7 @@ use super::save_file_tool::SaveFileTool;
 use super::tool_permissions::authorize_file_edit;
 use crate::{
     AgentTool, Templates, Thread, ToolCallEventStream, ToolInput,
-    edit_agent::{EditAgent, EditAgentOutputEvent, EditFormat},
+    edit_agent::{EditAgent, EditAgentOutputEvent},
 };
 use acp_thread::Diff;
 use agent_client_protocol::{self as acp, ToolCallLocation, ToolCallUpdateFields};
@@ -27,6 +27,10 @@ use util::rel_path::RelPath;
 
 const DEFAULT_UI_TEXT: &str = "Editing file";
 
+/// Files larger than this are rejected rather than edited, because the model
+/// needs to have seen the whole file to reproduce the text it replaces.
+const MAX_EDITABLE_FILE_SIZE: usize = 2 * 1024 * 1024;
+
 /// This is a tool for creating a new file or editing an existing file. For moving or renaming files, you should generally use the `move_path` tool instead.
 ///
 /// Before using this tool:
@@ -278,23 +282,22 @@ impl AgentTool for EditFileTool {
             let result: anyhow::Result<EditFileToolOutput> = async {
                 authorize.await?;
 
-                let (request, model, action_log) = self.thread.update(cx, |thread, cx| {
+                let (request, model, action_log, retry_completions) = self.thread.update(cx, |thread, cx| {
                     let request = thread.build_completion_request(CompletionIntent::ToolResults, cx);
-                    (request, thread.model().cloned(), thread.action_log().clone())
+                    (request, thread.model().cloned(), thread.action_log().clone(), thread.can_retry_completions(cx))
                 })?;
                 let request = request?;
-                let model = model.context("No language model configured")?;
-
-                let edit_format = EditFormat::from_model(model.clone())?;
-                let edit_agent = EditAgent::new(
+                let edit_agent = EditAgent::try_new(
                     model,
                     project.clone(),
                     action_log.clone(),
                     self.templates.clone(),
-                    edit_format,
                     allow_thinking,
                     update_agent_location,
-                );
+                )?
+                .with_whitespace_significance_detection(true)
+                .with_max_file_size(MAX_EDITABLE_FILE_SIZE)
+                .with_completion_retries(retry_completions);
 
                 let buffer = project
                     .update(cx, |project, cx| {
@@ -391,6 +394,7 @@ impl AgentTool for EditFileTool {
 
                 let mut hallucinated_old_text = false;
                 let mut ambiguous_ranges = Vec::new();
+                let mut insufficient_context_ranges = Vec::new();
                 let mut emitted_location = false;
                 loop {
                     let event = futures::select! {
@@ -403,7 +407,7 @@ impl AgentTool for EditFileTool {
                         }
                     };
                     match event {
-                        EditAgentOutputEvent::Edited(range) => {
+                        EditAgentOutputEvent::Edited { range, .. } => {
                             if !emitted_location {
                                 let line = Some(buffer.update(cx, |buffer, _cx| {
                                     range.start.to_point(&buffer.snapshot()).row
@@ -416,6 +420,7 @@ impl AgentTool for EditFileTool {
                         },
                         EditAgentOutputEvent::UnresolvedEditRange => hallucinated_old_text = true,
                         EditAgentOutputEvent::AmbiguousEditRange(ranges) => ambiguous_ranges = ranges,
+                        EditAgentOutputEvent::InsufficientContext(range) => insufficient_context_ranges.push(range),
                         EditAgentOutputEvent::ResolvingEditRange(range) => {
                             diff.update(cx, |card, cx| card.reveal_range(range.clone(), cx));
                         }
@@ -492,6 +497,21 @@ impl AgentTool for EditFileTool {
                             "}
                         }
                     );
+                    anyhow::ensure!(
+                        insufficient_context_ranges.is_empty(),
+                        {
+                            let line_numbers = insufficient_context_ranges
+                                .iter()
+                                .map(|range| range.start.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            formatdoc! {"
+                                <old_text> doesn't include enough unchanged lines around the change (lines: {line_numbers}).
+                                Extend <old_text> and <new_text> with the surrounding lines so that I can
+                                perform the requested edits.
+                            "}
+                        }
+                    );
                 }
 
                 anyhow::Ok(EditFileToolOutput::Success {
@@ -129,6 +129,10 @@ impl AgentTool for FetchTool {
         acp::ToolKind::Fetch
     }
 
+    fn is_idempotent() -> bool {
+        true
+    }
+
     fn initial_title(
         &self,
         input: Result<Self::Input, serde_json::Value>,
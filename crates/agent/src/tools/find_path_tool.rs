@@ -107,6 +107,10 @@ impl AgentTool for FindPathTool {
         acp::ToolKind::Search
     }
 
+    fn is_idempotent() -> bool {
+        true
+    }
+
     fn initial_title(
         &self,
         input: Result<Self::Input, serde_json::Value>,
@@ -86,6 +86,10 @@ impl AgentTool for GrepTool {
         acp::ToolKind::Search
     }
 
+    fn is_idempotent() -> bool {
+        true
+    }
+
     fn initial_title(
         &self,
         input: Result<Self::Input, serde_json::Value>,
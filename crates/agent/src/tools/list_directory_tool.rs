@@ -131,6 +131,10 @@ impl AgentTool for ListDirectoryTool {
         ToolKind::Read
     }
 
+    fn is_idempotent() -> bool {
+        true
+    }
+
     fn initial_title(
         &self,
         input: Result<Self::Input, serde_json::Value>,
@@ -85,6 +85,10 @@ impl AgentTool for ReadFileTool {
         acp::ToolKind::Read
     }
 
+    fn is_idempotent() -> bool {
+        true
+    }
+
     fn initial_title(
         &self,
         input: Result<Self::Input, serde_json::Value>,
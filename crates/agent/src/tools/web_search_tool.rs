@@ -59,6 +59,10 @@ impl AgentTool for WebSearchTool {
         acp::ToolKind::Fetch
     }
 
+    fn is_idempotent() -> bool {
+        true
+    }
+
     fn initial_title(
         &self,
         _input: Result<Self::Input, serde_json::Value>,
@@ -1211,6 +1211,7 @@ mod tests {
             thinking_effort: None,
             draft_prompt: None,
             ui_scroll_position: None,
+            interrupted_turn: None,
         }
     }
 
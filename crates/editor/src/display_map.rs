@@ -161,6 +161,7 @@ pub enum HighlightKey {
     DocumentHighlightWrite,
     EditPredictionHighlight,
     Editor,
+    FlashedRange,
     HighlightOnYank,
     HighlightsTreeView(usize),
     HoverState,
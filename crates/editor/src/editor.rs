@@ -253,6 +253,8 @@ pub(crate) const CURSORS_VISIBLE_FOR: Duration = Duration::from_millis(2000);
 #[doc(hidden)]
 pub const CODE_ACTIONS_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(250);
 pub const SELECTION_HIGHLIGHT_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(100);
+pub const FLASHED_RANGE_DURATION: Duration = Duration::from_millis(600);
+const FLASHED_RANGE_FADE_STEPS: u32 = 6;
 
 pub(crate) const CODE_ACTION_TIMEOUT: Duration = Duration::from_secs(5);
 pub(crate) const FORMAT_TIMEOUT: Duration = Duration::from_secs(5);
@@ -1178,6 +1180,7 @@ pub struct Editor {
     minimap_visibility: MinimapVisibility,
     offset_content: bool,
     disable_expand_excerpt_buttons: bool,
+    show_unchanged_line_counts: bool,
     delegate_expand_excerpts: bool,
     delegate_stage_and_restore: bool,
     delegate_open_excerpts: bool,
@@ -1198,6 +1201,7 @@ pub struct Editor {
     background_highlights: HashMap<HighlightKey, BackgroundHighlight>,
     gutter_highlights: HashMap<TypeId, GutterHighlight>,
     scrollbar_marker_state: ScrollbarMarkerState,
+    flashed_range_task: Option<Task<()>>,
     active_indent_guides_state: ActiveIndentGuidesState,
     nav_history: Option<ItemNavHistory>,
     context_menu: RefCell<Option<CodeContextMenu>>,
@@ -2416,6 +2420,7 @@ impl Editor {
             show_line_numbers: (!full_mode).then_some(false),
             use_relative_line_numbers: None,
             disable_expand_excerpt_buttons: !full_mode,
+            show_unchanged_line_counts: false,
             delegate_expand_excerpts: false,
             delegate_stage_and_restore: false,
             delegate_open_excerpts: false,
@@ -2434,6 +2439,7 @@ impl Editor {
             background_highlights: HashMap::default(),
             gutter_highlights: HashMap::default(),
             scrollbar_marker_state: ScrollbarMarkerState::default(),
+            flashed_range_task: None,
             active_indent_guides_state: ActiveIndentGuidesState::default(),
             nav_history: None,
             context_menu: RefCell::new(None),
@@ -17753,7 +17759,17 @@ impl Editor {
         cx: &mut Context<Self>,
     ) {
         let lines_to_expand = EditorSettings::get_global(cx).expand_excerpt_lines;
+        self.expand_excerpt_by_lines(excerpt_anchor, lines_to_expand, direction, window, cx);
+    }
 
+    pub(crate) fn expand_excerpt_by_lines(
+        &mut self,
+        excerpt_anchor: Anchor,
+        lines_to_expand: u32,
+        direction: ExpandExcerptDirection,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
         if self.delegate_expand_excerpts {
             cx.emit(EditorEvent::ExpandExcerptsRequested {
                 excerpt_anchors: vec![excerpt_anchor],
@@ -21877,6 +21893,13 @@ impl Editor {
         cx.notify();
     }
 
+    /// Shows how many lines are hidden between adjacent excerpts of the same
+    /// buffer, as a marker that reveals them when clicked.
+    pub fn set_show_unchanged_line_counts(&mut self, show: bool, cx: &mut Context<Self>) {
+        self.show_unchanged_line_counts = show;
+        cx.notify();
+    }
+
     pub fn set_number_deleted_lines(&mut self, number: bool, cx: &mut Context<Self>) {
         self.number_deleted_lines = number;
         cx.notify();
@@ -23962,6 +23985,56 @@ impl Editor {
         self.clear_background_highlights(HighlightKey::SearchWithinRange, cx);
     }
 
+    /// Briefly highlights a range of one of this editor's buffers and then fades
+    /// the highlight out, e.g. to show where an agent edit is about to land.
+    /// Flashing another range replaces the current one.
+    ///
+    /// Returns `false` if the range isn't part of any excerpt in this editor.
+    pub fn flash_buffer_range(
+        &mut self,
+        range: Range<text::Anchor>,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let Some(range) = self
+            .buffer
+            .read(cx)
+            .snapshot(cx)
+            .buffer_anchor_range_to_anchor_range(range)
+        else {
+            return false;
+        };
+
+        self.highlight_background(
+            HighlightKey::FlashedRange,
+            &[range.clone()],
+            |_, theme| theme.colors().search_match_background,
+            cx,
+        );
+        self.flashed_range_task = Some(cx.spawn(async move |this, cx| {
+            let step = FLASHED_RANGE_DURATION / FLASHED_RANGE_FADE_STEPS;
+            for remaining_steps in (0..FLASHED_RANGE_FADE_STEPS).rev() {
+                cx.background_executor().timer(step).await;
+                let opacity = remaining_steps as f32 / FLASHED_RANGE_FADE_STEPS as f32;
+                let updated = this.update(cx, |editor, cx| {
+                    if remaining_steps == 0 {
+                        editor.clear_background_highlights(HighlightKey::FlashedRange, cx);
+                    } else {
+                        editor.highlight_background(
+                            HighlightKey::FlashedRange,
+                            &[range.clone()],
+                            move |_, theme| theme.colors().search_match_background.opacity(opacity),
+                            cx,
+                        );
+                    }
+                });
+                if updated.is_err() {
+                    break;
+                }
+            }
+        }));
+        true
+    }
+
     pub fn highlight_background(
         &mut self,
         key: HighlightKey,
@@ -19102,6 +19102,50 @@ fn test_highlighted_ranges(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+fn test_flashed_buffer_range_fades_out(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let editor = cx.add_window(|window, cx| {
+        let buffer = MultiBuffer::build_simple(&sample_text(8, 6, 'a'), cx);
+        build_editor(buffer, window, cx)
+    });
+
+    _ = editor.update(cx, |editor, window, cx| {
+        let Some(buffer) = editor.buffer.read(cx).as_singleton() else {
+            panic!("expected a singleton buffer");
+        };
+        let resolved_range = buffer.read_with(cx, |buffer, _| {
+            buffer.anchor_before(Point::new(2, 1))..buffer.anchor_after(Point::new(3, 4))
+        });
+        assert!(editor.flash_buffer_range(resolved_range, cx));
+
+        let highlighted_ranges = editor
+            .all_text_background_highlights(window, cx)
+            .into_iter()
+            .map(|(range, _)| range)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            highlighted_ranges,
+            &[DisplayPoint::new(DisplayRow(2), 1)..DisplayPoint::new(DisplayRow(3), 4)]
+        );
+    });
+
+    cx.executor().advance_clock(FLASHED_RANGE_DURATION / 2);
+    cx.run_until_parked();
+    _ = editor.update(cx, |editor, window, cx| {
+        let highlights = editor.all_text_background_highlights(window, cx);
+        assert_eq!(highlights.len(), 1, "the highlight should still be fading");
+        assert!(highlights[0].1.a < cx.theme().colors().search_match_background.a);
+    });
+
+    cx.executor().advance_clock(FLASHED_RANGE_DURATION);
+    cx.run_until_parked();
+    _ = editor.update(cx, |editor, window, cx| {
+        assert!(editor.all_text_background_highlights(window, cx).is_empty());
+    });
+}
+
 #[gpui::test]
 async fn test_copy_highlight_json(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -58,7 +58,7 @@ use language::{
 use markdown::Markdown;
 use multi_buffer::{
     Anchor, ExcerptBoundaryInfo, ExpandExcerptDirection, ExpandInfo, MultiBufferPoint,
-    MultiBufferRow, RowInfo,
+    MultiBufferRow, MultiBufferSnapshot, RowInfo,
 };
 
 use project::{
@@ -4006,19 +4006,54 @@ impl EditorElement {
                 result.into_any_element()
             }
 
-            Block::ExcerptBoundary { .. } => {
+            Block::ExcerptBoundary { excerpt, .. } => {
                 let color = cx.theme().colors().clone();
                 let mut result = v_flex().id(block_id).w_full();
+                let unchanged_line_count = if self.editor.read(cx).show_unchanged_line_counts {
+                    unchanged_line_count_before(snapshot.buffer_snapshot(), excerpt)
+                } else {
+                    None
+                };
 
                 result = result.child(
-                    h_flex().relative().child(
-                        div()
-                            .top(line_height / 2.)
-                            .absolute()
-                            .w_full()
-                            .h_px()
-                            .bg(color.border_variant),
-                    ),
+                    h_flex()
+                        .relative()
+                        .w_full()
+                        .justify_center()
+                        .child(
+                            div()
+                                .top(line_height / 2.)
+                                .absolute()
+                                .w_full()
+                                .h_px()
+                                .bg(color.border_variant),
+                        )
+                        .when_some(unchanged_line_count, |this, line_count| {
+                            let editor = self.editor.clone();
+                            let start_anchor = excerpt.start_anchor;
+                            let label = if line_count == 1 {
+                                "1 unchanged line".to_string()
+                            } else {
+                                format!("{line_count} unchanged lines")
+                            };
+                            this.child(
+                                Button::new(("unchanged-lines", block_row_start.0 as usize), label)
+                                    .style(ButtonStyle::Filled)
+                                    .label_size(LabelSize::Small)
+                                    .color(Color::Muted)
+                                    .on_click(move |_, window, cx| {
+                                        editor.update(cx, |editor, cx| {
+                                            editor.expand_excerpt_by_lines(
+                                                start_anchor,
+                                                line_count,
+                                                ExpandExcerptDirection::Up,
+                                                window,
+                                                cx,
+                                            );
+                                        });
+                                    }),
+                            )
+                        }),
                 );
 
                 result.into_any()
@@ -8224,6 +8259,22 @@ pub(crate) fn header_jump_data(
     }
 }
 
+/// The number of lines hidden between the excerpt starting at `excerpt` and
+/// the excerpt before it, if both show the same buffer.
+pub(crate) fn unchanged_line_count_before(
+    snapshot: &MultiBufferSnapshot,
+    excerpt: &ExcerptBoundaryInfo,
+) -> Option<u32> {
+    let previous_excerpt = snapshot.excerpt_before(excerpt.start_anchor)?;
+    if previous_excerpt.context.end.buffer_id != excerpt.buffer_id() {
+        return None;
+    }
+    let buffer = excerpt.buffer(snapshot);
+    let previous_end_row = text::ToPoint::to_point(&previous_excerpt.context.end, buffer).row;
+    let start_row = text::ToPoint::to_point(&excerpt.start_text_anchor(), buffer).row;
+    Some(start_row.saturating_sub(previous_end_row + 1)).filter(|line_count| *line_count > 0)
+}
+
 pub(crate) fn render_buffer_header(
     editor: &Entity<Editor>,
     for_excerpt: &ExcerptBoundaryInfo,
@@ -567,10 +567,12 @@ impl SplittableEditor {
         });
 
         let render_diff_hunk_controls = self.rhs_editor.read(cx).render_diff_hunk_controls.clone();
+        let show_unchanged_line_counts = self.rhs_editor.read(cx).show_unchanged_line_counts;
         let lhs_editor = cx.new(|cx| {
             let mut editor =
                 Editor::for_multibuffer(lhs_multibuffer.clone(), Some(project.clone()), window, cx);
             editor.set_number_deleted_lines(true, cx);
+            editor.set_show_unchanged_line_counts(show_unchanged_line_counts, cx);
             editor.set_delegate_expand_excerpts(true);
             editor.set_delegate_stage_and_restore(true);
             editor.set_delegate_open_excerpts(true);
@@ -1090,6 +1092,86 @@ impl SplittableEditor {
         result
     }
 
+    /// Shows only the changed parts of `buffer`, with `context_line_count`
+    /// unchanged lines around each hunk. Runs of at least
+    /// `min_collapsed_line_count` unchanged lines are collapsed on both sides
+    /// into an "N unchanged lines" marker, and expanding them from either side
+    /// expands both. A buffer without changes is shown in full.
+    pub fn collapse_unchanged_regions(
+        &mut self,
+        path: PathKey,
+        buffer: Entity<Buffer>,
+        diff: Entity<BufferDiff>,
+        context_line_count: u32,
+        min_collapsed_line_count: u32,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let buffer_snapshot = buffer.read(cx).text_snapshot();
+        let hunk_ranges = diff
+            .read(cx)
+            .snapshot(cx)
+            .hunks(&buffer_snapshot)
+            .map(|hunk| hunk.range)
+            .collect::<Vec<_>>();
+        if hunk_ranges.is_empty() {
+            return self.expand_unchanged_regions(path, buffer, diff, cx);
+        }
+
+        // Keep runs that are too short to collapse by merging the ranges
+        // around them.
+        let mut ranges: Vec<Range<Point>> = Vec::new();
+        for range in hunk_ranges {
+            let collapsed_start_row = ranges
+                .last()
+                .map_or(0, |previous| previous.end.row + context_line_count + 1);
+            let collapsed_line_count = range
+                .start
+                .row
+                .saturating_sub(context_line_count)
+                .saturating_sub(collapsed_start_row);
+            match ranges.last_mut() {
+                Some(previous) if collapsed_line_count < min_collapsed_line_count => {
+                    previous.end = previous.end.max(range.end);
+                }
+                None if collapsed_line_count < min_collapsed_line_count => {
+                    ranges.push(Point::zero()..range.end);
+                }
+                _ => ranges.push(range),
+            }
+        }
+        let max_point = buffer_snapshot.max_point();
+        if let Some(last_range) = ranges.last_mut()
+            && max_point
+                .row
+                .saturating_sub(last_range.end.row + context_line_count)
+                < min_collapsed_line_count
+        {
+            last_range.end = max_point;
+        }
+
+        self.rhs_editor.update(cx, |editor, cx| {
+            editor.set_show_unchanged_line_counts(true, cx);
+        });
+        if let Some(lhs) = &self.lhs {
+            lhs.editor.update(cx, |editor, cx| {
+                editor.set_show_unchanged_line_counts(true, cx);
+            });
+        }
+        self.update_excerpts_for_path(path, buffer, ranges, context_line_count, diff, cx)
+    }
+
+    /// Shows all of `buffer` again after [`Self::collapse_unchanged_regions`].
+    pub fn expand_unchanged_regions(
+        &mut self,
+        path: PathKey,
+        buffer: Entity<Buffer>,
+        diff: Entity<BufferDiff>,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let max_point = buffer.read(cx).max_point();
+        self.update_excerpts_for_path(path, buffer, [Point::zero()..max_point], 0, diff, cx)
+    }
+
     fn expand_excerpts(
         &mut self,
         excerpt_anchors: impl Iterator<Item = Anchor> + Clone,
@@ -2484,6 +2566,197 @@ mod tests {
         });
     }
 
+    fn excerpt_counts(
+        editor: &Entity<SplittableEditor>,
+        cx: &mut VisualTestContext,
+    ) -> (usize, usize) {
+        editor.update(cx, |editor, cx| {
+            let lhs = editor.lhs.as_ref().expect("should have lhs editor");
+            (
+                editor
+                    .rhs_multibuffer
+                    .read(cx)
+                    .snapshot(cx)
+                    .excerpts()
+                    .count(),
+                lhs.multibuffer.read(cx).snapshot(cx).excerpts().count(),
+            )
+        })
+    }
+
+    /// The "N unchanged lines" markers shown by `editor`, with the anchor of
+    /// the excerpt each one precedes.
+    fn unchanged_line_markers(
+        editor: &Entity<Editor>,
+        cx: &mut VisualTestContext,
+    ) -> Vec<(u32, multi_buffer::Anchor)> {
+        editor.update(cx, |editor, cx| {
+            assert!(editor.show_unchanged_line_counts);
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            snapshot
+                .excerpt_boundaries_in_range(MultiBufferOffset(0)..)
+                .filter_map(|boundary| {
+                    let line_count =
+                        crate::element::unchanged_line_count_before(&snapshot, &boundary.next)?;
+                    Some((line_count, boundary.next.start_anchor))
+                })
+                .collect()
+        })
+    }
+
+    fn marker_line_counts(
+        editor: &Entity<SplittableEditor>,
+        cx: &mut VisualTestContext,
+    ) -> (Vec<u32>, Vec<u32>) {
+        let (rhs_editor, lhs_editor) = editor.update(cx, |editor, _| {
+            let lhs = editor.lhs.as_ref().expect("should have lhs editor");
+            (editor.rhs_editor.clone(), lhs.editor.clone())
+        });
+        let line_counts = |editor: &Entity<Editor>, cx: &mut VisualTestContext| {
+            unchanged_line_markers(editor, cx)
+                .into_iter()
+                .map(|(line_count, _)| line_count)
+                .collect::<Vec<_>>()
+        };
+        (line_counts(&rhs_editor, cx), line_counts(&lhs_editor, cx))
+    }
+
+    fn buffer_with_changed_ends(
+        unchanged_line_count: usize,
+        cx: &mut VisualTestContext,
+    ) -> (Entity<Buffer>, Entity<BufferDiff>) {
+        let unchanged_lines = "unchanged\n".repeat(unchanged_line_count);
+        let base_text = format!("first\n{unchanged_lines}last\n");
+        let current_text = format!("FIRST\n{unchanged_lines}LAST\n");
+        let (buffer, diff) = buffer_with_diff(&base_text, &current_text, cx);
+        let buffer_snapshot = buffer.read_with(cx, |buffer, _| buffer.text_snapshot());
+        diff.update(cx, |diff, cx| {
+            diff.recalculate_diff_sync(&buffer_snapshot, cx);
+        });
+        cx.run_until_parked();
+        (buffer, diff)
+    }
+
+    #[gpui::test]
+    async fn test_collapse_unchanged_regions(cx: &mut gpui::TestAppContext) {
+        let (editor, cx) = init_test(cx, SoftWrap::None, DiffViewStyle::Split).await;
+        let (buffer, diff) = buffer_with_changed_ends(100, cx);
+
+        let path = editor.update(cx, |_, cx| PathKey::for_buffer(&buffer, cx));
+        editor.update(cx, |editor, cx| {
+            editor.collapse_unchanged_regions(
+                path.clone(),
+                buffer.clone(),
+                diff.clone(),
+                2,
+                10,
+                cx,
+            );
+        });
+        cx.run_until_parked();
+
+        assert_eq!(
+            excerpt_counts(&editor, cx),
+            (2, 2),
+            "the unchanged lines between the hunks should be collapsed on both sides"
+        );
+        let (rhs_markers, lhs_markers) = marker_line_counts(&editor, cx);
+        assert_eq!(
+            rhs_markers.len(),
+            1,
+            "a single marker should replace the gap"
+        );
+        assert!(
+            rhs_markers[0] > 90,
+            "the marker should count the hidden lines"
+        );
+        assert_eq!(lhs_markers, rhs_markers);
+
+        editor.update(cx, |editor, cx| {
+            editor.expand_unchanged_regions(path, buffer.clone(), diff.clone(), cx);
+        });
+        cx.run_until_parked();
+        assert_eq!(excerpt_counts(&editor, cx), (1, 1));
+        assert_eq!(marker_line_counts(&editor, cx), (vec![], vec![]));
+    }
+
+    #[gpui::test]
+    async fn test_collapse_unchanged_regions_keeps_short_runs(cx: &mut gpui::TestAppContext) {
+        let (editor, cx) = init_test(cx, SoftWrap::None, DiffViewStyle::Split).await;
+        let (buffer, diff) = buffer_with_changed_ends(12, cx);
+
+        let path = editor.update(cx, |_, cx| PathKey::for_buffer(&buffer, cx));
+        editor.update(cx, |editor, cx| {
+            editor.collapse_unchanged_regions(path, buffer.clone(), diff.clone(), 2, 10, cx);
+        });
+        cx.run_until_parked();
+
+        assert_eq!(
+            excerpt_counts(&editor, cx),
+            (1, 1),
+            "runs shorter than the threshold should stay visible"
+        );
+        assert_eq!(marker_line_counts(&editor, cx), (vec![], vec![]));
+    }
+
+    #[gpui::test]
+    async fn test_expanding_unchanged_lines_from_the_left_expands_both_sides(
+        cx: &mut gpui::TestAppContext,
+    ) {
+        let (editor, cx) = init_test(cx, SoftWrap::None, DiffViewStyle::Split).await;
+        let (buffer, diff) = buffer_with_changed_ends(100, cx);
+
+        let path = editor.update(cx, |_, cx| PathKey::for_buffer(&buffer, cx));
+        editor.update(cx, |editor, cx| {
+            editor.collapse_unchanged_regions(path, buffer.clone(), diff.clone(), 2, 10, cx);
+        });
+        cx.run_until_parked();
+
+        let line_counts = |editor: &Entity<SplittableEditor>, cx: &mut VisualTestContext| {
+            editor.update(cx, |editor, cx| {
+                let lhs = editor.lhs.as_ref().expect("should have lhs editor");
+                (
+                    editor.rhs_multibuffer.read(cx).snapshot(cx).max_point().row,
+                    lhs.multibuffer.read(cx).snapshot(cx).max_point().row,
+                )
+            })
+        };
+        let (collapsed_rhs_line_count, collapsed_lhs_line_count) = line_counts(&editor, cx);
+
+        let lhs_editor = editor.update(cx, |editor, _| {
+            editor
+                .lhs
+                .as_ref()
+                .expect("should have lhs editor")
+                .editor
+                .clone()
+        });
+        let markers = unchanged_line_markers(&lhs_editor, cx);
+        assert_eq!(markers.len(), 1);
+        let (hidden_line_count, excerpt_anchor) = markers[0];
+        lhs_editor.update_in(cx, |editor, window, cx| {
+            editor.expand_excerpt_by_lines(
+                excerpt_anchor,
+                hidden_line_count,
+                multi_buffer::ExpandExcerptDirection::Up,
+                window,
+                cx,
+            );
+        });
+        cx.run_until_parked();
+
+        let (expanded_rhs_line_count, expanded_lhs_line_count) = line_counts(&editor, cx);
+        assert!(
+            expanded_rhs_line_count > collapsed_rhs_line_count,
+            "expanding from the left should grow the right side"
+        );
+        assert!(
+            expanded_lhs_line_count > collapsed_lhs_line_count,
+            "expanding from the left should grow the left side"
+        );
+        assert_eq!(marker_line_counts(&editor, cx), (vec![], vec![]));
+    }
+
     #[gpui::test]
     async fn test_basic_alignment(cx: &mut gpui::TestAppContext) {
         use rope::Point;
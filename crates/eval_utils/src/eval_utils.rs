@@ -2,7 +2,11 @@
 
 use std::{
     collections::HashMap,
-    sync::{Arc, mpsc},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
 };
 
 fn report_progress(evaluated_count: usize, failed_count: usize, iterations: usize) {
@@ -67,6 +71,151 @@ impl EvalOutputProcessor for NoProcessor {
     fn assert(&mut self) {}
 }
 
+/// Cancels an in-progress [`EvalRunner::run`] when triggered. Iterations that
+/// have already started still run to completion.
+#[derive(Clone, Debug, Default)]
+pub struct EvalCancellation(Arc<AtomicBool>);
+
+impl EvalCancellation {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// The output of a single iteration of an eval.
+#[derive(Clone, Debug)]
+pub struct EvalIteration<M> {
+    /// The order in which the iteration was started. Iterations may finish in
+    /// a different order.
+    pub index: usize,
+    pub output: EvalOutput<M>,
+}
+
+/// The outcomes of every iteration that ran.
+#[derive(Clone, Debug)]
+pub struct EvalResults<M> {
+    pub requested_iterations: usize,
+    pub iterations: Vec<EvalIteration<M>>,
+    pub cancelled: bool,
+}
+
+impl<M> EvalResults<M> {
+    pub fn passed_count(&self) -> usize {
+        self.count(OutcomeKind::Passed)
+    }
+
+    pub fn failed_count(&self) -> usize {
+        self.count(OutcomeKind::Failed)
+    }
+
+    pub fn errored_count(&self) -> usize {
+        self.count(OutcomeKind::Error)
+    }
+
+    /// The ratio of passed iterations to the iterations that ran.
+    pub fn pass_ratio(&self) -> f32 {
+        if self.iterations.is_empty() {
+            0.0
+        } else {
+            self.passed_count() as f32 / self.iterations.len() as f32
+        }
+    }
+
+    fn count(&self, outcome: OutcomeKind) -> usize {
+        self.iterations
+            .iter()
+            .filter(|iteration| iteration.output.outcome == outcome)
+            .count()
+    }
+}
+
+/// Runs an eval function repeatedly on the background executor, leaving the
+/// reporting of progress and results to the caller.
+pub struct EvalRunner {
+    iterations: usize,
+    concurrency: usize,
+    cancellation: EvalCancellation,
+}
+
+impl EvalRunner {
+    pub fn new(iterations: usize) -> Self {
+        Self {
+            iterations,
+            concurrency: 32,
+            cancellation: EvalCancellation::default(),
+        }
+    }
+
+    /// Sets how many iterations may run at the same time.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn cancellation(mut self, cancellation: EvalCancellation) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Runs the iterations, calling `on_output` as each of them finishes. The
+    /// first iteration runs on its own to warm any caches the rest can share.
+    pub fn run<M: 'static + Send>(
+        &self,
+        evalf: impl Fn() -> EvalOutput<M> + Send + Sync + 'static,
+        mut on_output: impl FnMut(&EvalIteration<M>),
+    ) -> EvalResults<M> {
+        let mut results = EvalResults {
+            requested_iterations: self.iterations,
+            iterations: Vec::new(),
+            cancelled: false,
+        };
+        if self.iterations == 0 || self.cancellation.is_cancelled() {
+            results.cancelled = self.cancellation.is_cancelled();
+            return results;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let executor = gpui_platform::background_executor();
+        let semaphore = Arc::new(smol::lock::Semaphore::new(self.concurrency));
+        let evalf = Arc::new(evalf);
+
+        tx.send(EvalIteration {
+            index: 0,
+            output: evalf(),
+        })
+        .ok();
+
+        for index in 1..self.iterations {
+            let tx = tx.clone();
+            let semaphore = semaphore.clone();
+            let evalf = evalf.clone();
+            let cancellation = self.cancellation.clone();
+            executor
+                .spawn(async move {
+                    let _guard = semaphore.acquire().await;
+                    if cancellation.is_cancelled() {
+                        return;
+                    }
+                    let output = evalf();
+                    tx.send(EvalIteration { index, output }).ok();
+                })
+                .detach();
+        }
+        drop(tx);
+
+        while let Ok(iteration) = rx.recv() {
+            on_output(&iteration);
+            results.iterations.push(iteration);
+        }
+        results.cancelled = results.iterations.len() < self.iterations;
+        results
+    }
+}
+
 pub fn eval<P>(
     iterations: usize,
     expected_pass_ratio: f32,
@@ -77,52 +226,29 @@ pub fn eval<P>(
 {
     let mut evaluated_count = 0;
     let mut failed_count = 0;
-    let evalf = Arc::new(evalf);
     report_progress(evaluated_count, failed_count, iterations);
 
-    let (tx, rx) = mpsc::channel();
-
-    let executor = gpui_platform::background_executor();
-    let semaphore = Arc::new(smol::lock::Semaphore::new(32));
-    let evalf = Arc::new(evalf);
-    // Warm the cache once
-    let first_output = evalf();
-    tx.send(first_output).ok();
-
-    for _ in 1..iterations {
-        let tx = tx.clone();
-        let semaphore = semaphore.clone();
-        let evalf = evalf.clone();
-        executor
-            .spawn(async move {
-                let _guard = semaphore.acquire().await;
-                let output = evalf();
-                tx.send(output).ok();
-            })
-            .detach();
-    }
-    drop(tx);
-
     let mut failed_evals = Vec::new();
     let mut errored_evals = HashMap::new();
-    while let Ok(output) = rx.recv() {
-        processor.process(&output);
+    EvalRunner::new(iterations).run(evalf, |iteration| {
+        let output = &iteration.output;
+        processor.process(output);
 
         match output.outcome {
             OutcomeKind::Passed => {}
             OutcomeKind::Failed => {
                 failed_count += 1;
-                failed_evals.push(output);
+                failed_evals.push(output.data.clone());
             }
             OutcomeKind::Error => {
                 failed_count += 1;
-                *errored_evals.entry(output.data).or_insert(0) += 1;
+                *errored_evals.entry(output.data.clone()).or_insert(0) += 1;
             }
         }
 
         evaluated_count += 1;
         report_progress(evaluated_count, failed_count, iterations);
-    }
+    });
 
     let actual_pass_ratio = (iterations - failed_count) as f32 / iterations as f32;
     println!("Actual pass ratio: {}\n", actual_pass_ratio);
@@ -133,7 +259,7 @@ pub fn eval<P>(
 
         for failed in failed_evals {
             println!("Eval failed");
-            println!("{}", failed.data);
+            println!("{}", failed);
         }
 
         panic!(
@@ -144,3 +270,70 @@ pub fn eval<P>(
 
     processor.assert();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_runner_collects_outcomes() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut reported = 0;
+        let results = EvalRunner::new(10).concurrency(4).run(
+            {
+                let calls = calls.clone();
+                move || {
+                    let call = calls.fetch_add(1, Ordering::SeqCst);
+                    if call % 2 == 0 {
+                        EvalOutput::<()>::passed("diff")
+                    } else {
+                        EvalOutput::failed("diff")
+                    }
+                }
+            },
+            |_| reported += 1,
+        );
+
+        assert_eq!(reported, 10);
+        assert_eq!(results.iterations.len(), 10);
+        assert_eq!(results.passed_count(), 5);
+        assert_eq!(results.failed_count(), 5);
+        assert_eq!(results.errored_count(), 0);
+        assert_eq!(results.pass_ratio(), 0.5);
+        assert!(!results.cancelled);
+
+        let mut indices = results
+            .iterations
+            .iter()
+            .map(|iteration| iteration.index)
+            .collect::<Vec<_>>();
+        indices.sort();
+        assert_eq!(indices, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_runner_stops_when_cancelled() {
+        let cancellation = EvalCancellation::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let results = EvalRunner::new(10)
+            .concurrency(1)
+            .cancellation(cancellation.clone())
+            .run(
+                {
+                    let calls = calls.clone();
+                    move || {
+                        if calls.fetch_add(1, Ordering::SeqCst) == 2 {
+                            cancellation.cancel();
+                        }
+                        EvalOutput::<()>::passed("")
+                    }
+                },
+                |_| {},
+            );
+
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(results.iterations.len(), 3);
+        assert!(results.cancelled);
+    }
+}
@@ -33,6 +33,8 @@ impl Render for UniformListExample {
                     items
                 }),
             )
+            .with_alternating_row_backgrounds(rgb(0xffffff), rgb(0xf5f5f5))
+            .with_row_separators(rgb(0xe0e0e0))
             .h_full(),
         )
     }
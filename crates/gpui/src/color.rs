@@ -822,13 +822,24 @@ impl LinearColorStop {
 impl Background {
     /// Returns the solid color if this is a solid background, None otherwise.
     pub fn as_solid(&self) -> Option<Hsla> {
-        if self.tag == BackgroundTag::Solid {
+        if self.is_solid() {
             Some(self.solid)
         } else {
             None
         }
     }
 
+    /// Returns whether this background is a single solid color, which renderers
+    /// can fill without evaluating a gradient or pattern per fragment.
+    pub fn is_solid(&self) -> bool {
+        self.tag == BackgroundTag::Solid
+    }
+
+    /// Returns whether this background is a linear gradient.
+    pub fn is_gradient(&self) -> bool {
+        self.tag == BackgroundTag::LinearGradient
+    }
+
     /// Use specified color space for color interpolation.
     ///
     /// <https://developer.mozilla.org/en-US/docs/Web/CSS/color-interpolation-method>
@@ -953,4 +964,27 @@ mod tests {
         assert!(!background.is_transparent());
         assert!(background.opacity(0.0).is_transparent());
     }
+
+    #[test]
+    fn test_background_kind_accessors() {
+        let color = Hsla::from(rgba(0xff0099ff));
+        let solid = Background::from(color);
+        assert!(solid.is_solid());
+        assert!(!solid.is_gradient());
+        assert_eq!(solid.as_solid(), Some(color));
+
+        let gradient = linear_gradient(
+            90.0,
+            linear_color_stop(rgba(0xff0099ff), 0.0),
+            linear_color_stop(rgba(0x00ff99ff), 1.0),
+        );
+        assert!(!gradient.is_solid());
+        assert!(gradient.is_gradient());
+        assert_eq!(gradient.as_solid(), None);
+
+        let pattern = pattern_slash(color, 1.0, 2.0);
+        assert!(!pattern.is_solid());
+        assert!(!pattern.is_gradient());
+        assert_eq!(pattern.as_solid(), None);
+    }
 }
@@ -5,10 +5,10 @@
 //! elements with uniform height.
 
 use crate::{
-    AnyElement, App, AvailableSpace, Bounds, ContentMask, Element, ElementId, Entity,
-    GlobalElementId, Hitbox, InspectorElementId, InteractiveElement, Interactivity, IntoElement,
-    IsZero, LayoutId, ListSizingBehavior, Overflow, Pixels, Point, ScrollHandle, Size,
-    StyleRefinement, Styled, Window, point, size,
+    AnyElement, App, AvailableSpace, Background, Bounds, ContentMask, Element, ElementId, Entity,
+    GlobalElementId, Hitbox, Hsla, InspectorElementId, InteractiveElement, Interactivity,
+    IntoElement, IsZero, LayoutId, ListSizingBehavior, Overflow, PaintQuad, Pixels, Point,
+    ScrollHandle, Size, StyleRefinement, Styled, Window, fill, point, px, size,
 };
 use smallvec::SmallVec;
 use std::{cell::RefCell, cmp, ops::Range, rc::Rc, usize};
@@ -51,6 +51,9 @@ where
         scroll_handle: None,
         sizing_behavior: ListSizingBehavior::default(),
         horizontal_sizing_behavior: ListHorizontalSizingBehavior::default(),
+        on_reached_end: None,
+        row_separator_color: None,
+        row_backgrounds: None,
     }
 }
 
@@ -66,12 +69,24 @@ pub struct UniformList {
     scroll_handle: Option<UniformListScrollHandle>,
     sizing_behavior: ListSizingBehavior,
     horizontal_sizing_behavior: ListHorizontalSizingBehavior,
+    on_reached_end: Option<(usize, Rc<dyn Fn(&mut Window, &mut App)>)>,
+    row_separator_color: Option<Hsla>,
+    row_backgrounds: Option<[Background; 2]>,
 }
 
 /// Frame state used by the [UniformList].
 pub struct UniformListFrameState {
     items: SmallVec<[AnyElement; 32]>,
     decorations: SmallVec<[AnyElement; 2]>,
+    row_backgrounds: SmallVec<[PaintQuad; 32]>,
+    row_separators: SmallVec<[PaintQuad; 32]>,
+}
+
+/// Element state used to fire [`UniformList::on_reached_end`] once per approach.
+#[derive(Default)]
+struct ReachedEndState {
+    /// The item count at the time the callback last fired, while the end is still in view.
+    fired_at_item_count: Option<usize>,
 }
 
 /// A handle for controlling the scroll position of a uniform list.
@@ -115,12 +130,28 @@ pub struct DeferredScrollToItem {
 pub struct UniformListScrollState {
     pub base_handle: ScrollHandle,
     pub deferred_scroll_to_item: Option<DeferredScrollToItem>,
+    /// The item whose trailing edge should be scrolled into view horizontally.
+    pub deferred_reveal_item_end: Option<usize>,
+    /// The item most recently scrolled into view horizontally, whose width keeps
+    /// the content wide enough to hold the scroll offset on later frames.
+    pub revealed_item: Option<RevealedItem>,
     /// Size of the item, captured during last layout.
     pub last_item_size: Option<ItemSize>,
     /// Whether the list was vertically flipped during last layout.
     pub y_flipped: bool,
 }
 
+/// An item whose trailing edge was scrolled into view horizontally.
+#[derive(Copy, Clone, Debug)]
+pub struct RevealedItem {
+    /// The index of the item.
+    pub index: usize,
+    /// The width of the item when it was revealed.
+    pub width: Pixels,
+    /// The number of items in the list when the item was revealed.
+    pub item_count: usize,
+}
+
 #[derive(Copy, Clone, Debug, Default)]
 /// The size of the item and its contents.
 pub struct ItemSize {
@@ -137,6 +168,8 @@ impl UniformListScrollHandle {
         Self(Rc::new(RefCell::new(UniformListScrollState {
             base_handle: ScrollHandle::new(),
             deferred_scroll_to_item: None,
+            deferred_reveal_item_end: None,
+            revealed_item: None,
             last_item_size: None,
             y_flipped: false,
         })))
@@ -212,6 +245,14 @@ impl UniformListScrollHandle {
         });
     }
 
+    /// Scroll the list horizontally so that the end of the given item is visible.
+    ///
+    /// Only has an effect when the list uses [`ListHorizontalSizingBehavior::Unconstrained`].
+    /// If the item's end is already visible, no scrolling occurs.
+    pub fn reveal_item_end(&self, ix: usize) {
+        self.0.borrow_mut().deferred_reveal_item_end = Some(ix);
+    }
+
     /// Check if the list is flipped vertically.
     pub fn y_flipped(&self) -> bool {
         self.0.borrow().y_flipped
@@ -312,6 +353,8 @@ impl Element for UniformList {
             UniformListFrameState {
                 items: SmallVec::new(),
                 decorations: SmallVec::new(),
+                row_backgrounds: SmallVec::new(),
+                row_separators: SmallVec::new(),
             },
         )
     }
@@ -345,8 +388,37 @@ impl Element for UniformList {
         );
 
         let longest_item_size = self.measure_item(None, window, cx);
+        let deferred_reveal_item_end = self
+            .scroll_handle
+            .as_ref()
+            .and_then(|handle| handle.0.borrow_mut().deferred_reveal_item_end.take());
+        let revealed_item = deferred_reveal_item_end
+            .filter(|_| can_scroll_horizontally)
+            .filter(|ix| *ix < self.item_count)
+            .map(|ix| RevealedItem {
+                index: ix,
+                width: self.measure_item_at(ix, None, window, cx).width,
+                item_count: self.item_count,
+            });
+        let revealed_item_width = revealed_item.map(|item| item.width);
+        let held_item_width = self.scroll_handle.as_ref().and_then(|handle| {
+            let mut handle = handle.0.borrow_mut();
+            if revealed_item.is_some() {
+                handle.revealed_item = revealed_item;
+            } else if handle
+                .revealed_item
+                .is_some_and(|item| item.item_count != self.item_count)
+            {
+                handle.revealed_item = None;
+            }
+            handle.revealed_item.map(|item| item.width)
+        });
         let content_width = if can_scroll_horizontally {
-            padded_bounds.size.width.max(longest_item_size.width)
+            padded_bounds
+                .size
+                .width
+                .max(longest_item_size.width)
+                .max(held_item_width.unwrap_or_default())
         } else {
             padded_bounds.size.width
         };
@@ -458,6 +530,16 @@ impl Element for UniformList {
                         scroll_offset = *updated_scroll_offset
                     }
 
+                    if let Some(item_width) = revealed_item_width {
+                        let list_width = padded_bounds.size.width;
+                        let mut updated_scroll_offset = shared_scroll_offset.borrow_mut();
+                        let scroll_left = -updated_scroll_offset.x;
+                        if item_width > scroll_left + list_width || item_width < scroll_left {
+                            updated_scroll_offset.x = -(item_width - list_width).max(Pixels::ZERO);
+                        }
+                        scroll_offset = *updated_scroll_offset;
+                    }
+
                     let first_visible_element_ix =
                         (-(scroll_offset.y + padding.top) / item_height).floor() as usize;
                     let last_visible_element_ix = ((-scroll_offset.y + padded_bounds.size.height)
@@ -467,6 +549,48 @@ impl Element for UniformList {
                     let visible_range = first_visible_element_ix
                         ..cmp::min(last_visible_element_ix, self.item_count);
 
+                    // Once the revealed item scrolls out of view, stop holding the
+                    // content open for it so the list can shrink back on the next frame.
+                    if let Some(handle) = self.scroll_handle.as_ref() {
+                        let mut handle = handle.0.borrow_mut();
+                        let visible_items = if y_flipped {
+                            self.item_count.saturating_sub(visible_range.end)
+                                ..self.item_count.saturating_sub(visible_range.start)
+                        } else {
+                            visible_range.clone()
+                        };
+                        if handle
+                            .revealed_item
+                            .is_some_and(|item| !visible_items.contains(&item.index))
+                        {
+                            handle.revealed_item = None;
+                        }
+                    }
+
+                    if let Some((threshold, callback)) = self.on_reached_end.as_ref() {
+                        let visible_end = if y_flipped {
+                            self.item_count.saturating_sub(visible_range.start)
+                        } else {
+                            visible_range.end
+                        };
+                        let item_count = self.item_count;
+                        let is_near_end = visible_end + threshold >= item_count;
+                        let should_fire = window.with_optional_element_state::<ReachedEndState, _>(
+                            global_id,
+                            |state, _| {
+                                let mut state = state.flatten().unwrap_or_default();
+                                let should_fire =
+                                    is_near_end && state.fired_at_item_count != Some(item_count);
+                                state.fired_at_item_count = is_near_end.then_some(item_count);
+                                (should_fire, Some(state))
+                            },
+                        );
+                        if should_fire {
+                            let callback = callback.clone();
+                            window.defer(cx, move |window, cx| callback(window, cx));
+                        }
+                    }
+
                     let items = if y_flipped {
                         let flipped_range = self.item_count.saturating_sub(visible_range.end)
                             ..self.item_count.saturating_sub(visible_range.start);
@@ -496,6 +620,30 @@ impl Element for UniformList {
                             item.layout_as_root(available_space, window, cx);
                             item.prepaint_at(item_origin, window, cx);
                             frame_state.items.push(item);
+
+                            let row_bounds =
+                                Bounds::new(item_origin, size(content_size.width, item_height));
+                            let item_index = if y_flipped {
+                                self.item_count - 1 - ix
+                            } else {
+                                ix
+                            };
+                            if let Some(row_backgrounds) = self.row_backgrounds {
+                                frame_state
+                                    .row_backgrounds
+                                    .push(fill(row_bounds, row_backgrounds[item_index % 2]));
+                            }
+                            if let Some(color) = self.row_separator_color
+                                && ix + 1 < self.item_count
+                            {
+                                let separator_bounds = Bounds::new(
+                                    point(row_bounds.left(), row_bounds.bottom() - px(1.)),
+                                    size(row_bounds.size.width, px(1.)),
+                                );
+                                frame_state
+                                    .row_separators
+                                    .push(fill(separator_bounds, color));
+                            }
                         }
 
                         let bounds =
@@ -544,9 +692,15 @@ impl Element for UniformList {
             window,
             cx,
             |_, window, cx| {
+                for row_background in request_layout.row_backgrounds.drain(..) {
+                    window.paint_quad(row_background);
+                }
                 for item in &mut request_layout.items {
                     item.paint(window, cx);
                 }
+                for row_separator in request_layout.row_separators.drain(..) {
+                    window.paint_quad(row_separator);
+                }
                 for decoration in &mut request_layout.decorations {
                     decoration.paint(window, cx);
                 }
@@ -637,6 +791,35 @@ impl UniformList {
         self
     }
 
+    /// Calls `callback` when the list is scrolled to within `threshold` items of its end,
+    /// e.g. to load more items. The callback fires once each time the end is approached,
+    /// and again if the item count changes while the end is still in view.
+    pub fn on_reached_end(
+        mut self,
+        threshold: usize,
+        callback: impl Fn(&mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_reached_end = Some((threshold, Rc::new(callback)));
+        self
+    }
+
+    /// Draws a one pixel line of the given color between rows. The line is drawn over
+    /// the bottom edge of each row, so it doesn't change the height of the rows.
+    pub fn with_row_separators(mut self, color: impl Into<Hsla>) -> Self {
+        self.row_separator_color = Some(color.into());
+        self
+    }
+
+    /// Paints the given backgrounds behind even and odd rows, respectively.
+    pub fn with_alternating_row_backgrounds(
+        mut self,
+        even: impl Into<Background>,
+        odd: impl Into<Background>,
+    ) -> Self {
+        self.row_backgrounds = Some([even.into(), odd.into()]);
+        self
+    }
+
     /// Adds a decoration element to the list.
     pub fn with_decoration(mut self, decoration: impl UniformListDecoration + 'static) -> Self {
         self.decorations.push(Box::new(decoration));
@@ -648,12 +831,22 @@ impl UniformList {
         list_width: Option<Pixels>,
         window: &mut Window,
         cx: &mut App,
+    ) -> Size<Pixels> {
+        self.measure_item_at(self.item_to_measure_index, list_width, window, cx)
+    }
+
+    fn measure_item_at(
+        &self,
+        item_ix: usize,
+        list_width: Option<Pixels>,
+        window: &mut Window,
+        cx: &mut App,
     ) -> Size<Pixels> {
         if self.item_count == 0 {
             return Size::default();
         }
 
-        let item_ix = cmp::min(self.item_to_measure_index, self.item_count - 1);
+        let item_ix = cmp::min(item_ix, self.item_count - 1);
         let mut items = (self.render_items)(item_ix..item_ix + 1, window, cx);
         let Some(mut item_to_measure) = items.pop() else {
             return Size::default();
@@ -850,4 +1043,213 @@ mod test {
             })
         }
     }
+
+    #[gpui::test]
+    fn test_reveal_item_end(cx: &mut TestAppContext) {
+        use crate::{
+            Context, ListHorizontalSizingBehavior, ScrollStrategy, UniformListScrollHandle, Window,
+            div, prelude::*, px, uniform_list,
+        };
+        use std::ops::Range;
+
+        struct TestView {
+            scroll_handle: UniformListScrollHandle,
+        }
+
+        impl Render for TestView {
+            fn render(
+                &mut self,
+                _window: &mut Window,
+                _cx: &mut Context<Self>,
+            ) -> impl IntoElement {
+                div().size_full().child(
+                    uniform_list("entries", 50, |range: Range<usize>, _window, _cx| {
+                        range
+                            .map(|ix| {
+                                let width = if ix == 30 { px(1000.) } else { px(100.) };
+                                div().id(ix).w(width).h(px(20.))
+                            })
+                            .collect()
+                    })
+                    .with_horizontal_sizing_behavior(ListHorizontalSizingBehavior::Unconstrained)
+                    .track_scroll(&self.scroll_handle)
+                    .w(px(200.))
+                    .h(px(200.)),
+                )
+            }
+        }
+
+        let scroll_handle = UniformListScrollHandle::new();
+        let (_view, cx) = cx.add_window_view({
+            let scroll_handle = scroll_handle.clone();
+            |_, _| TestView { scroll_handle }
+        });
+        assert_eq!(scroll_handle.0.borrow().base_handle.offset().x, px(0.));
+
+        // Selecting the wide row scrolls horizontally to show its end.
+        scroll_handle.scroll_to_item(30, ScrollStrategy::Nearest);
+        scroll_handle.reveal_item_end(30);
+        cx.update(|window, _| window.refresh());
+        cx.run_until_parked();
+        assert_eq!(scroll_handle.0.borrow().base_handle.offset().x, px(-800.));
+
+        // The offset survives later frames, even though the wide row is no
+        // longer the one being revealed.
+        cx.update(|window, _| window.refresh());
+        cx.run_until_parked();
+        assert_eq!(scroll_handle.0.borrow().base_handle.offset().x, px(-800.));
+
+        // Selecting a narrow row scrolls back to show it, and the content
+        // shrinks back to the list's width.
+        let content_width = |scroll_handle: &UniformListScrollHandle| {
+            scroll_handle
+                .0
+                .borrow()
+                .last_item_size
+                .map(|size| size.contents.width)
+        };
+        assert_eq!(content_width(&scroll_handle), Some(px(1000.)));
+        scroll_handle.scroll_to_item(0, ScrollStrategy::Nearest);
+        scroll_handle.reveal_item_end(0);
+        cx.update(|window, _| window.refresh());
+        cx.run_until_parked();
+        assert_eq!(scroll_handle.0.borrow().base_handle.offset().x, px(0.));
+        cx.update(|window, _| window.refresh());
+        cx.run_until_parked();
+        assert_eq!(content_width(&scroll_handle), Some(px(200.)));
+
+        // Scrolling the wide row out of view releases its width as well.
+        scroll_handle.scroll_to_item(30, ScrollStrategy::Nearest);
+        scroll_handle.reveal_item_end(30);
+        cx.update(|window, _| window.refresh());
+        cx.run_until_parked();
+        assert_eq!(content_width(&scroll_handle), Some(px(1000.)));
+        scroll_handle.scroll_to_item(0, ScrollStrategy::Nearest);
+        cx.update(|window, _| window.refresh());
+        cx.run_until_parked();
+        cx.update(|window, _| window.refresh());
+        cx.run_until_parked();
+        assert_eq!(content_width(&scroll_handle), Some(px(200.)));
+        assert_eq!(scroll_handle.0.borrow().base_handle.offset().x, px(0.));
+    }
+
+    #[gpui::test]
+    fn test_on_reached_end(cx: &mut TestAppContext) {
+        use crate::{
+            Context, ScrollStrategy, UniformListScrollHandle, Window, div, prelude::*, px,
+            uniform_list,
+        };
+        use std::{cell::Cell, ops::Range, rc::Rc};
+
+        struct TestView {
+            scroll_handle: UniformListScrollHandle,
+            reached_end_count: Rc<Cell<usize>>,
+        }
+
+        impl Render for TestView {
+            fn render(
+                &mut self,
+                _window: &mut Window,
+                _cx: &mut Context<Self>,
+            ) -> impl IntoElement {
+                let reached_end_count = self.reached_end_count.clone();
+                div().size_full().child(
+                    uniform_list("entries", 100, |range: Range<usize>, _window, _cx| {
+                        range.map(|ix| div().id(ix).h(px(20.))).collect()
+                    })
+                    .on_reached_end(5, move |_, _| {
+                        reached_end_count.set(reached_end_count.get() + 1)
+                    })
+                    .track_scroll(&self.scroll_handle)
+                    .h(px(200.)),
+                )
+            }
+        }
+
+        let scroll_handle = UniformListScrollHandle::new();
+        let reached_end_count = Rc::new(Cell::new(0));
+        let (_view, cx) = cx.add_window_view({
+            let scroll_handle = scroll_handle.clone();
+            let reached_end_count = reached_end_count.clone();
+            |_, _| TestView {
+                scroll_handle,
+                reached_end_count,
+            }
+        });
+        assert_eq!(reached_end_count.get(), 0);
+
+        let mut scroll_to = |ix: usize, strategy: ScrollStrategy| {
+            scroll_handle.scroll_to_item(ix, strategy);
+            cx.update(|window, _| window.refresh());
+            cx.run_until_parked();
+        };
+
+        // Scrolling near the end fires the callback once.
+        scroll_to(92, ScrollStrategy::Bottom);
+        assert_eq!(reached_end_count.get(), 1);
+
+        // Staying at the end doesn't fire it again.
+        scroll_to(99, ScrollStrategy::Bottom);
+        assert_eq!(reached_end_count.get(), 1);
+
+        // Leaving and approaching the end again fires it a second time.
+        scroll_to(0, ScrollStrategy::Top);
+        assert_eq!(reached_end_count.get(), 1);
+        scroll_to(99, ScrollStrategy::Bottom);
+        assert_eq!(reached_end_count.get(), 2);
+    }
+
+    #[gpui::test]
+    fn test_alternating_row_backgrounds(cx: &mut TestAppContext) {
+        use crate::{
+            Background, Context, ScaledPixels, Window, div, prelude::*, px, rgb, uniform_list,
+        };
+        use std::ops::Range;
+
+        let even_background = Background::from(rgb(0xeeeeee));
+        let odd_background = Background::from(rgb(0xdddddd));
+
+        struct TestView {
+            row_backgrounds: [Background; 2],
+        }
+
+        impl Render for TestView {
+            fn render(
+                &mut self,
+                _window: &mut Window,
+                _cx: &mut Context<Self>,
+            ) -> impl IntoElement {
+                let [even, odd] = self.row_backgrounds;
+                div().size_full().child(
+                    uniform_list("entries", 20, |range: Range<usize>, _window, _cx| {
+                        range.map(|ix| div().id(ix).h(px(20.))).collect()
+                    })
+                    .with_alternating_row_backgrounds(even, odd)
+                    .with_row_separators(rgb(0x000000))
+                    .h(px(200.)),
+                )
+            }
+        }
+
+        let (_view, cx) = cx.add_window_view(|_, _| TestView {
+            row_backgrounds: [even_background, odd_background],
+        });
+        cx.update(|window, _| {
+            let row_height = px(20.).scale(window.scale_factor());
+            let row_ix = |y: ScaledPixels| (y / row_height).round() as usize;
+            let quads = &window.rendered_frame.scene.quads;
+            let even_rows = quads
+                .iter()
+                .filter(|quad| quad.background == even_background)
+                .map(|quad| row_ix(quad.bounds.origin.y))
+                .collect::<Vec<_>>();
+            let odd_rows = quads
+                .iter()
+                .filter(|quad| quad.background == odd_background)
+                .map(|quad| row_ix(quad.bounds.origin.y))
+                .collect::<Vec<_>>();
+            assert_eq!(even_rows, [0, 2, 4, 6, 8]);
+            assert_eq!(odd_rows, [1, 3, 5, 7, 9]);
+        });
+    }
 }
@@ -175,6 +175,59 @@ impl Scene {
             surfaces_iter: self.surfaces.iter().peekable(),
         }
     }
+
+    /// Counts the primitives in this scene and the batches they would be drawn in,
+    /// without drawing anything. Call after [`Scene::finish`].
+    pub fn stats(&self) -> SceneStats {
+        SceneStats {
+            shadows: self.shadows.len(),
+            quads: self.quads.len(),
+            paths: self.paths.len(),
+            underlines: self.underlines.len(),
+            monochrome_sprites: self.monochrome_sprites.len(),
+            subpixel_sprites: self.subpixel_sprites.len(),
+            polychrome_sprites: self.polychrome_sprites.len(),
+            surfaces: self.surfaces.len(),
+            batches: self.batches().count(),
+        }
+    }
+}
+
+/// The number of primitives of each kind in a [`Scene`], as reported by [`Scene::stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SceneStats {
+    /// The number of shadows.
+    pub shadows: usize,
+    /// The number of quads.
+    pub quads: usize,
+    /// The number of paths.
+    pub paths: usize,
+    /// The number of underlines.
+    pub underlines: usize,
+    /// The number of monochrome sprites, such as glyphs and icons.
+    pub monochrome_sprites: usize,
+    /// The number of subpixel-rendered glyph sprites.
+    pub subpixel_sprites: usize,
+    /// The number of polychrome sprites, such as images and emoji.
+    pub polychrome_sprites: usize,
+    /// The number of surfaces.
+    pub surfaces: usize,
+    /// The number of batches the primitives are drawn in.
+    pub batches: usize,
+}
+
+impl SceneStats {
+    /// The total number of primitive instances across all kinds.
+    pub fn total_instances(&self) -> usize {
+        self.shadows
+            + self.quads
+            + self.paths
+            + self.underlines
+            + self.monochrome_sprites
+            + self.subpixel_sprites
+            + self.polychrome_sprites
+            + self.surfaces
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Default)]
@@ -894,3 +947,46 @@ impl PathVertex<Pixels> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContentMask, size};
+
+    #[test]
+    fn test_scene_stats() {
+        let mut scene = Scene::default();
+        for ix in 0..3 {
+            let bounds = Bounds::new(
+                point(ScaledPixels(ix as f32 * 10.), ScaledPixels(0.)),
+                size(ScaledPixels(10.), ScaledPixels(10.)),
+            );
+            scene.insert_primitive(Quad {
+                bounds,
+                content_mask: ContentMask { bounds },
+                ..Default::default()
+            });
+        }
+        // Primitives outside of their content mask are never added to the scene.
+        scene.insert_primitive(Quad {
+            bounds: Bounds::new(
+                point(ScaledPixels(100.), ScaledPixels(100.)),
+                size(ScaledPixels(10.), ScaledPixels(10.)),
+            ),
+            ..Default::default()
+        });
+        scene.finish();
+
+        let stats = scene.stats();
+        assert_eq!(
+            stats,
+            SceneStats {
+                quads: 3,
+                batches: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(stats.paths, 0);
+        assert_eq!(stats.total_instances(), 3);
+    }
+}
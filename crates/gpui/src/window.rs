@@ -11,7 +11,7 @@ use crate::{
     MouseMoveEvent, MouseUpEvent, Path, Pixels, PlatformAtlas, PlatformDisplay, PlatformInput,
     PlatformInputHandler, PlatformWindow, Point, PolychromeSprite, Priority, PromptButton,
     PromptLevel, Quad, Render, RenderGlyphParams, RenderImage, RenderImageParams, RenderSvgParams,
-    Replay, ResizeEdge, SMOOTH_SVG_SCALE_FACTOR, SUBPIXEL_VARIANTS_X, SUBPIXEL_VARIANTS_Y,
+    Replay, ResizeEdge, Rgba, SMOOTH_SVG_SCALE_FACTOR, SUBPIXEL_VARIANTS_X, SUBPIXEL_VARIANTS_Y,
     ScaledPixels, Scene, Shadow, SharedString, Size, StrikethroughStyle, Style, SubpixelSprite,
     SubscriberSet, Subscription, SystemWindowTab, SystemWindowTabController, TabStopMap,
     TaffyLayoutEngine, Task, TextRenderingMode, TextStyle, TextStyleRefinement, ThermalState,
@@ -1977,6 +1977,13 @@ impl Window {
             .render_to_image(&self.rendered_frame.scene)
     }
 
+    /// Returns counts of the primitives in the current frame's scene, so tests can check what
+    /// was painted without rasterizing it.
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn rendered_scene_stats(&self) -> crate::SceneStats {
+        self.rendered_frame.scene.stats()
+    }
+
     /// Set the content size of the window.
     pub fn resize(&mut self, size: Size<Pixels>) {
         self.platform_window.resize(size);
@@ -2086,6 +2093,13 @@ impl Window {
             .set_background_appearance(background_appearance);
     }
 
+    /// Sets the color that opaque windows are cleared to before each frame, so that
+    /// areas exposed while resizing match the window's contents. Only some
+    /// platforms honor this.
+    pub fn set_clear_color(&self, color: impl Into<Rgba>) {
+        self.platform_window.set_clear_color(color.into());
+    }
+
     /// Mark the window as dirty at the platform level.
     pub fn set_window_edited(&mut self, edited: bool) {
         self.platform_window.set_edited(edited);
@@ -23,6 +23,11 @@ struct DirectXAtlasState {
     polychrome_textures: AtlasTextureList<DirectXAtlasTexture>,
     subpixel_textures: AtlasTextureList<DirectXAtlasTexture>,
     tiles_by_key: FxHashMap<AtlasKey, AtlasTile>,
+    /// Incremented after every drawn frame, used to find textures that haven't been drawn from recently.
+    frame: u64,
+    /// How many bytes of textures the atlas may keep before it starts evicting
+    /// least recently used ones. `None` means the atlas grows without bound.
+    capacity_bytes: Option<u64>,
 }
 
 struct DirectXAtlasTexture {
@@ -32,6 +37,7 @@ struct DirectXAtlasTexture {
     texture: ID3D11Texture2D,
     view: [Option<ID3D11ShaderResourceView>; 1],
     live_atlas_keys: u32,
+    last_used_frame: u64,
 }
 
 impl DirectXAtlas {
@@ -43,18 +49,35 @@ impl DirectXAtlas {
             polychrome_textures: Default::default(),
             subpixel_textures: Default::default(),
             tiles_by_key: Default::default(),
+            frame: 0,
+            capacity_bytes: None,
         }))
     }
 
+    /// Returns the view of the given texture, marking it as used by the frame being drawn.
     pub(crate) fn get_texture_view(
         &self,
         id: AtlasTextureId,
     ) -> [Option<ID3D11ShaderResourceView>; 1] {
-        let lock = self.0.lock();
+        let mut lock = self.0.lock();
+        lock.touch(id);
         let tex = lock.texture(id);
         tex.view.clone()
     }
 
+    pub(crate) fn set_capacity(&self, capacity_bytes: Option<u64>) {
+        self.0.lock().capacity_bytes = capacity_bytes;
+    }
+
+    /// Called once a frame has been drawn. If the atlas is over capacity, evicts
+    /// the least recently used textures that the frame didn't draw from. Their
+    /// tiles are rasterized again the next time they're requested.
+    pub(crate) fn end_frame(&self) {
+        let mut lock = self.0.lock();
+        lock.evict_least_recently_used();
+        lock.frame += 1;
+    }
+
     pub(crate) fn handle_device_lost(
         &self,
         device: &ID3D11Device,
@@ -79,8 +102,9 @@ impl PlatformAtlas for DirectXAtlas {
         >,
     ) -> anyhow::Result<Option<AtlasTile>> {
         let mut lock = self.0.lock();
-        if let Some(tile) = lock.tiles_by_key.get(key) {
-            Ok(Some(tile.clone()))
+        if let Some(tile) = lock.tiles_by_key.get(key).cloned() {
+            lock.touch(tile.texture_id);
+            Ok(Some(tile))
         } else {
             let Some((size, bytes)) = build()? else {
                 return Ok(None);
@@ -88,6 +112,7 @@ impl PlatformAtlas for DirectXAtlas {
             let tile = lock
                 .allocate(size, key.texture_kind())
                 .ok_or_else(|| anyhow::anyhow!("failed to allocate"))?;
+            lock.touch(tile.texture_id);
             let texture = lock.texture(tile.texture_id);
             texture.upload(&lock.device_context, tile.bounds, &bytes);
             lock.tiles_by_key.insert(key.clone(), tile.clone());
@@ -233,6 +258,7 @@ impl DirectXAtlasState {
             texture,
             view,
             live_atlas_keys: 0,
+            last_used_frame: self.frame,
         };
         if let Some(ix) = index {
             texture_list.textures[ix] = Some(atlas_texture);
@@ -243,6 +269,63 @@ impl DirectXAtlasState {
         }
     }
 
+    fn textures_mut(
+        &mut self,
+        kind: AtlasTextureKind,
+    ) -> &mut AtlasTextureList<DirectXAtlasTexture> {
+        match kind {
+            AtlasTextureKind::Monochrome => &mut self.monochrome_textures,
+            AtlasTextureKind::Polychrome => &mut self.polychrome_textures,
+            AtlasTextureKind::Subpixel => &mut self.subpixel_textures,
+        }
+    }
+
+    fn touch(&mut self, id: AtlasTextureId) {
+        let frame = self.frame;
+        if let Some(Some(texture)) = self
+            .textures_mut(id.kind)
+            .textures
+            .get_mut(id.index as usize)
+        {
+            texture.last_used_frame = frame;
+        }
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let Some(capacity_bytes) = self.capacity_bytes else {
+            return;
+        };
+        let textures = self
+            .monochrome_textures
+            .textures
+            .iter()
+            .chain(&self.polychrome_textures.textures)
+            .chain(&self.subpixel_textures.textures)
+            .flatten();
+        let mut total_bytes: u64 = textures.clone().map(|texture| texture.bytes()).sum();
+        if total_bytes <= capacity_bytes {
+            return;
+        }
+
+        // Textures drawn from in this frame may still be referenced by the scene, so they're never evicted.
+        let mut candidates = textures
+            .filter(|texture| texture.last_used_frame < self.frame)
+            .map(|texture| (texture.last_used_frame, texture.id, texture.bytes()))
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(last_used_frame, _, _)| *last_used_frame);
+
+        for (_, id, bytes) in candidates {
+            if total_bytes <= capacity_bytes {
+                break;
+            }
+            let textures = self.textures_mut(id.kind);
+            textures.textures[id.index as usize] = None;
+            textures.free_list.push(id.index as usize);
+            self.tiles_by_key.retain(|_, tile| tile.texture_id != id);
+            total_bytes -= bytes;
+        }
+    }
+
     fn texture(&self, id: AtlasTextureId) -> &DirectXAtlasTexture {
         match id.kind {
             AtlasTextureKind::Monochrome => &self.monochrome_textures[id.index as usize]
@@ -299,6 +382,11 @@ impl DirectXAtlasTexture {
         }
     }
 
+    fn bytes(&self) -> u64 {
+        let size = self.allocator.size();
+        size.width as u64 * size.height as u64 * self.bytes_per_pixel as u64
+    }
+
     fn decrement_ref_count(&mut self) {
         self.live_atlas_keys -= 1;
     }
@@ -318,3 +406,120 @@ fn etagere_point_to_device(value: etagere::Point) -> Point<DevicePixels> {
         y: DevicePixels::from(value.y),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DirectXDevices;
+    use gpui::{FontId, GlyphId, RenderGlyphParams, point, px, size};
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_glyphs_at_different_scales_coexist() {
+        let devices = DirectXDevices::new().unwrap();
+        let atlas = DirectXAtlas::new(&devices.device, &devices.device_context);
+        let glyph_key = |scale_factor| {
+            AtlasKey::Glyph(RenderGlyphParams {
+                font_id: FontId(0),
+                glyph_id: GlyphId(42),
+                font_size: px(14.),
+                subpixel_variant: point(0, 0),
+                scale_factor,
+                is_emoji: false,
+                subpixel_rendering: false,
+            })
+        };
+
+        let tile_at_1x = atlas
+            .get_or_insert_with(&glyph_key(1.), &mut || {
+                Ok(Some((
+                    size(DevicePixels(8), DevicePixels(8)),
+                    Cow::Owned(vec![0xff; 8 * 8]),
+                )))
+            })
+            .unwrap()
+            .unwrap();
+        let tile_at_2x = atlas
+            .get_or_insert_with(&glyph_key(2.), &mut || {
+                Ok(Some((
+                    size(DevicePixels(16), DevicePixels(16)),
+                    Cow::Owned(vec![0xff; 16 * 16]),
+                )))
+            })
+            .unwrap()
+            .unwrap();
+        assert_ne!(tile_at_1x, tile_at_2x);
+
+        // Looking up either scale again reuses its tile instead of rasterizing.
+        let mut rasterized = false;
+        for (key, tile) in [(glyph_key(1.), tile_at_1x), (glyph_key(2.), tile_at_2x)] {
+            let cached_tile = atlas
+                .get_or_insert_with(&key, &mut || {
+                    rasterized = true;
+                    Ok(None)
+                })
+                .unwrap();
+            assert_eq!(cached_tile, Some(tile));
+        }
+        assert!(!rasterized);
+    }
+
+    #[test]
+    fn test_exceeding_capacity_evicts_least_recently_used_textures() {
+        let devices = DirectXDevices::new().unwrap();
+        let atlas = DirectXAtlas::new(&devices.device, &devices.device_context);
+        // Each tile is too large to share a 1024x1024 monochrome texture, so every
+        // glyph gets a texture of its own, taking 1 MiB.
+        atlas.set_capacity(Some(2 * 1024 * 1024));
+        let glyph_key = |glyph_id| {
+            AtlasKey::Glyph(RenderGlyphParams {
+                font_id: FontId(0),
+                glyph_id: GlyphId(glyph_id),
+                font_size: px(14.),
+                subpixel_variant: point(0, 0),
+                scale_factor: 1.,
+                is_emoji: false,
+                subpixel_rendering: false,
+            })
+        };
+        let rasterized = std::cell::Cell::new(0);
+        let get_glyph = |glyph_id| {
+            atlas
+                .get_or_insert_with(&glyph_key(glyph_id), &mut || {
+                    rasterized.set(rasterized.get() + 1);
+                    Ok(Some((
+                        size(DevicePixels(600), DevicePixels(600)),
+                        Cow::Owned(vec![0xff; 600 * 600]),
+                    )))
+                })
+                .unwrap()
+                .unwrap()
+        };
+
+        get_glyph(1);
+        atlas.end_frame();
+        get_glyph(2);
+        atlas.end_frame();
+        get_glyph(3);
+        atlas.end_frame();
+        assert_eq!(rasterized.get(), 3);
+
+        // The oldest glyph was evicted to get back under capacity, the others are still cached.
+        get_glyph(2);
+        get_glyph(3);
+        assert_eq!(rasterized.get(), 3);
+        let tile = get_glyph(1);
+        assert_eq!(rasterized.get(), 4);
+        assert!(atlas.get_texture_view(tile.texture_id)[0].is_some());
+        atlas.end_frame();
+
+        // Textures used by the frame are kept, even if that leaves the atlas over capacity.
+        atlas.set_capacity(Some(1024 * 1024));
+        get_glyph(1);
+        get_glyph(2);
+        atlas.end_frame();
+        get_glyph(1);
+        get_glyph(2);
+        assert_eq!(rasterized.get(), 4);
+    }
+}
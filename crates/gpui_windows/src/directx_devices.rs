@@ -1,3 +1,8 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
 use anyhow::{Context, Result};
 use itertools::Itertools;
 use util::ResultExt;
@@ -35,12 +40,94 @@ pub(crate) fn try_to_recover_from_device_lost<T>(mut f: impl FnMut() -> Result<T
         .context("DirectXRenderer failed to recover from lost device after multiple attempts")
 }
 
+/// How long to wait before recreating the devices after the first device loss. This gives
+/// the system time to recover, otherwise the final drawing result will be blank.
+const DEVICE_LOST_BASE_DELAY: Duration = Duration::from_millis(350);
+/// The window within which consecutive device losses count towards the recreation limit.
+const DEVICE_LOST_WINDOW: Duration = Duration::from_secs(60);
+/// The number of device recreations allowed within [`DEVICE_LOST_WINDOW`].
+const MAX_DEVICE_RECREATIONS: usize = 5;
+
+/// What the VSync thread should do with the GPU devices on a given frame.
+#[derive(Debug, PartialEq)]
+pub(crate) enum DeviceRecoveryStep {
+    /// The devices are usable, so windows can be redrawn.
+    Render,
+    /// The devices need to be recreated, but not before the system had time to recover.
+    Wait,
+    /// The devices should be recreated now.
+    Recreate,
+}
+
+/// Schedules device recreation for the VSync thread without blocking it.
+///
+/// When the GPU device keeps getting lost (e.g. while a driver update is in progress), the
+/// delay before each recreation doubles, and recovery is given up once it was lost too often,
+/// so that a wedged GPU doesn't keep recreating devices forever.
+#[derive(Default)]
+pub(crate) struct DeviceRecovery {
+    recent_losses: VecDeque<Instant>,
+    recreate_at: Option<Instant>,
+}
+
+impl DeviceRecovery {
+    /// Decides what to do on the frame at `now`. `device_lost` is only checked when no
+    /// recreation is pending. Devices that were invalidated without the GPU being lost (e.g.
+    /// after a failed resize) are recreated right away and don't count towards the limit.
+    ///
+    /// Returns an error once the device was lost too many times recently.
+    pub(crate) fn poll(
+        &mut self,
+        now: Instant,
+        device_lost: impl FnOnce() -> bool,
+        devices_invalidated: bool,
+    ) -> Result<DeviceRecoveryStep> {
+        if self.recreate_at.is_none() {
+            if device_lost() {
+                self.recent_losses
+                    .retain(|lost_at| now.duration_since(*lost_at) < DEVICE_LOST_WINDOW);
+                anyhow::ensure!(
+                    self.recent_losses.len() < MAX_DEVICE_RECREATIONS,
+                    "GPU device was lost {} times within {} seconds",
+                    self.recent_losses.len() + 1,
+                    DEVICE_LOST_WINDOW.as_secs()
+                );
+                let delay = DEVICE_LOST_BASE_DELAY * 2u32.pow(self.recent_losses.len() as u32);
+                self.recent_losses.push_back(now);
+                self.recreate_at = Some(now + delay);
+            } else if devices_invalidated {
+                self.recreate_at = Some(now);
+            }
+        }
+
+        Ok(match self.recreate_at {
+            Some(recreate_at) if recreate_at <= now => {
+                self.recreate_at = None;
+                DeviceRecoveryStep::Recreate
+            }
+            Some(_) => DeviceRecoveryStep::Wait,
+            None => DeviceRecoveryStep::Render,
+        })
+    }
+}
+
+/// The feature levels requested when creating a device, from most to least capable.
+/// 4x MSAA is required for Direct3D Feature Level 10.1 or better.
+const REQUESTED_FEATURE_LEVELS: [D3D_FEATURE_LEVEL; 3] = [
+    D3D_FEATURE_LEVEL_11_1,
+    D3D_FEATURE_LEVEL_11_0,
+    D3D_FEATURE_LEVEL_10_1,
+];
+
 #[derive(Clone)]
 pub(crate) struct DirectXDevices {
     pub(crate) adapter: IDXGIAdapter1,
     pub(crate) dxgi_factory: IDXGIFactory6,
     pub(crate) device: ID3D11Device,
     pub(crate) device_context: ID3D11DeviceContext,
+    /// The feature level the device was actually created with, which is one of
+    /// [`REQUESTED_FEATURE_LEVELS`].
+    pub(crate) feature_level: D3D_FEATURE_LEVEL,
 }
 
 impl DirectXDevices {
@@ -50,26 +137,34 @@ impl DirectXDevices {
             get_dxgi_factory(debug_layer_available).context("Creating DXGI factory")?;
         let (adapter, device, device_context, feature_level) =
             get_adapter(&dxgi_factory, debug_layer_available).context("Getting DXGI adapter")?;
-        match feature_level {
-            D3D_FEATURE_LEVEL_11_1 => {
-                log::info!("Created device with Direct3D 11.1 feature level.")
-            }
-            D3D_FEATURE_LEVEL_11_0 => {
-                log::info!("Created device with Direct3D 11.0 feature level.")
-            }
-            D3D_FEATURE_LEVEL_10_1 => {
-                log::info!("Created device with Direct3D 10.1 feature level.")
-            }
-            _ => unreachable!(),
-        }
+        log::info!(
+            "Created device with Direct3D {} feature level.",
+            feature_level_name(feature_level)
+        );
 
         Ok(Self {
             adapter,
             dxgi_factory,
             device,
             device_context,
+            feature_level,
         })
     }
+
+    /// Whether the device was created with at least the given feature level, and
+    /// so supports everything that level guarantees.
+    pub(crate) fn supports_feature_level(&self, feature_level: D3D_FEATURE_LEVEL) -> bool {
+        self.feature_level.0 >= feature_level.0
+    }
+}
+
+fn feature_level_name(feature_level: D3D_FEATURE_LEVEL) -> &'static str {
+    match feature_level {
+        D3D_FEATURE_LEVEL_11_1 => "11.1",
+        D3D_FEATURE_LEVEL_11_0 => "11.0",
+        D3D_FEATURE_LEVEL_10_1 => "10.1",
+        _ => "unknown",
+    }
 }
 
 #[inline]
@@ -158,12 +253,7 @@ fn get_device(
             D3D_DRIVER_TYPE_UNKNOWN,
             HMODULE::default(),
             device_flags,
-            // 4x MSAA is required for Direct3D Feature Level 10.1 or better
-            Some(&[
-                D3D_FEATURE_LEVEL_11_1,
-                D3D_FEATURE_LEVEL_11_0,
-                D3D_FEATURE_LEVEL_10_1,
-            ]),
+            Some(&REQUESTED_FEATURE_LEVELS),
             D3D11_SDK_VERSION,
             Some(&mut device),
             feature_level,
@@ -192,3 +282,83 @@ fn get_device(
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_loss_waits_without_blocking() {
+        let mut recovery = DeviceRecovery::default();
+        let start = Instant::now();
+
+        let step = recovery.poll(start, || true, false).unwrap();
+        assert_eq!(step, DeviceRecoveryStep::Wait);
+        // While a recreation is pending, the device isn't checked again.
+        let step = recovery
+            .poll(start + DEVICE_LOST_BASE_DELAY / 2, || unreachable!(), false)
+            .unwrap();
+        assert_eq!(step, DeviceRecoveryStep::Wait);
+        let step = recovery
+            .poll(start + DEVICE_LOST_BASE_DELAY, || unreachable!(), false)
+            .unwrap();
+        assert_eq!(step, DeviceRecoveryStep::Recreate);
+        let step = recovery
+            .poll(start + DEVICE_LOST_BASE_DELAY, || false, false)
+            .unwrap();
+        assert_eq!(step, DeviceRecoveryStep::Render);
+    }
+
+    #[test]
+    fn test_repeated_device_loss_backs_off_and_gives_up() {
+        let mut recovery = DeviceRecovery::default();
+        let start = Instant::now();
+
+        // Repeated losses back off exponentially until the limit is reached.
+        let mut now = start;
+        for attempt in 0..MAX_DEVICE_RECREATIONS {
+            assert_eq!(
+                recovery.poll(now, || true, false).unwrap(),
+                DeviceRecoveryStep::Wait
+            );
+            now += DEVICE_LOST_BASE_DELAY * 2u32.pow(attempt as u32);
+            assert_eq!(
+                recovery.poll(now, || unreachable!(), false).unwrap(),
+                DeviceRecoveryStep::Recreate
+            );
+        }
+        assert!(recovery.poll(now, || true, false).is_err());
+
+        // Once the earliest losses fall out of the window, recovery is allowed again.
+        let now = start + DEVICE_LOST_WINDOW + Duration::from_millis(500);
+        assert_eq!(
+            recovery.poll(now, || true, false).unwrap(),
+            DeviceRecoveryStep::Wait
+        );
+    }
+
+    #[test]
+    fn test_invalidated_devices_are_recreated_without_counting_as_losses() {
+        let mut recovery = DeviceRecovery::default();
+        let now = Instant::now();
+
+        for _ in 0..MAX_DEVICE_RECREATIONS * 4 {
+            assert_eq!(
+                recovery.poll(now, || false, true).unwrap(),
+                DeviceRecoveryStep::Recreate
+            );
+        }
+        assert_eq!(
+            recovery.poll(now, || true, false).unwrap(),
+            DeviceRecoveryStep::Wait
+        );
+    }
+
+    #[test]
+    fn test_records_obtained_feature_level() {
+        let devices = DirectXDevices::new().unwrap();
+        assert!(REQUESTED_FEATURE_LEVELS.contains(&devices.feature_level));
+        assert!(devices.supports_feature_level(D3D_FEATURE_LEVEL_10_1));
+        assert!(devices.supports_feature_level(devices.feature_level));
+    }
+}
@@ -218,6 +218,7 @@ impl WindowsPlatform {
         WindowCreationInfo {
             icon: self.icon,
             executor: self.foreground_executor.clone(),
+            background_executor: self.background_executor.clone(),
             current_cursor: self.inner.state.current_cursor.get(),
             drop_target_helper: self.drop_target_helper.clone().unwrap(),
             validation_number: self.inner.validation_number,
@@ -308,20 +309,29 @@ impl WindowsPlatform {
             .name("VSyncProvider".to_owned())
             .spawn(move || {
                 let vsync_provider = VSyncProvider::new();
+                let mut device_recovery = DeviceRecovery::default();
                 loop {
                     vsync_provider.wait_for_vsync();
-                    if check_device_lost(&directx_device.device)
-                        || invalidate_devices.fetch_and(false, Ordering::Acquire)
-                    {
-                        if let Err(err) = handle_gpu_device_lost(
+                    let step = device_recovery.poll(
+                        std::time::Instant::now(),
+                        || check_device_lost(&directx_device.device),
+                        invalidate_devices.fetch_and(false, Ordering::Acquire),
+                    );
+                    let recovered = match step {
+                        Ok(DeviceRecoveryStep::Render) => Ok(()),
+                        Ok(DeviceRecoveryStep::Wait) => continue,
+                        Ok(DeviceRecoveryStep::Recreate) => handle_gpu_device_lost(
                             &mut directx_device,
                             platform_window.as_raw(),
                             validation_number,
                             &all_windows,
                             &text_system,
-                        ) {
-                            panic!("Device lost: {err}");
-                        }
+                        ),
+                        Err(err) => Err(err),
+                    };
+                    if let Err(err) = recovered {
+                        report_gpu_unavailable(&err);
+                        break;
                     }
                     let Some(all_windows) = all_windows.upgrade() else {
                         break;
@@ -1003,6 +1013,7 @@ impl Drop for WindowsPlatform {
 pub(crate) struct WindowCreationInfo {
     pub(crate) icon: HICON,
     pub(crate) executor: ForegroundExecutor,
+    pub(crate) background_executor: BackgroundExecutor,
     pub(crate) current_cursor: Option<HCURSOR>,
     pub(crate) drop_target_helper: IDropTargetHelper,
     pub(crate) validation_number: usize,
@@ -1219,6 +1230,23 @@ fn check_device_lost(device: &ID3D11Device) -> bool {
     }
 }
 
+/// Tells the user that windows stopped updating because the GPU device couldn't be recovered.
+fn report_gpu_unavailable(error: &anyhow::Error) {
+    log::error!("Stopped rendering after the GPU device was lost: {error:#}");
+    let message = HSTRING::from(format!(
+        "The graphics device stopped responding and could not be recovered, so windows will \
+        no longer update. Please restart the application.\n\n{error:#}"
+    ));
+    unsafe {
+        MessageBoxW(
+            None,
+            &message,
+            w!("Graphics Device Lost"),
+            MB_OK | MB_ICONERROR,
+        );
+    }
+}
+
 fn handle_gpu_device_lost(
     directx_devices: &mut DirectXDevices,
     platform_window: HWND,
@@ -1226,10 +1254,6 @@ fn handle_gpu_device_lost(
     all_windows: &std::sync::Weak<RwLock<SmallVec<[SafeHwnd; 4]>>>,
     text_system: &std::sync::Weak<DirectWriteTextSystem>,
 ) -> Result<()> {
-    // Here we wait a bit to ensure the system has time to recover from the device lost state.
-    // If we don't wait, the final drawing result will be blank.
-    std::thread::sleep(std::time::Duration::from_millis(350));
-
     *directx_devices = try_to_recover_from_device_lost(|| {
         DirectXDevices::new().context("Failed to recreate new DirectX devices after device lost")
     })?;
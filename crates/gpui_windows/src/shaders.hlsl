@@ -10,6 +10,12 @@ cbuffer GlobalParams: register(b0) {
 Texture2D<float4> t_sprite: register(t0);
 SamplerState s_sprite: register(s0);
 
+// The atlas pages a single sprite draw can sample from. A sprite reads from the
+// slot its atlas texture index maps to, so consecutive batches on different pages
+// can be drawn together. Must match `SPRITE_PAGE_COUNT` in directx_renderer.rs.
+#define SPRITE_PAGE_COUNT 4
+Texture2D<float4> t_sprite_pages[SPRITE_PAGE_COUNT]: register(t2);
+
 struct SubpixelSpriteFragmentOutput {
     float4 foreground : SV_Target0;
     float4 alpha : SV_Target1;
@@ -248,9 +254,35 @@ float4 over(float4 below, float4 above) {
     return result;
 }
 
+uint sprite_page(AtlasTile tile) {
+    return tile.texture_id.index % SPRITE_PAGE_COUNT;
+}
+
+// Resource arrays can only be indexed with literals in shader model 5.0, hence the switches.
+float2 sprite_page_size(uint page) {
+    float2 size;
+    switch (page) {
+        case 0: t_sprite_pages[0].GetDimensions(size.x, size.y); break;
+        case 1: t_sprite_pages[1].GetDimensions(size.x, size.y); break;
+        case 2: t_sprite_pages[2].GetDimensions(size.x, size.y); break;
+        default: t_sprite_pages[3].GetDimensions(size.x, size.y); break;
+    }
+    return size;
+}
+
+// Atlas textures have a single mip level, so sampling level 0 is equivalent to
+// `Sample` and doesn't need gradients inside the divergent switch.
+float4 sample_sprite_page(uint page, float2 tile_position) {
+    switch (page) {
+        case 0: return t_sprite_pages[0].SampleLevel(s_sprite, tile_position, 0);
+        case 1: return t_sprite_pages[1].SampleLevel(s_sprite, tile_position, 0);
+        case 2: return t_sprite_pages[2].SampleLevel(s_sprite, tile_position, 0);
+        default: return t_sprite_pages[3].SampleLevel(s_sprite, tile_position, 0);
+    }
+}
+
 float2 to_tile_position(float2 unit_vertex, AtlasTile tile) {
-    float2 atlas_size;
-    t_sprite.GetDimensions(atlas_size.x, atlas_size.y);
+    float2 atlas_size = sprite_page_size(sprite_page(tile));
     return (float2(tile.bounds.origin) + unit_vertex * float2(tile.bounds.size)) / atlas_size;
 }
 
@@ -1116,6 +1148,7 @@ struct MonochromeSpriteVertexOutput {
     float4 position: SV_Position;
     float2 tile_position: POSITION;
     nointerpolation float4 color: COLOR;
+    nointerpolation uint page: PAGE;
     float4 clip_distance: SV_ClipDistance;
 };
 
@@ -1123,6 +1156,7 @@ struct MonochromeSpriteFragmentInput {
     float4 position: SV_Position;
     float2 tile_position: POSITION;
     nointerpolation float4 color: COLOR;
+    nointerpolation uint page: PAGE;
     float4 clip_distance: SV_ClipDistance;
 };
 
@@ -1141,12 +1175,13 @@ MonochromeSpriteVertexOutput monochrome_sprite_vertex(uint vertex_id: SV_VertexI
     output.position = device_position;
     output.tile_position = tile_position;
     output.color = color;
+    output.page = sprite_page(sprite.tile);
     output.clip_distance = clip_distance;
     return output;
 }
 
 float4 monochrome_sprite_fragment(MonochromeSpriteFragmentInput input): SV_Target {
-    float sample = t_sprite.Sample(s_sprite, input.tile_position).r;
+    float sample = sample_sprite_page(input.page, input.tile_position).r;
     float alpha_corrected = apply_contrast_and_gamma_correction(sample, input.color.rgb, grayscale_enhanced_contrast, gamma_ratios);
     return float4(input.color.rgb, input.color.a * alpha_corrected);
 }
@@ -1156,7 +1191,7 @@ MonochromeSpriteVertexOutput subpixel_sprite_vertex(uint vertex_id: SV_VertexID,
 }
 
 SubpixelSpriteFragmentOutput subpixel_sprite_fragment(MonochromeSpriteFragmentInput input) {
-    float3 sample = t_sprite.Sample(s_sprite, input.tile_position).rgb;
+    float3 sample = sample_sprite_page(input.page, input.tile_position).rgb;
     float3 alpha_corrected = apply_contrast_and_gamma_correction3(sample, input.color.rgb, subpixel_enhanced_contrast, gamma_ratios);
 
     SubpixelSpriteFragmentOutput output;
@@ -1215,7 +1250,7 @@ PolychromeSpriteVertexOutput polychrome_sprite_vertex(uint vertex_id: SV_VertexI
 
 float4 polychrome_sprite_fragment(PolychromeSpriteFragmentInput input): SV_Target {
     PolychromeSprite sprite = poly_sprites[input.sprite_id];
-    float4 sample = t_sprite.Sample(s_sprite, input.tile_position);
+    float4 sample = sample_sprite_page(sprite_page(sprite.tile), input.tile_position);
     float distance = quad_sdf(input.position.xy, sprite.bounds, sprite.corner_radii);
 
     float4 color = sample;
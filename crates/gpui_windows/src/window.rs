@@ -103,6 +103,7 @@ impl WindowsWindowState {
         appearance: WindowAppearance,
         disable_direct_composition: bool,
         invalidate_devices: Arc<AtomicBool>,
+        background_executor: BackgroundExecutor,
     ) -> Result<Self> {
         let scale_factor = {
             let monitor_dpi = unsafe { GetDpiForWindow(hwnd) } as f32;
@@ -122,8 +123,13 @@ impl WindowsWindowState {
         };
         let border_offset = WindowBorderOffset::default();
         let restore_from_minimized = None;
-        let renderer = DirectXRenderer::new(hwnd, directx_devices, disable_direct_composition)
-            .context("Creating DirectX renderer")?;
+        let renderer = DirectXRenderer::new(
+            hwnd,
+            directx_devices,
+            disable_direct_composition,
+            background_executor,
+        )
+        .context("Creating DirectX renderer")?;
         let callbacks = Callbacks::default();
         let input_handler = None;
         let pending_surrogate = None;
@@ -239,6 +245,7 @@ impl WindowsWindowInner {
             context.appearance,
             context.disable_direct_composition,
             context.invalidate_devices.clone(),
+            context.background_executor.clone(),
         )?;
 
         Ok(Rc::new(Self {
@@ -372,6 +379,7 @@ struct WindowCreateContext {
     is_movable: bool,
     min_size: Option<Size<Pixels>>,
     executor: ForegroundExecutor,
+    background_executor: BackgroundExecutor,
     current_cursor: Option<HCURSOR>,
     drop_target_helper: IDropTargetHelper,
     validation_number: usize,
@@ -393,6 +401,7 @@ impl WindowsWindow {
         let WindowCreationInfo {
             icon,
             executor,
+            background_executor,
             current_cursor,
             drop_target_helper,
             validation_number,
@@ -452,6 +461,10 @@ impl WindowsWindow {
 
             (dwexstyle, dwstyle)
         };
+        // The presentation mode has to be settled before the window exists, since the
+        // redirection bitmap can't be restored once the window is created without one.
+        let disable_direct_composition =
+            disable_direct_composition || !supports_direct_composition(&directx_devices);
         if !disable_direct_composition {
             dwexstyle |= WS_EX_NOREDIRECTIONBITMAP;
         }
@@ -472,6 +485,7 @@ impl WindowsWindow {
             is_movable: params.is_movable,
             min_size: params.window_min_size,
             executor,
+            background_executor,
             current_cursor,
             drop_target_helper,
             validation_number,
@@ -921,6 +935,10 @@ impl PlatformWindow for WindowsWindow {
             .set(Some(callback));
     }
 
+    fn set_clear_color(&self, color: Rgba) {
+        self.state.renderer.borrow_mut().set_clear_color(color);
+    }
+
     fn draw(&self, scene: &Scene) {
         self.state
             .renderer
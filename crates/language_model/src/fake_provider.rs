@@ -5,13 +5,21 @@ use crate::{
     LanguageModelRequest, LanguageModelToolChoice,
 };
 use anyhow::anyhow;
-use futures::{FutureExt, channel::mpsc, future::BoxFuture, stream::BoxStream, stream::StreamExt};
+use futures::{
+    FutureExt,
+    channel::mpsc,
+    future::BoxFuture,
+    stream::{self, BoxStream, StreamExt},
+};
 use gpui::{AnyView, App, AsyncApp, Entity, Task, Window};
 use http_client::Result;
 use parking_lot::Mutex;
-use std::sync::{
-    Arc,
-    atomic::{AtomicBool, Ordering::SeqCst},
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering::SeqCst},
+    },
 };
 
 #[derive(Clone)]
@@ -122,9 +130,13 @@ pub struct FakeLanguageModel {
             >,
         )>,
     >,
+    /// Responses to replay, in order, for upcoming completion requests instead
+    /// of leaving their streams open for the test to drive.
+    scripted_completions: Mutex<VecDeque<Vec<LanguageModelCompletionEvent>>>,
     forbid_requests: AtomicBool,
     supports_thinking: AtomicBool,
     supports_streaming_tools: AtomicBool,
+    supports_images: AtomicBool,
 }
 
 impl Default for FakeLanguageModel {
@@ -135,9 +147,11 @@ impl Default for FakeLanguageModel {
             provider_id: LanguageModelProviderId::from("fake".to_string()),
             provider_name: LanguageModelProviderName::from("Fake".to_string()),
             current_completion_txs: Mutex::new(Vec::new()),
+            scripted_completions: Mutex::new(VecDeque::new()),
             forbid_requests: AtomicBool::new(false),
             supports_thinking: AtomicBool::new(false),
             supports_streaming_tools: AtomicBool::new(false),
+            supports_images: AtomicBool::new(false),
         }
     }
 }
@@ -174,6 +188,24 @@ impl FakeLanguageModel {
         self.supports_streaming_tools.store(supports, SeqCst);
     }
 
+    pub fn set_supports_images(&self, supports: bool) {
+        self.supports_images.store(supports, SeqCst);
+    }
+
+    /// Queues a response for the next completion request that doesn't already
+    /// have one. The response's stream yields `events` and then ends.
+    ///
+    /// This lets a test script a whole multi-request interaction (e.g. a tool
+    /// use followed by the model's reply to the tool result) up front.
+    pub fn push_scripted_completion(
+        &self,
+        events: impl IntoIterator<Item = LanguageModelCompletionEvent>,
+    ) {
+        self.scripted_completions
+            .lock()
+            .push_back(events.into_iter().collect());
+    }
+
     pub fn pending_completions(&self) -> Vec<LanguageModelRequest> {
         self.current_completion_txs
             .lock()
@@ -280,7 +312,7 @@ impl LanguageModel for FakeLanguageModel {
     }
 
     fn supports_images(&self) -> bool {
-        false
+        self.supports_images.load(SeqCst)
     }
 
     fn supports_thinking(&self) -> bool {
@@ -299,8 +331,17 @@ impl LanguageModel for FakeLanguageModel {
         1000000
     }
 
-    fn count_tokens(&self, _: LanguageModelRequest, _: &App) -> BoxFuture<'static, Result<u64>> {
-        futures::future::ready(Ok(0)).boxed()
+    fn count_tokens(
+        &self,
+        request: LanguageModelRequest,
+        _: &App,
+    ) -> BoxFuture<'static, Result<u64>> {
+        let bytes = request
+            .messages
+            .iter()
+            .map(|message| message.string_contents().len())
+            .sum::<usize>();
+        futures::future::ready(Ok(bytes.div_ceil(4) as u64)).boxed()
     }
 
     fn stream_completion(
@@ -321,6 +362,8 @@ impl LanguageModel for FakeLanguageModel {
                 )))
             }
             .boxed()
+        } else if let Some(events) = self.scripted_completions.lock().pop_front() {
+            async move { Ok(stream::iter(events.into_iter().map(Ok)).boxed()) }.boxed()
         } else {
             let (tx, rx) = mpsc::unbounded();
             self.current_completion_txs.lock().push((request, tx));
@@ -5,24 +5,25 @@ use client::{Client, TelemetrySettings, UserStore, zed_urls};
 use cloud_api_types::Plan;
 use collections::HashMap;
 use fs::Fs;
-use gpui::{Action, Animation, AnimationExt, App, Entity, IntoElement, pulsating_between};
+use gpui::{Action, Animation, AnimationExt, App, Entity, Global, IntoElement, pulsating_between};
 use project::agent_server_store::AllAgentServersSettings;
 use project::project_settings::ProjectSettings;
 use project::{AgentRegistryStore, RegistryAgent};
 use settings::{
-    BaseKeymap, CustomAgentServerSettings, Settings, SettingsStore, update_settings_file,
+    BaseKeymap, CustomAgentServerSettings, Settings, SettingsContent, SettingsStore,
+    update_settings_file,
 };
 use theme::{Appearance, SystemAppearance, ThemeRegistry};
 use theme_settings::{ThemeAppearanceMode, ThemeName, ThemeSelection, ThemeSettings};
 use ui::{
-    AgentSetupButton, Divider, StatefulInteractiveElement, SwitchField, TintColor,
-    ToggleButtonGroup, ToggleButtonGroupSize, ToggleButtonSimple, ToggleButtonWithIcon, Tooltip,
-    prelude::*,
+    AgentSetupButton, Divider, StatefulInteractiveElement, TintColor, ToggleButtonGroup,
+    ToggleButtonGroupSize, ToggleButtonSimple, ToggleButtonWithIcon, prelude::*,
 };
 use vim_mode_setting::VimModeSetting;
 
 use crate::{
     ImportCursorSettings, ImportVsCodeSettings, SettingsImportState,
+    settings_toggle_group::{SettingsToggle, SettingsToggleGroup},
     theme_preview::{ThemePreviewStyle, ThemePreviewTile},
 };
 
@@ -43,6 +44,103 @@ fn get_theme_family_themes(theme_name: &str) -> Option<(&'static str, &'static s
     None
 }
 
+/// Like [`theme_settings::set_mode`], except that switching a single theme to
+/// follow the system keeps its family: the light and dark variants become the
+/// pair that is switched between when the system appearance changes.
+fn set_theme_mode(settings: &mut SettingsContent, mode: ThemeAppearanceMode) {
+    let family = match &settings.theme.theme {
+        Some(settings::ThemeSelection::Static(theme)) if mode == ThemeAppearanceMode::System => {
+            get_theme_family_themes(&theme.0)
+        }
+        _ => None,
+    };
+
+    theme_settings::set_mode(settings, mode);
+
+    if let Some((light_theme, dark_theme)) = family {
+        settings.theme.theme = Some(settings::ThemeSelection::Dynamic {
+            mode,
+            light: ThemeName(light_theme.into()),
+            dark: ThemeName(dark_theme.into()),
+        });
+    }
+}
+
+/// Walkthrough controls that write to the user's settings file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum SettingsControl {
+    Theme,
+    BaseKeymap,
+    VimMode,
+    WorktreeAutoTrust,
+    Telemetry,
+}
+
+/// The most recent failed settings write for each walkthrough control, shown
+/// next to the control until it is used again.
+#[derive(Default)]
+struct SettingsWriteErrors(HashMap<SettingsControl, SharedString>);
+
+impl Global for SettingsWriteErrors {}
+
+impl SettingsWriteErrors {
+    fn get(control: SettingsControl, cx: &App) -> Option<SharedString> {
+        cx.try_global::<Self>()?.0.get(&control).cloned()
+    }
+}
+
+/// Writes to the settings file on behalf of `control`, recording any failure
+/// so it can be displayed next to that control.
+fn write_settings(
+    control: SettingsControl,
+    cx: &mut App,
+    update: impl 'static + Send + FnOnce(&mut SettingsContent, &App),
+) {
+    if cx
+        .try_global::<SettingsWriteErrors>()
+        .is_some_and(|errors| errors.0.contains_key(&control))
+    {
+        cx.update_default_global(|errors: &mut SettingsWriteErrors, _| {
+            errors.0.remove(&control);
+        });
+        cx.refresh_windows();
+    }
+
+    let fs = <dyn Fs>::global(cx);
+    let write = SettingsStore::global(cx).update_settings_file_with_completion(fs, update);
+    cx.spawn(async move |cx| {
+        let Ok(Err(error)) = write.await else {
+            return;
+        };
+        zlog::error!("Failed to update settings for {control:?}: {error:#}");
+        cx.update(|cx| {
+            cx.update_default_global(|errors: &mut SettingsWriteErrors, _| {
+                errors.0.insert(
+                    control,
+                    "Couldn't save this setting. Check your settings file for errors.".into(),
+                );
+            });
+            cx.refresh_windows();
+        });
+    })
+    .detach();
+}
+
+fn render_settings_write_error(control: SettingsControl, cx: &App) -> Option<impl IntoElement> {
+    let error = SettingsWriteErrors::get(control, cx)?;
+
+    Some(
+        h_flex()
+            .gap_1()
+            .child(
+                Icon::new(IconName::Warning)
+                    .size(IconSize::Small)
+                    .color(Color::Error),
+            )
+            .child(Label::new(error).size(LabelSize::Small).color(Color::Error)),
+    )
+}
+
 fn render_theme_section(tab_index: &mut isize, cx: &mut App) -> impl IntoElement {
     let theme_selection = ThemeSettings::get_global(cx).theme.clone();
     let system_appearance = theme::SystemAppearance::global(cx);
@@ -97,7 +195,8 @@ fn render_theme_section(tab_index: &mut isize, cx: &mut App) -> impl IntoElement
                 .gap_2()
                 .justify_between()
                 .children(render_theme_previews(tab_index, &theme_selection, cx)),
-        );
+        )
+        .children(render_settings_write_error(SettingsControl::Theme, cx));
 
     fn render_theme_previews(
         tab_index: &mut isize,
@@ -107,7 +206,7 @@ fn render_theme_section(tab_index: &mut isize, cx: &mut App) -> impl IntoElement
         let system_appearance = SystemAppearance::global(cx);
         let theme_registry = ThemeRegistry::global(cx);
 
-        let theme_seed = 0xBEEF as f32;
+        let theme_seed = ThemePreviewTile::SHARED_SEED;
         let theme_mode = theme_selection
             .mode()
             .unwrap_or_else(|| match *system_appearance {
@@ -201,9 +300,8 @@ fn render_theme_section(tab_index: &mut isize, cx: &mut App) -> impl IntoElement
     }
 
     fn write_mode_change(mode: ThemeAppearanceMode, cx: &mut App) {
-        let fs = <dyn Fs>::global(cx);
-        update_settings_file(fs, cx, move |settings, _cx| {
-            theme_settings::set_mode(settings, mode);
+        write_settings(SettingsControl::Theme, cx, move |settings, _cx| {
+            set_theme_mode(settings, mode);
         });
     }
 
@@ -212,121 +310,83 @@ fn render_theme_section(tab_index: &mut isize, cx: &mut App) -> impl IntoElement
         theme_mode: ThemeAppearanceMode,
         cx: &mut App,
     ) {
-        let fs = <dyn Fs>::global(cx);
         let theme = theme.into();
-        update_settings_file(fs, cx, move |settings, cx| match theme_mode {
-            ThemeAppearanceMode::System => {
-                let (light_theme, dark_theme) =
-                    get_theme_family_themes(&theme).unwrap_or((theme.as_ref(), theme.as_ref()));
-
-                settings.theme.theme = Some(settings::ThemeSelection::Dynamic {
-                    mode: ThemeAppearanceMode::System,
-                    light: ThemeName(light_theme.into()),
-                    dark: ThemeName(dark_theme.into()),
-                });
-            }
-            ThemeAppearanceMode::Light => theme_settings::set_theme(
-                settings,
-                theme,
-                Appearance::Light,
-                *SystemAppearance::global(cx),
-            ),
-            ThemeAppearanceMode::Dark => theme_settings::set_theme(
-                settings,
-                theme,
-                Appearance::Dark,
-                *SystemAppearance::global(cx),
-            ),
-        });
+        write_settings(
+            SettingsControl::Theme,
+            cx,
+            move |settings, cx| match theme_mode {
+                ThemeAppearanceMode::System => {
+                    let (light_theme, dark_theme) =
+                        get_theme_family_themes(&theme).unwrap_or((theme.as_ref(), theme.as_ref()));
+
+                    settings.theme.theme = Some(settings::ThemeSelection::Dynamic {
+                        mode: ThemeAppearanceMode::System,
+                        light: ThemeName(light_theme.into()),
+                        dark: ThemeName(dark_theme.into()),
+                    });
+                }
+                ThemeAppearanceMode::Light => theme_settings::set_theme(
+                    settings,
+                    theme,
+                    Appearance::Light,
+                    *SystemAppearance::global(cx),
+                ),
+                ThemeAppearanceMode::Dark => theme_settings::set_theme(
+                    settings,
+                    theme,
+                    Appearance::Dark,
+                    *SystemAppearance::global(cx),
+                ),
+            },
+        );
     }
 }
 
 fn render_telemetry_section(tab_index: &mut isize, cx: &App) -> impl IntoElement {
-    let fs = <dyn Fs>::global(cx);
+    let telemetry_settings = TelemetrySettings::get_global(cx);
+
+    let toggles = SettingsToggleGroup::new([
+        SettingsToggle::new(
+            "onboarding-telemetry-metrics",
+            telemetry_settings.metrics,
+            |enabled, _, cx| {
+                write_settings(SettingsControl::Telemetry, cx, move |setting, _| {
+                    setting.telemetry.get_or_insert_default().metrics = Some(enabled);
+                });
 
-    v_flex()
-        .gap_4()
-        .child(
-            SwitchField::new(
-                "onboarding-telemetry-metrics",
-                None::<&str>,
-                Some("Help improve Zed by sending anonymous usage data".into()),
-                if TelemetrySettings::get_global(cx).metrics {
-                    ui::ToggleState::Selected
-                } else {
-                    ui::ToggleState::Unselected
-                },
-                {
-                    let fs = fs.clone();
-                    move |selection, _, cx| {
-                        let enabled = match selection {
-                            ToggleState::Selected => true,
-                            ToggleState::Unselected => false,
-                            ToggleState::Indeterminate => {
-                                return;
-                            }
-                        };
-
-                        update_settings_file(fs.clone(), cx, move |setting, _| {
-                            setting.telemetry.get_or_insert_default().metrics = Some(enabled);
-                        });
-
-                        // This telemetry event shouldn't fire when it's off. If it does we'll be alerted
-                        // and can fix it in a timely manner to respect a user's choice.
-                        telemetry::event!(
-                            "Welcome Page Telemetry Metrics Toggled",
-                            options = if enabled { "on" } else { "off" }
-                        );
-                    }
-                },
-            )
-            .tab_index({
-                *tab_index += 1;
-                *tab_index
-            }),
+                // This telemetry event shouldn't fire when it's off. If it does we'll be alerted
+                // and can fix it in a timely manner to respect a user's choice.
+                telemetry::event!(
+                    "Welcome Page Telemetry Metrics Toggled",
+                    options = if enabled { "on" } else { "off" }
+                );
+            },
         )
-        .child(
-            SwitchField::new(
-                "onboarding-telemetry-crash-reports",
-                None::<&str>,
-                Some(
-                    "Help fix Zed by sending crash reports so we can fix critical issues fast"
-                        .into(),
-                ),
-                if TelemetrySettings::get_global(cx).diagnostics {
-                    ui::ToggleState::Selected
-                } else {
-                    ui::ToggleState::Unselected
-                },
-                {
-                    let fs = fs.clone();
-                    move |selection, _, cx| {
-                        let enabled = match selection {
-                            ToggleState::Selected => true,
-                            ToggleState::Unselected => false,
-                            ToggleState::Indeterminate => {
-                                return;
-                            }
-                        };
-
-                        update_settings_file(fs.clone(), cx, move |setting, _| {
-                            setting.telemetry.get_or_insert_default().diagnostics = Some(enabled);
-                        });
-
-                        // This telemetry event shouldn't fire when it's off. If it does we'll be alerted
-                        // and can fix it in a timely manner to respect a user's choice.
-                        telemetry::event!(
-                            "Welcome Page Telemetry Diagnostics Toggled",
-                            options = if enabled { "on" } else { "off" }
-                        );
-                    }
-                },
-            )
-            .tab_index({
-                *tab_index += 1;
-                *tab_index
-            }),
+        .description("Help improve Zed by sending anonymous usage data"),
+        SettingsToggle::new(
+            "onboarding-telemetry-crash-reports",
+            telemetry_settings.diagnostics,
+            |enabled, _, cx| {
+                write_settings(SettingsControl::Telemetry, cx, move |setting, _| {
+                    setting.telemetry.get_or_insert_default().diagnostics = Some(enabled);
+                });
+
+                // This telemetry event shouldn't fire when it's off. If it does we'll be alerted
+                // and can fix it in a timely manner to respect a user's choice.
+                telemetry::event!(
+                    "Welcome Page Telemetry Diagnostics Toggled",
+                    options = if enabled { "on" } else { "off" }
+                );
+            },
         )
+        .description("Help fix Zed by sending crash reports so we can fix critical issues fast"),
+    ])
+    .tab_index(tab_index);
+
+    v_flex()
+        .gap_2()
+        .child(toggles)
+        .children(render_settings_write_error(SettingsControl::Telemetry, cx))
 }
 
 fn render_base_keymap_section(tab_index: &mut isize, cx: &mut App) -> impl IntoElement {
@@ -340,45 +400,55 @@ fn render_base_keymap_section(tab_index: &mut isize, cx: &mut App) -> impl IntoE
         BaseKeymap::TextMate | BaseKeymap::None => None,
     };
 
-    return v_flex().gap_2().child(Label::new("Base Keymap")).child(
-        ToggleButtonGroup::two_rows(
-            "base_keymap_selection",
-            [
-                ToggleButtonWithIcon::new("VS Code", IconName::EditorVsCode, |_, _, cx| {
-                    write_keymap_base(BaseKeymap::VSCode, cx);
-                }),
-                ToggleButtonWithIcon::new("JetBrains", IconName::EditorJetBrains, |_, _, cx| {
-                    write_keymap_base(BaseKeymap::JetBrains, cx);
-                }),
-                ToggleButtonWithIcon::new("Sublime Text", IconName::EditorSublime, |_, _, cx| {
-                    write_keymap_base(BaseKeymap::SublimeText, cx);
-                }),
-            ],
-            [
-                ToggleButtonWithIcon::new("Atom", IconName::EditorAtom, |_, _, cx| {
-                    write_keymap_base(BaseKeymap::Atom, cx);
-                }),
-                ToggleButtonWithIcon::new("Emacs", IconName::EditorEmacs, |_, _, cx| {
-                    write_keymap_base(BaseKeymap::Emacs, cx);
-                }),
-                ToggleButtonWithIcon::new("Cursor", IconName::EditorCursor, |_, _, cx| {
-                    write_keymap_base(BaseKeymap::Cursor, cx);
-                }),
-            ],
+    return v_flex()
+        .gap_2()
+        .child(Label::new("Base Keymap"))
+        .child(
+            ToggleButtonGroup::two_rows(
+                "base_keymap_selection",
+                [
+                    ToggleButtonWithIcon::new("VS Code", IconName::EditorVsCode, |_, _, cx| {
+                        write_keymap_base(BaseKeymap::VSCode, cx);
+                    }),
+                    ToggleButtonWithIcon::new(
+                        "JetBrains",
+                        IconName::EditorJetBrains,
+                        |_, _, cx| {
+                            write_keymap_base(BaseKeymap::JetBrains, cx);
+                        },
+                    ),
+                    ToggleButtonWithIcon::new(
+                        "Sublime Text",
+                        IconName::EditorSublime,
+                        |_, _, cx| {
+                            write_keymap_base(BaseKeymap::SublimeText, cx);
+                        },
+                    ),
+                ],
+                [
+                    ToggleButtonWithIcon::new("Atom", IconName::EditorAtom, |_, _, cx| {
+                        write_keymap_base(BaseKeymap::Atom, cx);
+                    }),
+                    ToggleButtonWithIcon::new("Emacs", IconName::EditorEmacs, |_, _, cx| {
+                        write_keymap_base(BaseKeymap::Emacs, cx);
+                    }),
+                    ToggleButtonWithIcon::new("Cursor", IconName::EditorCursor, |_, _, cx| {
+                        write_keymap_base(BaseKeymap::Cursor, cx);
+                    }),
+                ],
+            )
+            .when_some(base_keymap, |this, base_keymap| {
+                this.selected_index(base_keymap)
+            })
+            .full_width()
+            .tab_index(tab_index)
+            .size(ui::ToggleButtonGroupSize::Medium)
+            .style(ui::ToggleButtonGroupStyle::Outlined),
         )
-        .when_some(base_keymap, |this, base_keymap| {
-            this.selected_index(base_keymap)
-        })
-        .full_width()
-        .tab_index(tab_index)
-        .size(ui::ToggleButtonGroupSize::Medium)
-        .style(ui::ToggleButtonGroupStyle::Outlined),
-    );
-
-    fn write_keymap_base(keymap_base: BaseKeymap, cx: &App) {
-        let fs = <dyn Fs>::global(cx);
+        .children(render_settings_write_error(SettingsControl::BaseKeymap, cx));
 
-        update_settings_file(fs, cx, move |setting, _| {
+    fn write_keymap_base(keymap_base: BaseKeymap, cx: &mut App) {
+        write_settings(SettingsControl::BaseKeymap, cx, move |setting, _| {
             setting.base_keymap = Some(keymap_base.into());
         });
 
@@ -386,28 +456,15 @@ fn render_base_keymap_section(tab_index: &mut isize, cx: &mut App) -> impl IntoE
     }
 }
 
-fn render_vim_mode_switch(tab_index: &mut isize, cx: &mut App) -> impl IntoElement {
-    let toggle_state = if VimModeSetting::get_global(cx).0 {
-        ui::ToggleState::Selected
-    } else {
-        ui::ToggleState::Unselected
-    };
-    SwitchField::new(
-        "onboarding-vim-mode",
-        Some("Vim Mode"),
-        Some("Coming from Neovim? Use our first-class implementation of Vim Mode".into()),
-        toggle_state,
-        {
-            let fs = <dyn Fs>::global(cx);
-            move |&selection, _, cx| {
-                let vim_mode = match selection {
-                    ToggleState::Selected => true,
-                    ToggleState::Unselected => false,
-                    ToggleState::Indeterminate => {
-                        return;
-                    }
-                };
-                update_settings_file(fs.clone(), cx, move |setting, _| {
+fn render_editing_settings_section(tab_index: &mut isize, cx: &mut App) -> impl IntoElement {
+    let tooltip_description = "Zed can only allow services like language servers, project settings, and MCP servers to run after you mark a new project as trusted.";
+
+    let toggles = SettingsToggleGroup::new([
+        SettingsToggle::new(
+            "onboarding-vim-mode",
+            VimModeSetting::get_global(cx).0,
+            |vim_mode, _, cx| {
+                write_settings(SettingsControl::VimMode, cx, move |setting, _| {
                     setting.vim_mode = Some(vim_mode);
                 });
 
@@ -415,40 +472,15 @@ fn render_vim_mode_switch(tab_index: &mut isize, cx: &mut App) -> impl IntoEleme
                     "Welcome Vim Mode Toggled",
                     options = if vim_mode { "on" } else { "off" },
                 );
-            }
-        },
-    )
-    .tab_index({
-        *tab_index += 1;
-        *tab_index - 1
-    })
-}
-
-fn render_worktree_auto_trust_switch(tab_index: &mut isize, cx: &mut App) -> impl IntoElement {
-    let toggle_state = if ProjectSettings::get_global(cx).session.trust_all_worktrees {
-        ui::ToggleState::Selected
-    } else {
-        ui::ToggleState::Unselected
-    };
-
-    let tooltip_description = "Zed can only allow services like language servers, project settings, and MCP servers to run after you mark a new project as trusted.";
-
-    SwitchField::new(
-        "onboarding-auto-trust-worktrees",
-        Some("Trust All Projects By Default"),
-        Some("Automatically mark all new projects as trusted to unlock all Zed's features".into()),
-        toggle_state,
-        {
-            let fs = <dyn Fs>::global(cx);
-            move |&selection, _, cx| {
-                let trust = match selection {
-                    ToggleState::Selected => true,
-                    ToggleState::Unselected => false,
-                    ToggleState::Indeterminate => {
-                        return;
-                    }
-                };
-                update_settings_file(fs.clone(), cx, move |setting, _| {
+            },
+        )
+        .label("Vim Mode")
+        .description("Coming from Neovim? Use our first-class implementation of Vim Mode"),
+        SettingsToggle::new(
+            "onboarding-auto-trust-worktrees",
+            ProjectSettings::get_global(cx).session.trust_all_worktrees,
+            |trust, _, cx| {
+                write_settings(SettingsControl::WorktreeAutoTrust, cx, move |setting, _| {
                     setting.session.get_or_insert_default().trust_all_worktrees = Some(trust);
                 });
 
@@ -456,14 +488,22 @@ fn render_worktree_auto_trust_switch(tab_index: &mut isize, cx: &mut App) -> imp
                     "Welcome Page Worktree Auto Trust Toggled",
                     options = if trust { "on" } else { "off" }
                 );
-            }
-        },
-    )
-    .tab_index({
-        *tab_index += 1;
-        *tab_index - 1
-    })
-    .tooltip(Tooltip::text(tooltip_description))
+            },
+        )
+        .label("Trust All Projects By Default")
+        .description("Automatically mark all new projects as trusted to unlock all Zed's features")
+        .tooltip(tooltip_description),
+    ])
+    .tab_index(tab_index);
+
+    v_flex()
+        .gap_2()
+        .child(toggles)
+        .children(render_settings_write_error(SettingsControl::VimMode, cx))
+        .children(render_settings_write_error(
+            SettingsControl::WorktreeAutoTrust,
+            cx,
+        ))
 }
 
 fn render_setting_import_button(
@@ -695,18 +735,95 @@ fn render_ai_section(user_store: &Entity<UserStore>, cx: &mut App) -> impl IntoE
         .child(grid)
 }
 
-pub(crate) fn render_basics_page(user_store: &Entity<UserStore>, cx: &mut App) -> impl IntoElement {
+/// The number of sections returned by [`render_basics_sections`].
+pub(crate) const BASICS_SECTION_COUNT: usize = 6;
+
+/// Renders each section of the basics page as its own element, so the
+/// onboarding page can scroll to a section when its step is selected.
+pub(crate) fn render_basics_sections(
+    user_store: &Entity<UserStore>,
+    cx: &mut App,
+) -> [AnyElement; BASICS_SECTION_COUNT] {
     let mut tab_index = 0;
 
-    v_flex()
-        .id("basics-page")
-        .gap_6()
-        .child(render_theme_section(&mut tab_index, cx))
-        .child(render_base_keymap_section(&mut tab_index, cx))
-        .child(render_ai_section(user_store, cx))
-        .child(render_import_settings_section(&mut tab_index, cx))
-        .child(render_vim_mode_switch(&mut tab_index, cx))
-        .child(render_worktree_auto_trust_switch(&mut tab_index, cx))
-        .child(Divider::horizontal().color(ui::DividerColor::BorderVariant))
-        .child(render_telemetry_section(&mut tab_index, cx))
+    [
+        render_theme_section(&mut tab_index, cx).into_any_element(),
+        render_base_keymap_section(&mut tab_index, cx).into_any_element(),
+        render_ai_section(user_store, cx).into_any_element(),
+        render_import_settings_section(&mut tab_index, cx).into_any_element(),
+        render_editing_settings_section(&mut tab_index, cx).into_any_element(),
+        v_flex()
+            .gap_6()
+            .child(Divider::horizontal().color(ui::DividerColor::BorderVariant))
+            .child(render_telemetry_section(&mut tab_index, cx))
+            .into_any_element(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+
+    #[gpui::test]
+    async fn test_failed_settings_write_is_surfaced(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        fs.create_dir(paths::settings_file().parent().unwrap())
+            .await
+            .unwrap();
+        fs.insert_file(paths::settings_file(), b"not json".to_vec())
+            .await;
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            <dyn Fs>::set_global(fs.clone(), cx);
+        });
+
+        cx.update(|cx| {
+            write_settings(SettingsControl::VimMode, cx, |settings, _| {
+                settings.vim_mode = Some(true);
+            });
+        });
+        cx.run_until_parked();
+        cx.update(|cx| {
+            assert!(SettingsWriteErrors::get(SettingsControl::VimMode, cx).is_some());
+            assert!(SettingsWriteErrors::get(SettingsControl::Theme, cx).is_none());
+        });
+
+        fs.insert_file(paths::settings_file(), b"{}".to_vec()).await;
+        cx.update(|cx| {
+            write_settings(SettingsControl::VimMode, cx, |settings, _| {
+                settings.vim_mode = Some(true);
+            });
+        });
+        cx.run_until_parked();
+        cx.update(|cx| {
+            assert!(SettingsWriteErrors::get(SettingsControl::VimMode, cx).is_none());
+        });
+    }
+
+    #[gpui::test]
+    fn test_system_mode_follows_appearance_changes(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme_settings::init(theme::LoadThemes::All(Box::new(assets::Assets)), cx);
+
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.theme.theme = Some(settings::ThemeSelection::Static(ThemeName(
+                        "Ayu Dark".into(),
+                    )));
+                    set_theme_mode(settings, ThemeAppearanceMode::System);
+                });
+            });
+
+            theme_settings::set_system_appearance(Appearance::Light, cx);
+            assert_eq!(cx.theme().name, "Ayu Light");
+
+            theme_settings::set_system_appearance(Appearance::Dark, cx);
+            assert_eq!(cx.theme().name, "Ayu Dark");
+        });
+    }
 }
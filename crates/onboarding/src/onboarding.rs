@@ -3,9 +3,9 @@ use client::{Client, UserStore, zed_urls};
 use db::kvp::KeyValueStore;
 use fs::Fs;
 use gpui::{
-    Action, AnyElement, App, AppContext, AsyncWindowContext, Context, Entity, EventEmitter,
-    FocusHandle, Focusable, Global, IntoElement, KeyContext, Render, ScrollHandle, SharedString,
-    Subscription, Task, WeakEntity, Window, actions,
+    Action, AnyElement, App, AppContext, AsyncWindowContext, Context, DismissEvent, Entity,
+    EventEmitter, FocusHandle, Focusable, Global, IntoElement, KeyContext, Render, ScrollHandle,
+    SharedString, Subscription, Task, WeakEntity, Window, actions,
 };
 use notifications::status_toast::{StatusToast, ToastIcon};
 use schemars::JsonSchema;
@@ -13,15 +13,14 @@ use serde::Deserialize;
 use settings::{SettingsStore, VsCodeSettingsSource};
 use std::sync::Arc;
 use ui::{
-    Divider, KeyBinding, ParentElement as _, StatefulInteractiveElement, Vector, VectorName,
-    WithScrollbar as _, prelude::*, rems_from_px,
+    Divider, KeyBinding, ParentElement as _, StatefulInteractiveElement, StepProgress, Vector,
+    VectorName, WithScrollbar as _, prelude::*, rems_from_px,
 };
 
 pub use workspace::welcome::ShowWelcome;
 use workspace::welcome::WelcomePage;
 use workspace::{
-    AppState, Workspace, WorkspaceId,
-    dock::DockPosition,
+    AppState, ModalView, Workspace, WorkspaceId,
     item::{Item, ItemEvent},
     notifications::NotifyResultExt as _,
     open_new, register_serializable_item, with_active_or_new_workspace,
@@ -31,6 +30,7 @@ use zed_actions::OpenOnboarding;
 mod base_keymap_picker;
 mod basics_page;
 pub mod multibuffer_hint;
+mod settings_toggle_group;
 mod theme_preview;
 
 /// Imports settings from Visual Studio Code.
@@ -88,7 +88,8 @@ pub fn init(cx: &mut App) {
                     if let Some(existing) = existing {
                         workspace.activate_item(&existing, true, true, window, cx);
                     } else {
-                        let settings_page = Onboarding::new(workspace, cx);
+                        let settings_page =
+                            Onboarding::new(workspace, OnboardingPresentation::Tab, cx);
                         workspace.add_item_to_active_pane(
                             Box::new(settings_page),
                             None,
@@ -179,66 +180,109 @@ pub fn init(cx: &mut App) {
     register_serializable_item::<WelcomePage>(cx);
 }
 
-pub fn show_onboarding_view(app_state: Arc<AppState>, cx: &mut App) -> Task<anyhow::Result<()>> {
-    telemetry::event!("Onboarding Page Opened");
+/// Opens a new workspace with onboarding shown as a modal on top of it, leaving
+/// the workspace itself ready to use once the modal is dismissed.
+pub fn show_onboarding_modal(app_state: Arc<AppState>, cx: &mut App) -> Task<anyhow::Result<()>> {
+    telemetry::event!("Onboarding Modal Opened");
     open_new(
         Default::default(),
         app_state,
         cx,
         |workspace, window, cx| {
-            {
-                workspace.toggle_dock(DockPosition::Left, window, cx);
-                let onboarding_page = Onboarding::new(workspace, cx);
-                workspace.add_item_to_center(Box::new(onboarding_page.clone()), window, cx);
-
-                window.focus(&onboarding_page.focus_handle(cx), cx);
-
-                cx.notify();
-            };
-            let kvp = KeyValueStore::global(cx);
-            db::write_and_log(cx, move || async move {
-                kvp.write_kvp(FIRST_OPEN.to_string(), "false".to_string())
-                    .await
+            let weak_workspace = workspace.weak_handle();
+            let user_store = workspace.user_store().clone();
+            workspace.toggle_modal(window, cx, |_, cx| {
+                Onboarding::build(
+                    weak_workspace,
+                    user_store,
+                    OnboardingPresentation::Modal,
+                    cx,
+                )
             });
+            mark_first_open_complete(cx);
         },
     )
 }
 
+fn mark_first_open_complete(cx: &mut App) {
+    let kvp = KeyValueStore::global(cx);
+    db::write_and_log(cx, move || async move {
+        kvp.write_kvp(FIRST_OPEN.to_string(), "false".to_string())
+            .await
+    });
+}
+
+/// Where the onboarding flow is displayed. Both presentations render the same
+/// content and differ only in the surrounding container and how they close.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OnboardingPresentation {
+    /// A full-size item in the center pane, replaced by the welcome page when finished.
+    Tab,
+    /// A centered modal over the workspace, dismissed when finished.
+    Modal,
+}
+
 struct Onboarding {
     workspace: WeakEntity<Workspace>,
     focus_handle: FocusHandle,
     user_store: Entity<UserStore>,
     scroll_handle: ScrollHandle,
+    presentation: OnboardingPresentation,
+    current_section: usize,
     _settings_subscription: Subscription,
 }
 
 impl Onboarding {
-    fn new(workspace: &Workspace, cx: &mut App) -> Entity<Self> {
-        let font_family_cache = theme::FontFamilyCache::global(cx);
+    /// The index of the first page section among the children of the scrolled
+    /// content, which starts with the header and a divider.
+    const FIRST_SECTION_INDEX: usize = 2;
 
-        cx.new(|cx| {
-            cx.spawn(async move |this, cx| {
-                font_family_cache.prefetch(cx).await;
-                this.update(cx, |_, cx| {
-                    cx.notify();
-                })
-            })
-            .detach();
+    fn new(
+        workspace: &Workspace,
+        presentation: OnboardingPresentation,
+        cx: &mut App,
+    ) -> Entity<Self> {
+        let weak_workspace = workspace.weak_handle();
+        let user_store = workspace.user_store().clone();
+        cx.new(|cx| Self::build(weak_workspace, user_store, presentation, cx))
+    }
 
-            Self {
-                workspace: workspace.weak_handle(),
-                focus_handle: cx.focus_handle(),
-                scroll_handle: ScrollHandle::new(),
-                user_store: workspace.user_store().clone(),
-                _settings_subscription: cx
-                    .observe_global::<SettingsStore>(move |_, cx| cx.notify()),
-            }
+    fn build(
+        workspace: WeakEntity<Workspace>,
+        user_store: Entity<UserStore>,
+        presentation: OnboardingPresentation,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let font_family_cache = theme::FontFamilyCache::global(cx);
+        cx.spawn(async move |this, cx| {
+            font_family_cache.prefetch(cx).await;
+            this.update(cx, |_, cx| {
+                cx.notify();
+            })
         })
+        .detach();
+
+        Self {
+            workspace,
+            focus_handle: cx.focus_handle(),
+            scroll_handle: ScrollHandle::new(),
+            user_store,
+            presentation,
+            current_section: 0,
+            _settings_subscription: cx.observe_global::<SettingsStore>(move |_, cx| cx.notify()),
+        }
     }
 
-    fn on_finish(_: &Finish, _: &mut Window, cx: &mut App) {
+    fn on_finish(&mut self, _: &Finish, _: &mut Window, cx: &mut Context<Self>) {
         telemetry::event!("Finish Setup");
-        go_to_welcome_page(cx);
+        match self.presentation {
+            OnboardingPresentation::Tab => go_to_welcome_page(cx),
+            OnboardingPresentation::Modal => cx.emit(DismissEvent),
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, _: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
     }
 
     fn handle_sign_in(&mut self, _: &SignIn, window: &mut Window, cx: &mut Context<Self>) {
@@ -259,14 +303,91 @@ impl Onboarding {
         cx.open_url(&zed_urls::account_url(cx))
     }
 
-    fn render_page(&mut self, cx: &mut Context<Self>) -> AnyElement {
-        crate::basics_page::render_basics_page(&self.user_store, cx).into_any_element()
+    fn select_section(&mut self, section: &usize, _: &mut Window, cx: &mut Context<Self>) {
+        let section = (*section).min(basics_page::BASICS_SECTION_COUNT - 1);
+        self.current_section = section;
+        self.scroll_handle
+            .scroll_to_top_of_item(Self::FIRST_SECTION_INDEX + section);
+        cx.notify();
+    }
+
+    fn render_page(&mut self, cx: &mut Context<Self>) -> impl IntoIterator<Item = AnyElement> {
+        basics_page::render_basics_sections(&self.user_store, cx)
+    }
+
+    fn render_content(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .id("page-content")
+            .m_auto()
+            .p_12()
+            .size_full()
+            .max_w_full()
+            .min_w_0()
+            .gap_6()
+            .overflow_y_scroll()
+            .child(
+                h_flex()
+                    .w_full()
+                    .gap_4()
+                    .justify_between()
+                    .child(
+                        h_flex()
+                            .gap_4()
+                            .child(Vector::square(VectorName::ZedLogo, rems(2.5)))
+                            .child(
+                                v_flex()
+                                    .child(
+                                        Headline::new("Welcome to Zed").size(HeadlineSize::Small),
+                                    )
+                                    .child(
+                                        Label::new("The editor for what's next")
+                                            .color(Color::Muted)
+                                            .size(LabelSize::Small)
+                                            .italic(),
+                                    ),
+                            ),
+                    )
+                    .child(
+                        StepProgress::new(
+                            "onboarding-progress",
+                            basics_page::BASICS_SECTION_COUNT,
+                            self.current_section,
+                        )
+                        .completed(0..self.current_section)
+                        .on_step_click(cx.listener(Self::select_section)),
+                    )
+                    .child({
+                        Button::new("finish_setup", "Finish Setup")
+                            .style(ButtonStyle::Filled)
+                            .size(ButtonSize::Medium)
+                            .width(rems_from_px(200.))
+                            .key_binding(KeyBinding::for_action_in(&Finish, &self.focus_handle, cx))
+                            .on_click(|_, window, cx| {
+                                window.dispatch_action(Finish.boxed_clone(), cx);
+                            })
+                    }),
+            )
+            .child(Divider::horizontal().color(ui::DividerColor::BorderVariant))
+            .children(self.render_page(cx))
+            .track_scroll(&self.scroll_handle)
     }
 }
 
 impl Render for Onboarding {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        div()
+        let container = match self.presentation {
+            OnboardingPresentation::Tab => {
+                div().size_full().bg(cx.theme().colors().editor_background)
+            }
+            OnboardingPresentation::Modal => div()
+                .elevation_3(cx)
+                .w(rems(48.))
+                .h(window.viewport_size().height * 0.8)
+                .overflow_hidden()
+                .on_action(cx.listener(Self::cancel)),
+        };
+
+        container
             .image_cache(gpui::retain_all("onboarding-page"))
             .key_context({
                 let mut ctx = KeyContext::new_with_defaults();
@@ -275,9 +396,7 @@ impl Render for Onboarding {
                 ctx
             })
             .track_focus(&self.focus_handle)
-            .size_full()
-            .bg(cx.theme().colors().editor_background)
-            .on_action(Self::on_finish)
+            .on_action(cx.listener(Self::on_finish))
             .on_action(cx.listener(Self::handle_sign_in))
             .on_action(Self::handle_open_account)
             .on_action(cx.listener(|_, _: &menu::SelectNext, window, cx| {
@@ -293,58 +412,7 @@ impl Render for Onboarding {
                     .max_w(Rems(48.0))
                     .size_full()
                     .mx_auto()
-                    .child(
-                        v_flex()
-                            .id("page-content")
-                            .m_auto()
-                            .p_12()
-                            .size_full()
-                            .max_w_full()
-                            .min_w_0()
-                            .gap_6()
-                            .overflow_y_scroll()
-                            .child(
-                                h_flex()
-                                    .w_full()
-                                    .gap_4()
-                                    .justify_between()
-                                    .child(
-                                        h_flex()
-                                            .gap_4()
-                                            .child(Vector::square(VectorName::ZedLogo, rems(2.5)))
-                                            .child(
-                                                v_flex()
-                                                    .child(
-                                                        Headline::new("Welcome to Zed")
-                                                            .size(HeadlineSize::Small),
-                                                    )
-                                                    .child(
-                                                        Label::new("The editor for what's next")
-                                                            .color(Color::Muted)
-                                                            .size(LabelSize::Small)
-                                                            .italic(),
-                                                    ),
-                                            ),
-                                    )
-                                    .child({
-                                        Button::new("finish_setup", "Finish Setup")
-                                            .style(ButtonStyle::Filled)
-                                            .size(ButtonSize::Medium)
-                                            .width(rems_from_px(200.))
-                                            .key_binding(KeyBinding::for_action_in(
-                                                &Finish,
-                                                &self.focus_handle,
-                                                cx,
-                                            ))
-                                            .on_click(|_, window, cx| {
-                                                window.dispatch_action(Finish.boxed_clone(), cx);
-                                            })
-                                    }),
-                            )
-                            .child(Divider::horizontal().color(ui::DividerColor::BorderVariant))
-                            .child(self.render_page(cx))
-                            .track_scroll(&self.scroll_handle),
-                    )
+                    .child(self.render_content(cx))
                     .vertical_scrollbar_for(&self.scroll_handle, window, cx),
             )
     }
@@ -352,6 +420,14 @@ impl Render for Onboarding {
 
 impl EventEmitter<ItemEvent> for Onboarding {}
 
+impl EventEmitter<DismissEvent> for Onboarding {}
+
+impl ModalView for Onboarding {
+    fn fade_out_background(&self) -> bool {
+        true
+    }
+}
+
 impl Focusable for Onboarding {
     fn focus_handle(&self, _: &App) -> gpui::FocusHandle {
         self.focus_handle.clone()
@@ -383,12 +459,13 @@ impl Item for Onboarding {
         _: &mut Window,
         cx: &mut Context<Self>,
     ) -> Task<Option<Entity<Self>>> {
-        Task::ready(Some(cx.new(|cx| Onboarding {
-            workspace: self.workspace.clone(),
-            user_store: self.user_store.clone(),
-            scroll_handle: ScrollHandle::new(),
-            focus_handle: cx.focus_handle(),
-            _settings_subscription: cx.observe_global::<SettingsStore>(move |_, cx| cx.notify()),
+        Task::ready(Some(cx.new(|cx| {
+            Onboarding::build(
+                self.workspace.clone(),
+                self.user_store.clone(),
+                self.presentation,
+                cx,
+            )
         })))
     }
 
@@ -575,7 +652,9 @@ impl workspace::SerializableItem for Onboarding {
         let db = persistence::OnboardingPagesDb::global(cx);
         window.spawn(cx, async move |cx| {
             if let Some(_) = db.get_onboarding_page(item_id, workspace_id)? {
-                workspace.update(cx, |workspace, cx| Onboarding::new(workspace, cx))
+                workspace.update(cx, |workspace, cx| {
+                    Onboarding::new(workspace, OnboardingPresentation::Tab, cx)
+                })
             } else {
                 Err(anyhow::anyhow!("No onboarding page to deserialize"))
             }
@@ -671,3 +750,112 @@ mod persistence {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::FakeFs;
+    use gpui::{Modifiers, TestAppContext, VisualTestContext};
+    use project::Project;
+    use workspace::MultiWorkspace;
+
+    async fn init_test(cx: &mut TestAppContext) -> (Entity<Workspace>, &mut VisualTestContext) {
+        cx.update(|cx| {
+            AppState::test(cx);
+            theme_settings::init(theme::LoadThemes::All(Box::new(assets::Assets)), cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let window = cx.add_window(|window, cx| MultiWorkspace::test_new(project, window, cx));
+        let cx = VisualTestContext::from_window(*window, cx).into_mut();
+        let workspace = window
+            .read_with(cx, |multi_workspace, _| multi_workspace.workspace().clone())
+            .unwrap();
+        (workspace, cx)
+    }
+
+    #[gpui::test]
+    async fn test_onboarding_renders_as_tab_and_modal(cx: &mut TestAppContext) {
+        let (workspace, cx) = init_test(cx).await;
+
+        let tab = workspace.update_in(cx, |workspace, window, cx| {
+            let tab = Onboarding::new(workspace, OnboardingPresentation::Tab, cx);
+            workspace.add_item_to_active_pane(Box::new(tab.clone()), None, true, window, cx);
+            tab
+        });
+        cx.run_until_parked();
+        cx.update(|window, cx| {
+            assert!(tab.focus_handle(cx).is_focused(window));
+        });
+
+        workspace.update_in(cx, |workspace, window, cx| {
+            let weak_workspace = workspace.weak_handle();
+            let user_store = workspace.user_store().clone();
+            workspace.toggle_modal(window, cx, |_, cx| {
+                Onboarding::build(
+                    weak_workspace,
+                    user_store,
+                    OnboardingPresentation::Modal,
+                    cx,
+                )
+            });
+        });
+        cx.run_until_parked();
+        let modal = workspace
+            .read_with(cx, |workspace, cx| workspace.active_modal::<Onboarding>(cx))
+            .expect("onboarding modal should be open");
+        cx.update(|window, cx| {
+            assert!(modal.focus_handle(cx).is_focused(window));
+        });
+
+        // Finishing from the modal dismisses it without touching the tab.
+        modal.update_in(cx, |modal, window, cx| modal.on_finish(&Finish, window, cx));
+        cx.run_until_parked();
+        workspace.read_with(cx, |workspace, cx| {
+            assert!(workspace.active_modal::<Onboarding>(cx).is_none());
+            assert!(
+                workspace
+                    .active_pane()
+                    .read(cx)
+                    .items()
+                    .any(|item| item.downcast::<Onboarding>().is_some())
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_clicking_a_progress_step_selects_its_section(cx: &mut TestAppContext) {
+        let (workspace, cx) = init_test(cx).await;
+
+        let onboarding = workspace.update_in(cx, |workspace, window, cx| {
+            let onboarding = Onboarding::new(workspace, OnboardingPresentation::Tab, cx);
+            workspace.add_item_to_active_pane(Box::new(onboarding.clone()), None, true, window, cx);
+            onboarding
+        });
+        cx.run_until_parked();
+        onboarding.read_with(cx, |onboarding, _| {
+            assert_eq!(onboarding.current_section, 0)
+        });
+
+        let Some(step_bounds) = cx.debug_bounds("STEP-3") else {
+            panic!("the progress indicator should render a step per section");
+        };
+        cx.simulate_click(step_bounds.center(), Modifiers::none());
+        cx.run_until_parked();
+        onboarding.read_with(cx, |onboarding, _| {
+            assert_eq!(onboarding.current_section, 3)
+        });
+
+        // Out-of-range selections land on the last section.
+        onboarding.update_in(cx, |onboarding, window, cx| {
+            onboarding.select_section(&usize::MAX, window, cx)
+        });
+        onboarding.read_with(cx, |onboarding, _| {
+            assert_eq!(
+                onboarding.current_section,
+                basics_page::BASICS_SECTION_COUNT - 1
+            )
+        });
+    }
+}
@@ -0,0 +1,199 @@
+use std::rc::Rc;
+
+use ui::{SwitchField, Tooltip, component_prelude::Documented, prelude::*};
+
+/// A single switch within a [`SettingsToggleGroup`].
+pub struct SettingsToggle {
+    id: ElementId,
+    label: Option<SharedString>,
+    description: Option<SharedString>,
+    tooltip: Option<SharedString>,
+    enabled: bool,
+    on_toggle: Rc<dyn Fn(bool, &mut Window, &mut App)>,
+}
+
+impl SettingsToggle {
+    pub fn new(
+        id: impl Into<ElementId>,
+        enabled: bool,
+        on_toggle: impl Fn(bool, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            label: None,
+            description: None,
+            tooltip: None,
+            enabled,
+            on_toggle: Rc::new(on_toggle),
+        }
+    }
+
+    pub fn label(mut self, label: impl Into<SharedString>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<SharedString>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn tooltip(mut self, tooltip: impl Into<SharedString>) -> Self {
+        self.tooltip = Some(tooltip.into());
+        self
+    }
+
+    /// Forwards a switch selection to the entry's callback. Indeterminate
+    /// selections carry no on/off value and are ignored.
+    pub fn toggle(&self, selection: &ToggleState, window: &mut Window, cx: &mut App) {
+        let enabled = match selection {
+            ToggleState::Selected => true,
+            ToggleState::Unselected => false,
+            ToggleState::Indeterminate => return,
+        };
+        (self.on_toggle)(enabled, window, cx);
+    }
+}
+
+/// A vertical stack of labeled switches, each backed by a boolean setting.
+#[derive(IntoElement, RegisterComponent, Documented)]
+pub struct SettingsToggleGroup {
+    entries: Vec<SettingsToggle>,
+    tab_index: Option<isize>,
+}
+
+impl SettingsToggleGroup {
+    pub fn new(entries: impl IntoIterator<Item = SettingsToggle>) -> Self {
+        Self {
+            entries: entries.into_iter().collect(),
+            tab_index: None,
+        }
+    }
+
+    /// Assigns consecutive tab indices to the entries, starting at `tab_index`,
+    /// and advances it past the last entry.
+    pub fn tab_index(mut self, tab_index: &mut isize) -> Self {
+        self.tab_index = Some(*tab_index);
+        *tab_index += self.entries.len() as isize;
+        self
+    }
+}
+
+impl RenderOnce for SettingsToggleGroup {
+    fn render(self, _window: &mut Window, _cx: &mut App) -> impl IntoElement {
+        let tab_index = self.tab_index;
+
+        v_flex()
+            .gap_4()
+            .children(self.entries.into_iter().enumerate().map(|(ix, entry)| {
+                let entry = Rc::new(entry);
+                let mut switch = SwitchField::new(
+                    entry.id.clone(),
+                    entry.label.clone(),
+                    entry.description.clone(),
+                    ToggleState::from(entry.enabled),
+                    {
+                        let entry = entry.clone();
+                        move |selection, window, cx| entry.toggle(selection, window, cx)
+                    },
+                );
+                if let Some(tab_index) = tab_index {
+                    switch = switch.tab_index(tab_index + ix as isize);
+                }
+                if let Some(tooltip) = entry.tooltip.clone() {
+                    switch = switch.tooltip(Tooltip::text(tooltip));
+                }
+                switch
+            }))
+    }
+}
+
+impl Component for SettingsToggleGroup {
+    fn scope() -> ComponentScope {
+        ComponentScope::Onboarding
+    }
+
+    fn name() -> &'static str {
+        "Settings Toggle Group"
+    }
+
+    fn sort_name() -> &'static str {
+        "Settings Toggle Group"
+    }
+
+    fn description() -> Option<&'static str> {
+        Some(Self::DOCS)
+    }
+
+    fn preview(_window: &mut Window, _cx: &mut App) -> Option<AnyElement> {
+        Some(
+            v_flex()
+                .gap_6()
+                .p_4()
+                .child(example_group(vec![
+                    single_example(
+                        "Labeled",
+                        SettingsToggleGroup::new([
+                            SettingsToggle::new("preview-vim-mode", true, |_, _, _| {})
+                                .label("Vim Mode")
+                                .description("Use our first-class implementation of Vim Mode"),
+                            SettingsToggle::new("preview-auto-trust", false, |_, _, _| {})
+                                .label("Trust All Projects By Default")
+                                .description("Automatically mark all new projects as trusted")
+                                .tooltip("Shown when hovering the switch"),
+                        ])
+                        .into_any_element(),
+                    ),
+                    single_example(
+                        "Description Only",
+                        SettingsToggleGroup::new([
+                            SettingsToggle::new("preview-metrics", true, |_, _, _| {})
+                                .description("Help improve Zed by sending anonymous usage data"),
+                            SettingsToggle::new("preview-crash-reports", false, |_, _, _| {})
+                                .description("Help fix Zed by sending crash reports"),
+                        ])
+                        .into_any_element(),
+                    ),
+                ]))
+                .into_any_element(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use std::cell::RefCell;
+
+    #[gpui::test]
+    fn test_toggling_entry_invokes_callback(cx: &mut TestAppContext) {
+        let toggled = Rc::new(RefCell::new(Vec::new()));
+        let entry = SettingsToggle::new("test-toggle", false, {
+            let toggled = toggled.clone();
+            move |enabled, _, _| toggled.borrow_mut().push(enabled)
+        });
+
+        let cx = cx.add_empty_window();
+        cx.update(|window, cx| {
+            entry.toggle(&ToggleState::Selected, window, cx);
+            entry.toggle(&ToggleState::Indeterminate, window, cx);
+            entry.toggle(&ToggleState::Unselected, window, cx);
+        });
+
+        assert_eq!(*toggled.borrow(), vec![true, false]);
+    }
+
+    #[test]
+    fn test_tab_index_advances_past_entries() {
+        let mut tab_index = 3;
+        let group = SettingsToggleGroup::new([
+            SettingsToggle::new("a", true, |_, _, _| {}),
+            SettingsToggle::new("b", false, |_, _, _| {}),
+        ])
+        .tab_index(&mut tab_index);
+
+        assert_eq!(group.tab_index, Some(3));
+        assert_eq!(tab_index, 5);
+    }
+}
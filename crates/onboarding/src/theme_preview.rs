@@ -1,8 +1,8 @@
 #![allow(unused, dead_code)]
 use gpui::{Hsla, Length};
 use std::{
-    cell::LazyCell,
-    sync::{Arc, LazyLock, OnceLock},
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{Arc, LazyLock},
 };
 use theme::{Theme, ThemeColors, ThemeRegistry};
 use ui::{
@@ -42,6 +42,9 @@ impl ThemePreviewTile {
     pub const ROOT_BORDER: Pixels = px(2.0);
     pub const ROOT_PADDING: Pixels = px(2.0);
     pub const CHILD_BORDER: Pixels = px(1.0);
+    /// A seed to share across tiles that should show identical content, so that
+    /// only their colors differ and they can be compared side by side.
+    pub const SHARED_SEED: f32 = 0xBEEF as f32;
 
     pub fn new(theme: Arc<Theme>, seed: f32) -> Self {
         Self {
@@ -51,6 +54,19 @@ impl ThemePreviewTile {
         }
     }
 
+    /// Creates a tile whose content is derived from the theme's name, so each
+    /// theme gets its own content that stays the same across renders.
+    pub fn with_theme_seed(theme: Arc<Theme>) -> Self {
+        let seed = Self::seed_for_theme(&theme);
+        Self::new(theme, seed)
+    }
+
+    fn seed_for_theme(theme: &Theme) -> f32 {
+        let mut hasher = DefaultHasher::new();
+        theme.name.hash(&mut hasher);
+        (hasher.finish() % 10_000) as f32 / 10_000.0
+    }
+
     pub fn style(mut self, style: ThemePreviewStyle) -> Self {
         self.style = style;
         self
@@ -67,12 +83,8 @@ impl ThemePreviewTile {
     ) -> [impl IntoElement; Self::SIDEBAR_SKELETON_ITEM_COUNT] {
         let skeleton_height = skeleton_height.into();
         std::array::from_fn(|index| {
-            let width = {
-                let value = (seed * 1000.0 + index as f32 * 10.0).sin() * 0.5 + 0.5;
-                0.5 + value * 0.45
-            };
             Self::item_skeleton(
-                relative(width).into(),
+                relative(sidebar_item_width(seed, index)).into(),
                 skeleton_height,
                 colors.text.alpha(0.45),
             )
@@ -85,69 +97,26 @@ impl ThemePreviewTile {
         skeleton_height: impl Into<Length>,
     ) -> impl IntoElement {
         let colors = theme.colors();
-        let syntax = theme.syntax();
-
-        let keyword_color = syntax.style_for_name("keyword").and_then(|s| s.color);
-        let function_color = syntax.style_for_name("function").and_then(|s| s.color);
-        let string_color = syntax.style_for_name("string").and_then(|s| s.color);
-        let comment_color = syntax.style_for_name("comment").and_then(|s| s.color);
-        let variable_color = syntax.style_for_name("variable").and_then(|s| s.color);
-        let type_color = syntax.style_for_name("type").and_then(|s| s.color);
-        let punctuation_color = syntax.style_for_name("punctuation").and_then(|s| s.color);
-
-        let syntax_colors = [
-            keyword_color,
-            function_color,
-            string_color,
-            variable_color,
-            type_color,
-            punctuation_color,
-            comment_color,
-        ];
+        let syntax_colors = syntax_colors(&theme);
 
         let skeleton_height = skeleton_height.into();
 
-        let line_width = |line_idx: usize, block_idx: usize| -> f32 {
-            let val =
-                (seed * 100.0 + line_idx as f32 * 20.0 + block_idx as f32 * 5.0).sin() * 0.5 + 0.5;
-            0.05 + val * 0.2
-        };
-
-        let indentation = |line_idx: usize| -> f32 {
-            let step = line_idx % 6;
-            if step < 3 {
-                step as f32 * 0.1
-            } else {
-                (5 - step) as f32 * 0.1
-            }
-        };
-
-        let pick_color = |line_idx: usize, block_idx: usize| -> Hsla {
-            let idx = ((seed * 10.0 + line_idx as f32 * 7.0 + block_idx as f32 * 3.0).sin() * 3.5)
-                .abs() as usize
-                % syntax_colors.len();
-            syntax_colors[idx].unwrap_or(colors.text)
-        };
-
-        let line_count = 10;
-
-        let lines = (0..line_count)
-            .map(|line_idx| {
-                let block_count = (((seed * 30.0 + line_idx as f32 * 12.0).sin() * 0.5 + 0.5) * 3.0)
-                    .round() as usize
-                    + 2;
-
-                let indent = indentation(line_idx);
-
-                let blocks = (0..block_count)
-                    .map(|block_idx| {
-                        let width = line_width(line_idx, block_idx);
-                        let color = pick_color(line_idx, block_idx);
-                        Self::item_skeleton(relative(width).into(), skeleton_height, color)
+        let lines = pseudo_code_layout(seed, syntax_colors.len())
+            .into_iter()
+            .map(|line| {
+                let blocks = line
+                    .blocks
+                    .into_iter()
+                    .map(|block| {
+                        let color = syntax_colors[block.color_index].unwrap_or(colors.text);
+                        Self::item_skeleton(relative(block.width).into(), skeleton_height, color)
                     })
                     .collect::<Vec<_>>();
 
-                h_flex().gap_0p5().ml(relative(indent)).children(blocks)
+                h_flex()
+                    .gap_0p5()
+                    .ml(relative(line.indent))
+                    .children(blocks)
             })
             .collect::<Vec<_>>();
 
@@ -282,6 +251,87 @@ impl ThemePreviewTile {
     }
 }
 
+fn syntax_colors(theme: &Theme) -> [Option<Hsla>; 7] {
+    let syntax = theme.syntax();
+
+    let keyword_color = syntax.style_for_name("keyword").and_then(|s| s.color);
+    let function_color = syntax.style_for_name("function").and_then(|s| s.color);
+    let string_color = syntax.style_for_name("string").and_then(|s| s.color);
+    let comment_color = syntax.style_for_name("comment").and_then(|s| s.color);
+    let variable_color = syntax.style_for_name("variable").and_then(|s| s.color);
+    let type_color = syntax.style_for_name("type").and_then(|s| s.color);
+    let punctuation_color = syntax.style_for_name("punctuation").and_then(|s| s.color);
+
+    [
+        keyword_color,
+        function_color,
+        string_color,
+        variable_color,
+        type_color,
+        punctuation_color,
+        comment_color,
+    ]
+}
+
+fn sidebar_item_width(seed: f32, index: usize) -> f32 {
+    let value = (seed * 1000.0 + index as f32 * 10.0).sin() * 0.5 + 0.5;
+    0.5 + value * 0.45
+}
+
+#[derive(Debug, PartialEq)]
+struct PseudoCodeLine {
+    indent: f32,
+    blocks: Vec<PseudoCodeBlock>,
+}
+
+#[derive(Debug, PartialEq)]
+struct PseudoCodeBlock {
+    width: f32,
+    color_index: usize,
+}
+
+fn pseudo_code_layout(seed: f32, color_count: usize) -> Vec<PseudoCodeLine> {
+    let line_width = |line_idx: usize, block_idx: usize| -> f32 {
+        let val =
+            (seed * 100.0 + line_idx as f32 * 20.0 + block_idx as f32 * 5.0).sin() * 0.5 + 0.5;
+        0.05 + val * 0.2
+    };
+
+    let indentation = |line_idx: usize| -> f32 {
+        let step = line_idx % 6;
+        if step < 3 {
+            step as f32 * 0.1
+        } else {
+            (5 - step) as f32 * 0.1
+        }
+    };
+
+    let pick_color = |line_idx: usize, block_idx: usize| -> usize {
+        ((seed * 10.0 + line_idx as f32 * 7.0 + block_idx as f32 * 3.0).sin() * 3.5).abs() as usize
+            % color_count
+    };
+
+    let line_count = 10;
+
+    (0..line_count)
+        .map(|line_idx| {
+            let block_count = (((seed * 30.0 + line_idx as f32 * 12.0).sin() * 0.5 + 0.5) * 3.0)
+                .round() as usize
+                + 2;
+
+            PseudoCodeLine {
+                indent: indentation(line_idx),
+                blocks: (0..block_count)
+                    .map(|block_idx| PseudoCodeBlock {
+                        width: line_width(line_idx, block_idx),
+                        color_index: pick_color(line_idx, block_idx),
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
 impl RenderOnce for ThemePreviewTile {
     fn render(self, _window: &mut ui::Window, _cx: &mut ui::App) -> impl IntoElement {
         match self.style {
@@ -348,7 +398,7 @@ impl Component for ThemePreviewTile {
                             div()
                                 .w(px(240.))
                                 .h(px(180.))
-                                .child(ThemePreviewTile::new(one_dark, 0.42))
+                                .child(ThemePreviewTile::with_theme_seed(one_dark))
                                 .into_any_element(),
                         )])]
                     } else {
@@ -367,7 +417,7 @@ impl Component for ThemePreviewTile {
                                         div()
                                             .w(px(200.))
                                             .h(px(140.))
-                                            .child(ThemePreviewTile::new(theme, 0.42))
+                                            .child(ThemePreviewTile::with_theme_seed(theme))
                                     })
                                     .collect::<Vec<_>>(),
                             )
@@ -379,3 +429,15 @@ impl Component for ThemePreviewTile {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_yields_identical_layout() {
+        let seed = ThemePreviewTile::SHARED_SEED;
+        assert_eq!(pseudo_code_layout(seed, 7), pseudo_code_layout(seed, 7));
+        assert_ne!(pseudo_code_layout(seed, 7), pseudo_code_layout(0.42, 7));
+    }
+}
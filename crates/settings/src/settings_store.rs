@@ -545,11 +545,21 @@ impl SettingsStore {
         fs: Arc<dyn Fs>,
         update: impl 'static + Send + FnOnce(&mut SettingsContent, &App),
     ) {
-        _ = self.update_settings_file_inner(fs, move |old_text: String, cx: AsyncApp| {
+        _ = self.update_settings_file_with_completion(fs, update);
+    }
+
+    /// Like [`Self::update_settings_file`], but returns a receiver that resolves
+    /// once the settings file has been written, or with the reason it wasn't.
+    pub fn update_settings_file_with_completion(
+        &self,
+        fs: Arc<dyn Fs>,
+        update: impl 'static + Send + FnOnce(&mut SettingsContent, &App),
+    ) -> oneshot::Receiver<Result<()>> {
+        self.update_settings_file_inner(fs, move |old_text: String, cx: AsyncApp| {
             cx.read_global(|store: &SettingsStore, cx| {
                 store.new_text_for_update(old_text, |content| update(content, cx))
             })
-        });
+        })
     }
 
     pub fn import_vscode_settings(
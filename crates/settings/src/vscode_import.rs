@@ -949,6 +949,7 @@ impl VsCodeSettings {
             theme: None,
             icon_theme: None,
             ui_density: None,
+            reduce_motion: None,
             unnecessary_code_fade: None,
             experimental_theme_overrides: None,
             theme_overrides: Default::default(),
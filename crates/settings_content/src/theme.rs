@@ -160,6 +160,9 @@ pub struct ThemeSettingsContent {
     #[serde(rename = "unstable.ui_density")]
     pub ui_density: Option<UiDensity>,
 
+    /// Whether to skip decorative animations, such as the feedback shown when a button is pressed.
+    pub reduce_motion: Option<bool>,
+
     /// How much to fade out unused code.
     #[schemars(range(min = 0.0, max = 0.9))]
     pub unnecessary_code_fade: Option<CodeFade>,
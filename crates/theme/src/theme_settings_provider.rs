@@ -21,6 +21,9 @@ pub trait ThemeSettingsProvider: Send + Sync + 'static {
 
     /// Returns the current UI density setting.
     fn ui_density(&self, cx: &App) -> UiDensity;
+
+    /// Returns whether decorative animations should be skipped.
+    fn reduce_motion(&self, cx: &App) -> bool;
 }
 
 struct GlobalThemeSettingsProvider(Box<dyn ThemeSettingsProvider>);
@@ -74,6 +74,8 @@ pub struct ThemeSettings {
     /// The density of the UI.
     /// Note: This setting is still experimental. See [this tracking issue](
     pub ui_density: UiDensity,
+    /// Whether decorative animations should be skipped.
+    pub reduce_motion: bool,
     /// The amount of fading applied to unnecessary code.
     pub unnecessary_code_fade: f32,
 }
@@ -628,6 +630,7 @@ impl settings::Settings for ThemeSettings {
             theme_overrides: content.theme_overrides.clone(),
             icon_theme: icon_theme_selection,
             ui_density: ui_density_from_settings(content.ui_density.unwrap_or_default()),
+            reduce_motion: content.reduce_motion.unwrap_or_default(),
             unnecessary_code_fade: content.unnecessary_code_fade.unwrap().0.clamp(0.0, 0.9),
         }
     }
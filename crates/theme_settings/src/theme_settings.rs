@@ -58,6 +58,10 @@ impl ThemeSettingsProvider for ThemeSettingsProviderImpl {
     fn ui_density(&self, cx: &App) -> UiDensity {
         ThemeSettings::get_global(cx).ui_density
     }
+
+    fn reduce_motion(&self, cx: &App) -> bool {
+        ThemeSettings::get_global(cx).reduce_motion
+    }
 }
 
 /// Initialize the theme system with settings integration.
@@ -194,6 +198,14 @@ pub fn reload_icon_theme(cx: &mut App) {
     cx.refresh_windows();
 }
 
+/// Records a change in the system appearance and reloads the themes, so that
+/// themes set to follow the system switch to their light or dark variant.
+pub fn set_system_appearance(appearance: Appearance, cx: &mut App) {
+    *SystemAppearance::global_mut(cx) = SystemAppearance(appearance);
+    reload_theme(cx);
+    reload_icon_theme(cx);
+}
+
 /// Loads the themes bundled with the Zed binary into the registry.
 pub fn load_bundled_themes(registry: &ThemeRegistry) {
     let theme_paths = registry
@@ -29,10 +29,12 @@ mod notification;
 mod popover;
 mod popover_menu;
 mod progress;
+mod recent_item_button;
 mod redistributable_columns;
 mod right_click_menu;
 mod scrollbar;
 mod stack;
+mod step_progress;
 mod sticky_items;
 mod tab;
 mod tab_bar;
@@ -71,10 +73,12 @@ pub use notification::*;
 pub use popover::*;
 pub use popover_menu::*;
 pub use progress::*;
+pub use recent_item_button::*;
 pub use redistributable_columns::*;
 pub use right_click_menu::*;
 pub use scrollbar::*;
 pub use stack::*;
+pub use step_progress::*;
 pub use sticky_items::*;
 pub use tab::*;
 pub use tab_bar::*;
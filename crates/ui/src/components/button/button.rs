@@ -1,5 +1,6 @@
 use crate::component_prelude::*;
 use gpui::{AnyElement, AnyView, DefiniteLength};
+use std::time::Duration;
 use ui_macros::RegisterComponent;
 
 use crate::traits::animation_ext::CommonAnimationExt;
@@ -194,6 +195,14 @@ impl Button {
         self.loading = loading;
         self
     }
+
+    /// Ignores clicks that arrive within `duration` of the last handled click.
+    ///
+    /// See [`ButtonLike::debounce`].
+    pub fn debounce(mut self, duration: Duration) -> Self {
+        self.base = self.base.debounce(duration);
+        self
+    }
 }
 
 impl Toggleable for Button {
@@ -1,12 +1,14 @@
 use documented::Documented;
 use gpui::{
-    AnyElement, AnyView, ClickEvent, CursorStyle, DefiniteLength, FocusHandle, Hsla, MouseButton,
-    MouseClickEvent, MouseDownEvent, MouseUpEvent, Rems, StyleRefinement, relative,
+    Animation, AnimationExt, AnyElement, AnyView, Bounds, BoxShadow, ClickEvent, CursorStyle,
+    DefiniteLength, FocusHandle, Hsla, MouseButton, MouseClickEvent, MouseDownEvent, MouseUpEvent,
+    Pixels, Point, Rems, StyleRefinement, canvas, ease_out_quint, fill, point, relative, size,
     transparent_black,
 };
 use smallvec::SmallVec;
+use std::time::{Duration, Instant};
 
-use crate::{DynamicSpacing, ElevationIndex, prelude::*};
+use crate::{DynamicSpacing, ElevationIndex, animation::reduce_motion, prelude::*};
 
 /// A trait for buttons that can be Selected. Enables setting the [`ButtonStyle`] of a button when it is selected.
 pub trait SelectableButton: Toggleable {
@@ -197,6 +199,19 @@ pub(crate) struct ButtonLikeStyles {
     pub icon_color: Hsla,
 }
 
+/// The ring drawn around a button that received focus from the keyboard.
+///
+/// This is drawn as a shadow rather than a border so that buttons without a
+/// border don't shift when they gain focus.
+pub(crate) fn focus_ring(cx: &App) -> BoxShadow {
+    BoxShadow {
+        color: cx.theme().colors().border_focused,
+        offset: point(px(0.), px(0.)),
+        blur_radius: px(0.),
+        spread_radius: px(1.),
+    }
+}
+
 fn element_bg_from_elevation(elevation: Option<ElevationIndex>, cx: &mut App) -> Hsla {
     match elevation {
         Some(ElevationIndex::Background) => cx.theme().colors().element_background,
@@ -497,10 +512,66 @@ pub struct ButtonLike {
     cursor_style: CursorStyle,
     on_click: Option<Box<dyn Fn(&ClickEvent, &mut Window, &mut App) + 'static>>,
     on_right_click: Option<Box<dyn Fn(&ClickEvent, &mut Window, &mut App) + 'static>>,
+    debounce: Option<Duration>,
+    press_feedback: bool,
     children: SmallVec<[AnyElement; 2]>,
     focus_handle: Option<FocusHandle>,
 }
 
+/// Tracks the last click a debounced [`ButtonLike`] let through, across renders.
+#[derive(Default)]
+struct ClickDebounce {
+    last_accepted_click: Option<Instant>,
+}
+
+impl ClickDebounce {
+    fn accept(&mut self, now: Instant, window: Duration) -> bool {
+        if let Some(last_accepted_click) = self.last_accepted_click
+            && now.saturating_duration_since(last_accepted_click) < window
+        {
+            return false;
+        }
+        self.last_accepted_click = Some(now);
+        true
+    }
+}
+
+/// Counts presses so each one can start a fresh ripple, and remembers where
+/// the latest press landed so the ripple can grow from there.
+#[derive(Default, Clone, Copy)]
+struct PressFeedback {
+    press_count: usize,
+    origin: Point<Pixels>,
+}
+
+fn press_feedback_animation(press_count: usize, cx: &App) -> Option<Animation> {
+    if press_count == 0 || reduce_motion(cx) {
+        return None;
+    }
+    Some(Animation::new(AnimationDuration::Slow.into()).with_easing(ease_out_quint()))
+}
+
+/// Paints a ripple centered on `origin` that grows to cover the button and
+/// fades out as `delta` goes from 0 to 1.
+fn paint_press_ripple(
+    origin: Point<Pixels>,
+    bounds: Bounds<Pixels>,
+    delta: f32,
+    color: Hsla,
+    window: &mut Window,
+) {
+    let horizontal = f32::from(origin.x - bounds.left()).max(f32::from(bounds.right() - origin.x));
+    let vertical = f32::from(origin.y - bounds.top()).max(f32::from(bounds.bottom() - origin.y));
+    let radius = px(horizontal.hypot(vertical) * delta);
+    window.paint_quad(
+        fill(
+            Bounds::centered_at(origin, size(radius * 2., radius * 2.)),
+            color.opacity(1. - delta),
+        )
+        .corner_radii(radius),
+    );
+}
+
 impl ButtonLike {
     pub fn new(id: impl Into<ElementId>) -> Self {
         Self {
@@ -520,6 +591,8 @@ impl ButtonLike {
             cursor_style: CursorStyle::PointingHand,
             on_click: None,
             on_right_click: None,
+            debounce: None,
+            press_feedback: false,
             layer: None,
             tab_index: None,
             focus_handle: None,
@@ -568,6 +641,24 @@ impl ButtonLike {
         self.hoverable_tooltip = Some(Box::new(tooltip));
         self
     }
+
+    /// Ignores clicks that arrive within `duration` of the last handled click.
+    ///
+    /// Use this for buttons whose action is expensive or not idempotent, where
+    /// an accidental double-click would run it twice.
+    pub fn debounce(mut self, duration: Duration) -> Self {
+        self.debounce = Some(duration);
+        self
+    }
+
+    /// Shows a ripple spreading out from where the button was pressed, to
+    /// acknowledge the press before the click's effect is visible.
+    ///
+    /// The ripple is skipped when the `reduce_motion` setting is enabled.
+    pub fn press_feedback(mut self, press_feedback: bool) -> Self {
+        self.press_feedback = press_feedback;
+        self
+    }
 }
 
 impl Disableable for ButtonLike {
@@ -665,7 +756,29 @@ impl ParentElement for ButtonLike {
 }
 
 impl RenderOnce for ButtonLike {
-    fn render(self, _: &mut Window, cx: &mut App) -> impl IntoElement {
+    fn render(self, window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let debounce = self.debounce.map(|duration| {
+            let state = window.use_keyed_state((self.id.clone(), "debounce"), cx, |_, _| {
+                ClickDebounce::default()
+            });
+            (state, duration)
+        });
+        let press_feedback = (self.press_feedback && !self.disabled).then(|| {
+            window.use_keyed_state((self.id.clone(), "press_feedback"), cx, |_, _| {
+                PressFeedback::default()
+            })
+        });
+        let press_ripple = press_feedback.as_ref().and_then(|state| {
+            let PressFeedback {
+                press_count,
+                origin,
+            } = *state.read(cx);
+            let animation = press_feedback_animation(press_count, cx)?;
+            let id = ElementId::from((self.id.clone(), format!("press_{press_count}")));
+            let color = cx.theme().colors().element_active;
+            Some((id, animation, origin, color))
+        });
+
         let style = self
             .selected_style
             .filter(|_| self.selected)
@@ -727,7 +840,9 @@ impl RenderOnce for ButtonLike {
                                 s.border_color(cx.theme().colors().border_focused)
                             })
                         } else {
-                            this.focus_visible(focus_color)
+                            this.focus_visible(|refinement| {
+                                focus_color(refinement).shadow(vec![focus_ring(cx)])
+                            })
                         }
                     })
                     .active(|active| active.bg(style.active(cx).background))
@@ -769,10 +884,43 @@ impl RenderOnce for ButtonLike {
                     this.on_mouse_down(MouseButton::Left, |_, window, _| window.prevent_default())
                         .on_click(move |event, window, cx| {
                             cx.stop_propagation();
+                            if let Some((state, duration)) = debounce.as_ref() {
+                                let accepted = state.update(cx, |state, _| {
+                                    state.accept(Instant::now(), *duration)
+                                });
+                                if !accepted {
+                                    return;
+                                }
+                            }
                             (on_click)(event, window, cx)
                         })
                 },
             )
+            .when_some(press_feedback, |this, state| {
+                this.on_mouse_down(MouseButton::Left, move |event, _, cx| {
+                    state.update(cx, |state, cx| {
+                        state.press_count += 1;
+                        state.origin = event.position;
+                        cx.notify();
+                    });
+                })
+            })
+            .when_some(press_ripple, |this, (id, animation, origin, color)| {
+                this.relative().overflow_hidden().child(
+                    div()
+                        .debug_selector(|| "PRESS_RIPPLE".into())
+                        .absolute()
+                        .inset_0()
+                        .with_animation(id, animation, move |ripple, delta| {
+                            ripple.child(canvas(
+                                |_, _, _| {},
+                                move |bounds, _, window, _| {
+                                    paint_press_ripple(origin, bounds, delta, color, window)
+                                },
+                            ))
+                        }),
+                )
+            })
             .when_some(self.tooltip, |this, tooltip| {
                 this.tooltip(move |window, cx| tooltip(window, cx))
             })
@@ -878,3 +1026,188 @@ impl Component for ButtonLike {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use gpui::{Font, KeyBinding, Modifiers, Pixels, TestAppContext, font};
+    use theme::{ThemeSettingsProvider, UiDensity};
+
+    use super::*;
+
+    struct TestThemeSettings {
+        font: Font,
+        reduce_motion: bool,
+    }
+
+    impl ThemeSettingsProvider for TestThemeSettings {
+        fn ui_font<'a>(&'a self, _: &'a App) -> &'a Font {
+            &self.font
+        }
+
+        fn buffer_font<'a>(&'a self, _: &'a App) -> &'a Font {
+            &self.font
+        }
+
+        fn ui_font_size(&self, _: &App) -> Pixels {
+            px(14.)
+        }
+
+        fn buffer_font_size(&self, _: &App) -> Pixels {
+            px(14.)
+        }
+
+        fn ui_density(&self, _: &App) -> UiDensity {
+            UiDensity::Default
+        }
+
+        fn reduce_motion(&self, _: &App) -> bool {
+            self.reduce_motion
+        }
+    }
+
+    fn init_test(reduce_motion: bool, cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            theme::init(theme::LoadThemes::JustBase, cx);
+            theme::set_theme_settings_provider(
+                Box::new(TestThemeSettings {
+                    font: font("Zed Plex Sans"),
+                    reduce_motion,
+                }),
+                cx,
+            );
+            cx.bind_keys([KeyBinding::new("tab", menu::SelectNext, None)]);
+        });
+    }
+
+    struct FocusRingTest {
+        focus_handle: FocusHandle,
+        button_focus_handle: FocusHandle,
+    }
+
+    impl Render for FocusRingTest {
+        fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+            div()
+                .size_full()
+                .track_focus(&self.focus_handle)
+                .on_action(|_: &menu::SelectNext, window, cx| window.focus_next(cx))
+                .child(
+                    div().debug_selector(|| "BUTTON".into()).child(
+                        ButtonLike::new("button")
+                            .track_focus(&self.button_focus_handle)
+                            .width(px(80.))
+                            .child(div().size_4()),
+                    ),
+                )
+        }
+    }
+
+    #[gpui::test]
+    fn test_focus_ring_only_applies_when_focus_visible(cx: &mut TestAppContext) {
+        init_test(false, cx);
+        let (view, cx) = cx.add_window_view(|window, cx| {
+            let focus_handle = cx.focus_handle();
+            window.focus(&focus_handle, cx);
+            FocusRingTest {
+                focus_handle,
+                button_focus_handle: cx.focus_handle().tab_stop(true),
+            }
+        });
+        cx.run_until_parked();
+        let shadows_without_ring = cx.update(|window, _| window.rendered_scene_stats().shadows);
+
+        cx.simulate_keystrokes("tab");
+        cx.update(|window, cx| {
+            let button_focus_handle = view.read(cx).button_focus_handle.clone();
+            assert!(button_focus_handle.is_focused(window));
+            assert_eq!(
+                window.rendered_scene_stats().shadows,
+                shadows_without_ring + 1,
+                "focusing the button from the keyboard should draw the focus ring"
+            );
+        });
+
+        let Some(button_bounds) = cx.debug_bounds("BUTTON") else {
+            panic!("the button should be rendered");
+        };
+        cx.simulate_click(
+            button_bounds.origin + point(px(4.), px(4.)),
+            Modifiers::none(),
+        );
+        cx.run_until_parked();
+        cx.update(|window, cx| {
+            let button_focus_handle = view.read(cx).button_focus_handle.clone();
+            assert!(button_focus_handle.is_focused(window));
+            assert_eq!(
+                window.rendered_scene_stats().shadows,
+                shadows_without_ring,
+                "the focus ring should not be drawn after a mouse click"
+            );
+        });
+    }
+
+    #[test]
+    fn test_debounce_ignores_clicks_within_window() {
+        let window = Duration::from_millis(500);
+        let start = Instant::now();
+        let mut debounce = ClickDebounce::default();
+
+        let mut handled_clicks = 0;
+        for click_time in [start, start + Duration::from_millis(100)] {
+            if debounce.accept(click_time, window) {
+                handled_clicks += 1;
+            }
+        }
+        assert_eq!(handled_clicks, 1, "double-click should be handled once");
+
+        assert!(
+            debounce.accept(start + Duration::from_millis(600), window),
+            "clicks after the window has elapsed should be handled"
+        );
+        assert!(!debounce.accept(start + Duration::from_millis(700), window));
+    }
+
+    struct PressFeedbackTest;
+
+    impl Render for PressFeedbackTest {
+        fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+            div().size_full().child(
+                div().debug_selector(|| "BUTTON".into()).child(
+                    ButtonLike::new("button")
+                        .press_feedback(true)
+                        .width(px(80.))
+                        .child(div().size_4()),
+                ),
+            )
+        }
+    }
+
+    fn press_button(reduce_motion: bool, cx: &mut TestAppContext) -> bool {
+        init_test(reduce_motion, cx);
+        let (_, cx) = cx.add_window_view(|_, _| PressFeedbackTest);
+        cx.run_until_parked();
+        assert!(
+            cx.debug_bounds("PRESS_RIPPLE").is_none(),
+            "nothing should animate before the first press"
+        );
+
+        let Some(button_bounds) = cx.debug_bounds("BUTTON") else {
+            panic!("the button should be rendered");
+        };
+        cx.simulate_click(
+            button_bounds.origin + point(px(4.), px(4.)),
+            Modifiers::none(),
+        );
+        cx.run_until_parked();
+        cx.debug_bounds("PRESS_RIPPLE").is_some()
+    }
+
+    #[gpui::test]
+    fn test_press_starts_a_ripple(cx: &mut TestAppContext) {
+        assert!(press_button(false, cx));
+    }
+
+    #[gpui::test]
+    fn test_press_ripple_respects_reduced_motion(cx: &mut TestAppContext) {
+        assert!(!press_button(true, cx));
+    }
+}
@@ -0,0 +1,199 @@
+use chrono::{Duration, Local};
+use gpui::ClickEvent;
+
+use crate::{
+    ButtonLike, KeyBinding,
+    prelude::*,
+    utils::{DateTimeType, FormatDistance},
+};
+
+/// A full-width button for reopening something that was used recently, such as
+/// a project or a file.
+///
+/// Shows an icon, the item's name, its shortened path and, when known, how long
+/// ago the item was last opened.
+#[derive(IntoElement, RegisterComponent)]
+pub struct RecentItemButton {
+    id: ElementId,
+    icon: IconName,
+    name: SharedString,
+    path: Option<SharedString>,
+    timestamp: Option<DateTimeType>,
+    key_binding: Option<KeyBinding>,
+    tab_index: Option<isize>,
+    on_click: Option<Box<dyn Fn(&ClickEvent, &mut Window, &mut App) + 'static>>,
+}
+
+impl RecentItemButton {
+    pub fn new(id: impl Into<ElementId>, icon: IconName, name: impl Into<SharedString>) -> Self {
+        Self {
+            id: id.into(),
+            icon,
+            name: name.into(),
+            path: None,
+            timestamp: None,
+            key_binding: None,
+            tab_index: None,
+            on_click: None,
+        }
+    }
+
+    /// Sets the path shown below the name. Callers are expected to shorten it
+    /// first, e.g. by replacing the home directory with `~`.
+    pub fn path(mut self, path: impl Into<SharedString>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Sets when the item was last opened, shown as a relative time.
+    pub fn timestamp(mut self, timestamp: DateTimeType) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn key_binding(mut self, key_binding: impl Into<Option<KeyBinding>>) -> Self {
+        self.key_binding = key_binding.into();
+        self
+    }
+
+    pub fn tab_index(mut self, tab_index: isize) -> Self {
+        self.tab_index = Some(tab_index);
+        self
+    }
+
+    pub fn on_click(
+        mut self,
+        handler: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_click = Some(Box::new(handler));
+        self
+    }
+
+    fn timestamp_label(&self) -> Option<String> {
+        self.timestamp.map(|timestamp| {
+            FormatDistance::from_now(timestamp)
+                .add_suffix(true)
+                .to_string()
+        })
+    }
+}
+
+impl RenderOnce for RecentItemButton {
+    fn render(self, _window: &mut Window, _cx: &mut App) -> impl IntoElement {
+        let timestamp_label = self.timestamp_label();
+
+        ButtonLike::new(self.id)
+            .full_width()
+            .size(ButtonSize::Medium)
+            .when_some(self.tab_index, |this, tab_index| this.tab_index(tab_index))
+            .child(
+                h_flex()
+                    .w_full()
+                    .min_w_0()
+                    .gap_2()
+                    .justify_between()
+                    .child(
+                        h_flex()
+                            .min_w_0()
+                            .gap_2()
+                            .child(
+                                Icon::new(self.icon)
+                                    .color(Color::Muted)
+                                    .size(IconSize::Small),
+                            )
+                            .child(
+                                v_flex()
+                                    .min_w_0()
+                                    .child(Label::new(self.name).truncate())
+                                    .when_some(self.path, |this, path| {
+                                        this.child(
+                                            Label::new(path)
+                                                .size(LabelSize::XSmall)
+                                                .color(Color::Muted)
+                                                .truncate(),
+                                        )
+                                    }),
+                            ),
+                    )
+                    .child(
+                        h_flex()
+                            .flex_none()
+                            .gap_2()
+                            .when_some(timestamp_label, |this, label| {
+                                this.child(
+                                    Label::new(label)
+                                        .size(LabelSize::XSmall)
+                                        .color(Color::Muted),
+                                )
+                            })
+                            .children(
+                                self.key_binding
+                                    .map(|key_binding| key_binding.size(rems_from_px(12.))),
+                            ),
+                    ),
+            )
+            .when_some(self.on_click, |this, on_click| this.on_click(on_click))
+    }
+}
+
+impl Component for RecentItemButton {
+    fn scope() -> ComponentScope {
+        ComponentScope::Navigation
+    }
+
+    fn description() -> Option<&'static str> {
+        Some("A button for reopening a recently used project or file.")
+    }
+
+    fn preview(_window: &mut Window, _cx: &mut App) -> Option<AnyElement> {
+        let hours_ago = |hours| DateTimeType::Local(Local::now() - Duration::hours(hours));
+
+        Some(
+            v_flex()
+                .gap_6()
+                .child(example_group_with_title(
+                    "Recent Item Button",
+                    vec![
+                        single_example(
+                            "Name Only",
+                            RecentItemButton::new("preview-name-only", IconName::Folder, "zed")
+                                .into_any_element(),
+                        ),
+                        single_example(
+                            "Project",
+                            RecentItemButton::new("preview-project", IconName::Folder, "zed")
+                                .path("~/projects/zed")
+                                .timestamp(hours_ago(3))
+                                .into_any_element(),
+                        ),
+                        single_example(
+                            "Remote Project",
+                            RecentItemButton::new(
+                                "preview-remote",
+                                IconName::Server,
+                                "Remote Project",
+                            )
+                            .path("dev-box:~/src/api")
+                            .timestamp(hours_ago(24 * 12))
+                            .into_any_element(),
+                        ),
+                    ],
+                ))
+                .into_any_element(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_renders_as_relative_time() {
+        let button = RecentItemButton::new("recent", IconName::Folder, "zed");
+        assert_eq!(button.timestamp_label(), None);
+
+        let button = button.timestamp(DateTimeType::Local(Local::now() - Duration::days(3)));
+        assert_eq!(button.timestamp_label().as_deref(), Some("3 days ago"));
+    }
+}
@@ -0,0 +1,253 @@
+use std::{collections::BTreeSet, rc::Rc};
+
+use gpui::Stateful;
+
+use crate::prelude::*;
+
+/// How each step of a [`StepProgress`] is drawn.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StepProgressStyle {
+    /// A small dot per step, with the current step drawn as a wider pill.
+    #[default]
+    Dots,
+    /// A numbered circle per step.
+    Numbers,
+}
+
+/// A horizontal indicator showing how far along a multi-step flow the user is.
+///
+/// The current step is emphasized and completed steps are shown with a check
+/// mark. When a click handler is set, clicking a step reports its index so the
+/// owner can jump to it.
+#[derive(IntoElement, RegisterComponent)]
+pub struct StepProgress {
+    id: ElementId,
+    total_steps: usize,
+    current_step: usize,
+    completed_steps: BTreeSet<usize>,
+    style: StepProgressStyle,
+    on_step_click: Option<Box<dyn Fn(&usize, &mut Window, &mut App) + 'static>>,
+}
+
+impl StepProgress {
+    /// Creates an indicator for `total_steps` steps. A `current_step` past the
+    /// end is clamped to the last step.
+    pub fn new(id: impl Into<ElementId>, total_steps: usize, current_step: usize) -> Self {
+        Self {
+            id: id.into(),
+            total_steps,
+            current_step: current_step.min(total_steps.saturating_sub(1)),
+            completed_steps: BTreeSet::default(),
+            style: StepProgressStyle::default(),
+            on_step_click: None,
+        }
+    }
+
+    /// Marks the given steps as completed. Indices past the last step are ignored.
+    pub fn completed(mut self, steps: impl IntoIterator<Item = usize>) -> Self {
+        let total_steps = self.total_steps;
+        self.completed_steps
+            .extend(steps.into_iter().filter(|step| *step < total_steps));
+        self
+    }
+
+    /// Sets whether steps are drawn as dots or numbered circles.
+    pub fn style(mut self, style: StepProgressStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Makes each step clickable, calling `handler` with the index of the
+    /// clicked step. The owner decides whether to make that step current.
+    pub fn on_step_click(
+        mut self,
+        handler: impl Fn(&usize, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_step_click = Some(Box::new(handler));
+        self
+    }
+
+    fn render_step(&self, step: usize, cx: &App) -> Stateful<Div> {
+        let is_current = step == self.current_step;
+        let is_completed = self.completed_steps.contains(&step);
+        let colors = cx.theme().colors();
+
+        let (background, border) = if is_current {
+            (colors.text_accent, colors.text_accent)
+        } else if is_completed {
+            (colors.element_selected, colors.border)
+        } else {
+            (colors.element_background, colors.border)
+        };
+
+        let step_element = div()
+            .id(step)
+            .debug_selector(move || format!("STEP-{step}"))
+            .flex_none()
+            .flex()
+            .items_center()
+            .justify_center()
+            .rounded_full()
+            .border_1()
+            .border_color(border)
+            .bg(background);
+
+        match self.style {
+            StepProgressStyle::Dots => step_element
+                .h(rems_from_px(12.))
+                .w(if is_current {
+                    rems_from_px(24.)
+                } else {
+                    rems_from_px(12.)
+                })
+                .when(is_completed && !is_current, |this| {
+                    this.child(
+                        Icon::new(IconName::Check)
+                            .size(IconSize::XSmall)
+                            .color(Color::Muted),
+                    )
+                }),
+            StepProgressStyle::Numbers => step_element.size(rems_from_px(20.)).map(|this| {
+                if is_completed && !is_current {
+                    this.child(
+                        Icon::new(IconName::Check)
+                            .size(IconSize::XSmall)
+                            .color(Color::Default),
+                    )
+                } else {
+                    this.child(
+                        Label::new((step + 1).to_string())
+                            .size(LabelSize::XSmall)
+                            .color(if is_current {
+                                Color::Default
+                            } else {
+                                Color::Muted
+                            }),
+                    )
+                }
+            }),
+        }
+    }
+}
+
+impl RenderOnce for StepProgress {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let steps = (0..self.total_steps)
+            .map(|step| self.render_step(step, cx))
+            .collect::<Vec<_>>();
+        let on_step_click = self
+            .on_step_click
+            .map(Rc::<dyn Fn(&usize, &mut Window, &mut App)>::from);
+
+        h_flex()
+            .id(self.id)
+            .gap_1p5()
+            .children(steps.into_iter().enumerate().map(|(step, element)| {
+                element.when_some(on_step_click.clone(), |this, on_step_click| {
+                    this.cursor_pointer()
+                        .on_click(move |_, window, cx| on_step_click(&step, window, cx))
+                })
+            }))
+    }
+}
+
+impl Component for StepProgress {
+    fn scope() -> ComponentScope {
+        ComponentScope::Onboarding
+    }
+
+    fn description() -> Option<&'static str> {
+        Some("An indicator of progress through a multi-step flow, such as a walkthrough.")
+    }
+
+    fn preview(_window: &mut Window, _cx: &mut App) -> Option<AnyElement> {
+        Some(
+            v_flex()
+                .gap_6()
+                .child(example_group_with_title(
+                    "Dots",
+                    vec![
+                        single_example(
+                            "First Step",
+                            StepProgress::new("preview-dots-first", 5, 0).into_any_element(),
+                        ),
+                        single_example(
+                            "In Progress",
+                            StepProgress::new("preview-dots-progress", 5, 2)
+                                .completed(0..2)
+                                .into_any_element(),
+                        ),
+                    ],
+                ))
+                .child(example_group_with_title(
+                    "Numbers",
+                    vec![
+                        single_example(
+                            "In Progress",
+                            StepProgress::new("preview-numbers-progress", 5, 2)
+                                .completed(0..2)
+                                .style(StepProgressStyle::Numbers)
+                                .into_any_element(),
+                        ),
+                        single_example(
+                            "Skipped Ahead",
+                            StepProgress::new("preview-numbers-skipped", 5, 3)
+                                .completed([0, 4])
+                                .style(StepProgressStyle::Numbers)
+                                .into_any_element(),
+                        ),
+                    ],
+                ))
+                .into_any_element(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gpui::{Modifiers, TestAppContext};
+
+    use super::*;
+
+    struct Walkthrough {
+        current_step: usize,
+    }
+
+    impl Render for Walkthrough {
+        fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+            div().size_full().child(
+                StepProgress::new("steps", 4, self.current_step)
+                    .completed(0..self.current_step)
+                    .on_step_click(cx.listener(|this, step: &usize, _, cx| {
+                        this.current_step = *step;
+                        cx.notify();
+                    })),
+            )
+        }
+    }
+
+    #[test]
+    fn test_out_of_range_steps_are_clamped() {
+        let progress = StepProgress::new("steps", 3, 7).completed([1, 3, 9]);
+        assert_eq!(progress.current_step, 2);
+        assert_eq!(progress.completed_steps, BTreeSet::from([1]));
+
+        let empty = StepProgress::new("empty", 0, 0);
+        assert_eq!(empty.current_step, 0);
+    }
+
+    #[gpui::test]
+    fn test_clicking_a_step_makes_it_active(cx: &mut TestAppContext) {
+        cx.update(|cx| theme::init(theme::LoadThemes::JustBase, cx));
+        let (walkthrough, cx) = cx.add_window_view(|_, _| Walkthrough { current_step: 0 });
+        cx.run_until_parked();
+
+        let Some(step_bounds) = cx.debug_bounds("STEP-2") else {
+            panic!("the third step should be rendered");
+        };
+        cx.simulate_click(step_bounds.center(), Modifiers::none());
+        cx.run_until_parked();
+
+        walkthrough.read_with(cx, |walkthrough, _| assert_eq!(walkthrough.current_step, 2));
+    }
+}
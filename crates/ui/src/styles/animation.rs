@@ -23,6 +23,14 @@ impl Into<std::time::Duration> for AnimationDuration {
     }
 }
 
+/// Returns whether the user prefers reduced motion.
+///
+/// When this is true, components should skip decorative animations and snap
+/// straight to their final state.
+pub fn reduce_motion(cx: &App) -> bool {
+    theme::theme_settings(cx).reduce_motion(cx)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AnimationDirection {
     FromBottom,
@@ -1,15 +1,15 @@
 use crate::{
-    NewFile, Open, OpenMode, PathList, SerializedWorkspaceLocation, ToggleWorkspaceSidebar,
-    Workspace, WorkspaceId,
+    NewFile, Open, OpenMode, OpenOptions, OpenVisible, Pane, PathList, SerializedWorkspaceLocation,
+    ToggleWorkspaceSidebar, Workspace, WorkspaceId,
     item::{Item, ItemEvent},
     persistence::WorkspaceDb,
 };
 use agent_settings::AgentSettings;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, Utc};
 use git::Clone as GitClone;
 use gpui::{
-    Action, App, Context, Entity, EventEmitter, FocusHandle, Focusable, InteractiveElement,
-    ParentElement, Render, Styled, Task, Window, actions,
+    Action, App, Context, Entity, EventEmitter, ExternalPaths, FocusHandle, Focusable,
+    InteractiveElement, ParentElement, Render, Styled, Subscription, Task, Window, actions,
 };
 use gpui::{WeakEntity, linear_color_stop, linear_gradient};
 use menu::{SelectNext, SelectPrevious};
@@ -17,8 +17,13 @@ use menu::{SelectNext, SelectPrevious};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
-use ui::{ButtonLike, Divider, DividerColor, KeyBinding, Vector, VectorName, prelude::*};
-use util::ResultExt;
+use std::{any::Any, path::PathBuf};
+use ui::{
+    ButtonLike, Divider, DividerColor, KeyBinding, RecentItemButton, Vector, VectorName,
+    prelude::*, utils::DateTimeType,
+};
+use ui_input::{ErasedEditorEvent, InputField};
+use util::{ResultExt, paths::PathExt as _};
 use zed_actions::{
     Extensions, OpenKeymap, OpenOnboarding, OpenSettings, assistant::ToggleFocus, command_palette,
 };
@@ -250,6 +255,8 @@ pub struct WelcomePage {
             DateTime<Utc>,
         )>,
     >,
+    recent_projects_filter: Option<Entity<InputField>>,
+    _recent_projects_filter_subscription: Option<Subscription>,
 }
 
 impl WelcomePage {
@@ -285,14 +292,71 @@ impl WelcomePage {
             .detach();
         }
 
+        let recent_projects_filter = (fallback_to_recent_projects
+            && ui_input::ERASED_EDITOR_FACTORY.get().is_some())
+        .then(|| {
+            cx.new(|cx| {
+                InputField::new(window, cx, "Filter recent projects…")
+                    .start_icon(IconName::MagnifyingGlass)
+            })
+        });
+        let recent_projects_filter_subscription = recent_projects_filter.as_ref().map(|filter| {
+            let this = cx.weak_entity();
+            let editor = filter.read(cx).editor().clone();
+            editor.subscribe(
+                Box::new(move |event, _, cx| {
+                    if event == ErasedEditorEvent::BufferEdited {
+                        this.update(cx, |_, cx| cx.notify()).ok();
+                    }
+                }),
+                window,
+                cx,
+            )
+        });
+
         WelcomePage {
             workspace,
             focus_handle,
             fallback_to_recent_projects,
             recent_workspaces: None,
+            recent_projects_filter,
+            _recent_projects_filter_subscription: recent_projects_filter_subscription,
         }
     }
 
+    fn recent_projects_query(&self, cx: &App) -> String {
+        self.recent_projects_filter
+            .as_ref()
+            .map(|filter| filter.read(cx).text(cx).trim().to_string())
+            .unwrap_or_default()
+    }
+
+    fn matching_recent_workspaces(
+        &self,
+        cx: &App,
+    ) -> impl Iterator<
+        Item = (
+            usize,
+            &SerializedWorkspaceLocation,
+            &PathList,
+            &DateTime<Utc>,
+        ),
+    > {
+        let query = self.recent_projects_query(cx).to_lowercase();
+        self.recent_workspaces
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter(move |(_, (_, _, paths, _))| {
+                query.is_empty()
+                    || paths
+                        .paths()
+                        .iter()
+                        .any(|path| path.to_string_lossy().to_lowercase().contains(&query))
+            })
+            .map(|(index, (_, location, paths, timestamp))| (index, location, paths, timestamp))
+    }
+
     fn select_next(&mut self, _: &SelectNext, window: &mut Window, cx: &mut Context<Self>) {
         window.focus_next(cx);
         cx.notify();
@@ -316,15 +380,8 @@ impl WelcomePage {
                 let is_local = matches!(location, SerializedWorkspaceLocation::Local);
 
                 if is_local {
-                    let paths = paths.clone();
                     let paths = paths.paths().to_vec();
-                    self.workspace
-                        .update(cx, |workspace, cx| {
-                            workspace
-                                .open_workspace_for_paths(OpenMode::Activate, paths, window, cx)
-                                .detach_and_log_err(cx);
-                        })
-                        .log_err();
+                    open_local_project(&self.workspace, paths, window, cx);
                 } else {
                     use zed_actions::OpenRecent;
                     window.dispatch_action(OpenRecent::default().boxed_clone(), cx);
@@ -333,6 +390,57 @@ impl WelcomePage {
         }
     }
 
+    /// Opens dropped folders as a project, the same way the recent project
+    /// entries do. Any other dropped paths are opened in the current workspace.
+    fn open_dropped_paths(&self, paths: &ExternalPaths, window: &mut Window, cx: &mut App) -> bool {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return false;
+        };
+        let workspace = workspace.read(cx);
+        if !workspace.project().read(cx).is_local() {
+            return false;
+        }
+
+        let fs = workspace.app_state().fs.clone();
+        let paths = paths.paths().to_vec();
+        let workspace = self.workspace.clone();
+        window
+            .spawn(cx, async move |cx| {
+                let mut directories = Vec::new();
+                for path in &paths {
+                    if fs.is_dir(path).await {
+                        directories.push(path.clone());
+                    }
+                }
+
+                cx.update(|window, cx| {
+                    if directories.is_empty() {
+                        workspace
+                            .update(cx, |workspace, cx| {
+                                workspace
+                                    .open_paths(
+                                        paths,
+                                        OpenOptions {
+                                            visible: Some(OpenVisible::OnlyDirectories),
+                                            ..Default::default()
+                                        },
+                                        None,
+                                        window,
+                                        cx,
+                                    )
+                                    .detach();
+                            })
+                            .log_err();
+                    } else {
+                        open_local_project(&workspace, directories, window, cx);
+                    }
+                })
+                .log_err();
+            })
+            .detach();
+        true
+    }
+
     fn render_agent_card(&self, tab_index: usize, cx: &mut Context<Self>) -> impl IntoElement {
         let focus = self.focus_handle.clone();
         let color = cx.theme().colors();
@@ -385,10 +493,26 @@ impl WelcomePage {
     fn render_recent_project_section(
         &self,
         recent_projects: Vec<impl IntoElement>,
+        cx: &App,
     ) -> impl IntoElement {
+        let query = self.recent_projects_query(cx);
+        let no_matches = !query.is_empty() && recent_projects.is_empty();
+
         v_flex()
             .w_full()
             .child(SectionHeader::new("Recent Projects"))
+            .when_some(self.recent_projects_filter.clone(), |this, filter| {
+                this.child(div().px_1().mb_1().child(filter))
+            })
+            .when(no_matches, |this| {
+                this.child(
+                    div().px_1().child(
+                        Label::new(format!("No projects match \"{query}\""))
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+                )
+            })
             .children(recent_projects)
     }
 
@@ -398,30 +522,35 @@ impl WelcomePage {
         tab_index: usize,
         location: &SerializedWorkspaceLocation,
         paths: &PathList,
+        timestamp: &DateTime<Utc>,
+        cx: &App,
     ) -> impl IntoElement {
-        let (icon, title) = match location {
+        let first_path = paths.paths().first();
+        let (icon, title, path) = match location {
             SerializedWorkspaceLocation::Local => {
-                let path = paths.paths().first().map(|p| p.as_path());
-                let name = path
+                let name = first_path
                     .and_then(|p| p.file_name())
                     .map(|n| n.to_string_lossy().to_string())
                     .unwrap_or_else(|| "Untitled".to_string());
-                (IconName::Folder, name)
+                let path = first_path.map(|p| p.compact().to_string_lossy().to_string());
+                (IconName::Folder, name, path)
             }
             SerializedWorkspaceLocation::Remote(_) => {
-                (IconName::Server, "Remote Project".to_string())
+                (IconName::Server, "Remote Project".to_string(), None)
             }
         };
 
-        SectionButton::new(
-            title,
-            icon,
-            &OpenRecentProject {
-                index: project_index,
-            },
-            tab_index,
-            self.focus_handle.clone(),
-        )
+        let action = OpenRecentProject {
+            index: project_index,
+        };
+        let focus_handle = self.focus_handle.clone();
+
+        RecentItemButton::new(format!("recent-project-{project_index}"), icon, title)
+            .when_some(path, |this, path| this.path(path))
+            .timestamp(DateTimeType::Local(timestamp.with_timezone(&Local)))
+            .tab_index(tab_index as isize)
+            .key_binding(KeyBinding::for_action_in(&action, &self.focus_handle, cx))
+            .on_click(move |_, window, cx| focus_handle.dispatch_action(&action, window, cx))
     }
 }
 
@@ -434,21 +563,28 @@ impl Render for WelcomePage {
         let ai_enabled = AgentSettings::get_global(cx).enabled(cx);
 
         let recent_projects = self
-            .recent_workspaces
-            .as_ref()
-            .into_iter()
-            .flatten()
+            .matching_recent_workspaces(cx)
             .take(5)
             .enumerate()
-            .map(|(index, (_, loc, paths, _))| {
-                self.render_recent_project(index, first_section_entries + index, loc, paths)
+            .map(|(position, (index, loc, paths, timestamp))| {
+                self.render_recent_project(
+                    index,
+                    first_section_entries + position,
+                    loc,
+                    paths,
+                    timestamp,
+                    cx,
+                )
             })
             .collect::<Vec<_>>();
 
-        let showing_recent_projects =
-            self.fallback_to_recent_projects && !recent_projects.is_empty();
+        let showing_recent_projects = self.fallback_to_recent_projects
+            && self
+                .recent_workspaces
+                .as_ref()
+                .is_some_and(|workspaces| !workspaces.is_empty());
         let second_section = if showing_recent_projects {
-            self.render_recent_project_section(recent_projects)
+            self.render_recent_project_section(recent_projects, cx)
                 .into_any_element()
         } else {
             second_section
@@ -546,6 +682,50 @@ impl Item for WelcomePage {
     fn to_item_events(event: &Self::Event, f: &mut dyn FnMut(crate::item::ItemEvent)) {
         f(*event)
     }
+
+    fn handle_drop(
+        &self,
+        _active_pane: &Pane,
+        dropped: &dyn Any,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> bool {
+        match dropped.downcast_ref::<ExternalPaths>() {
+            Some(paths) => self.open_dropped_paths(paths, window, cx),
+            None => false,
+        }
+    }
+}
+
+fn open_local_project(
+    workspace: &WeakEntity<Workspace>,
+    paths: Vec<PathBuf>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    // Go through the multi-workspace so that replacing the current
+    // workspace prompts to save unsaved changes first.
+    let multi_workspace = workspace.upgrade().and_then(|workspace| {
+        workspace
+            .read(cx)
+            .multi_workspace()
+            .and_then(|multi_workspace| multi_workspace.upgrade())
+    });
+    if let Some(multi_workspace) = multi_workspace {
+        multi_workspace.update(cx, |multi_workspace, cx| {
+            multi_workspace
+                .open_project(paths, OpenMode::Activate, window, cx)
+                .detach_and_log_err(cx);
+        });
+    } else {
+        workspace
+            .update(cx, |workspace, cx| {
+                workspace
+                    .open_workspace_for_paths(OpenMode::Activate, paths, window, cx)
+                    .detach_and_log_err(cx);
+            })
+            .log_err();
+    }
 }
 
 impl crate::SerializableItem for WelcomePage {
@@ -661,3 +841,274 @@ mod persistence {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        MultiWorkspace,
+        item::{
+            ItemHandle as _,
+            test::{TestItem, TestProjectItem},
+        },
+    };
+    use fs::FakeFs;
+    use gpui::{AnyElement, TestAppContext, VisualTestContext};
+    use project::{DisableAiSettings, Project};
+    use serde_json::json;
+    use settings::SettingsStore;
+    use std::sync::Arc;
+    use ui_input::ErasedEditor;
+
+    /// Stands in for the editor-backed input, which can't be built from this crate.
+    struct FakeFilterEditor {
+        text: Entity<String>,
+        focus_handle: FocusHandle,
+    }
+
+    impl ErasedEditor for FakeFilterEditor {
+        fn text(&self, cx: &App) -> String {
+            self.text.read(cx).clone()
+        }
+
+        fn set_text(&self, text: &str, _: &mut Window, cx: &mut App) {
+            self.text.update(cx, |current_text, cx| {
+                *current_text = text.to_string();
+                cx.notify();
+            });
+        }
+
+        fn clear(&self, window: &mut Window, cx: &mut App) {
+            self.set_text("", window, cx);
+        }
+
+        fn set_placeholder_text(&self, _: &str, _: &mut Window, _: &mut App) {}
+
+        fn move_selection_to_end(&self, _: &mut Window, _: &mut App) {}
+
+        fn set_masked(&self, _: bool, _: &mut Window, _: &mut App) {}
+
+        fn focus_handle(&self, _: &App) -> FocusHandle {
+            self.focus_handle.clone()
+        }
+
+        fn subscribe(
+            &self,
+            mut callback: Box<dyn FnMut(ErasedEditorEvent, &mut Window, &mut App) + 'static>,
+            window: &mut Window,
+            cx: &mut App,
+        ) -> Subscription {
+            window.observe(&self.text, cx, move |_, window, cx| {
+                callback(ErasedEditorEvent::BufferEdited, window, cx)
+            })
+        }
+
+        fn render(&self, _: &mut Window, _: &App) -> AnyElement {
+            div().into_any_element()
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[gpui::test]
+    async fn test_open_recent_project_with_unsaved_changes_can_be_cancelled(
+        cx: &mut TestAppContext,
+    ) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            cx.set_global(db::AppDatabase::test_new());
+            theme_settings::init(theme::LoadThemes::JustBase, cx);
+            DisableAiSettings::register(cx);
+            DisableAiSettings::override_global(DisableAiSettings { disable_ai: true }, cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/root", json!({ "one": "" })).await;
+        fs.insert_tree("/other", json!({ "two": "" })).await;
+        let project = Project::test(fs, ["/root".as_ref()], cx).await;
+        let (multi_workspace, cx) =
+            cx.add_window_view(|window, cx| MultiWorkspace::test_new(project, window, cx));
+        let workspace =
+            multi_workspace.read_with(cx, |multi_workspace, _| multi_workspace.workspace().clone());
+
+        let untitled_item = cx.new(|cx| TestItem::new(cx).with_dirty(true));
+        let saved_item = cx.new(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_project_items(&[TestProjectItem::new(1, "1.txt", cx)])
+        });
+        let welcome_page = workspace.update_in(cx, |workspace, window, cx| {
+            workspace.add_item_to_active_pane(Box::new(untitled_item), None, true, window, cx);
+            workspace.add_item_to_active_pane(Box::new(saved_item), None, true, window, cx);
+            let weak_workspace = workspace.weak_handle();
+            cx.new(|cx| WelcomePage::new(weak_workspace, false, window, cx))
+        });
+        welcome_page.update(cx, |welcome_page, _| {
+            welcome_page.recent_workspaces = Some(vec![(
+                WorkspaceId::from_i64(1),
+                SerializedWorkspaceLocation::Local,
+                PathList::new(&["/other"]),
+                Utc::now(),
+            )]);
+        });
+
+        welcome_page.update_in(cx, |welcome_page, window, cx| {
+            welcome_page.open_recent_project(&OpenRecentProject { index: 0 }, window, cx);
+        });
+        cx.run_until_parked();
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer("Cancel");
+        cx.run_until_parked();
+
+        multi_workspace.read_with(cx, |multi_workspace, _| {
+            assert_eq!(multi_workspace.workspace(), &workspace);
+        });
+        workspace.read_with(cx, |workspace, cx| {
+            assert!(workspace.items(cx).any(|item| item.is_dirty(cx)));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_dropping_folder_opens_it_as_project(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            cx.set_global(db::AppDatabase::test_new());
+            theme_settings::init(theme::LoadThemes::JustBase, cx);
+            DisableAiSettings::register(cx);
+            DisableAiSettings::override_global(DisableAiSettings { disable_ai: true }, cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/root", json!({ "one": "" })).await;
+        fs.insert_tree("/other", json!({ "two": "" })).await;
+        let project = Project::test(fs, ["/root".as_ref()], cx).await;
+        let (multi_workspace, cx) =
+            cx.add_window_view(|window, cx| MultiWorkspace::test_new(project, window, cx));
+        let workspace =
+            multi_workspace.read_with(cx, |multi_workspace, _| multi_workspace.workspace().clone());
+
+        let untitled_item = cx.new(|cx| TestItem::new(cx).with_dirty(true));
+        let pane = workspace.update_in(cx, |workspace, window, cx| {
+            workspace.add_item_to_active_pane(Box::new(untitled_item), None, true, window, cx);
+            let welcome_page =
+                cx.new(|cx| WelcomePage::new(workspace.weak_handle(), false, window, cx));
+            workspace.add_item_to_active_pane(Box::new(welcome_page), None, true, window, cx);
+            workspace.active_pane().clone()
+        });
+
+        // Dropping a folder replaces the current workspace, so the unsaved item
+        // has to be resolved first.
+        let dropped = ExternalPaths(vec![PathBuf::from("/other")].into());
+        let handled = pane.update_in(cx, |pane, window, cx| {
+            let welcome_page = pane.active_item().expect("welcome page should be active");
+            welcome_page.handle_drop(pane, &dropped, window, cx)
+        });
+        assert!(handled);
+        cx.run_until_parked();
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer("Cancel");
+        cx.run_until_parked();
+
+        multi_workspace.read_with(cx, |multi_workspace, _| {
+            assert_eq!(multi_workspace.workspace(), &workspace);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_filter_input_filters_recent_projects(cx: &mut TestAppContext) {
+        _ = ui_input::ERASED_EDITOR_FACTORY.set(|_, cx| {
+            Arc::new(FakeFilterEditor {
+                text: cx.new(|_| String::new()),
+                focus_handle: cx.focus_handle(),
+            }) as Arc<dyn ErasedEditor>
+        });
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            cx.set_global(db::AppDatabase::test_new());
+            theme_settings::init(theme::LoadThemes::JustBase, cx);
+            DisableAiSettings::register(cx);
+            AgentSettings::register(cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let (workspace, cx) =
+            cx.add_window_view(|window, cx| Workspace::test_new(project, window, cx));
+        let weak_workspace = workspace.read_with(cx, |workspace, _| workspace.weak_handle());
+        let welcome_page =
+            cx.update(|window, cx| cx.new(|cx| WelcomePage::new(weak_workspace, true, window, cx)));
+        cx.run_until_parked();
+
+        welcome_page.update(cx, |welcome_page, _| {
+            welcome_page.recent_workspaces = Some(
+                ["/code/zed", "/code/Other-Project", "/notes"]
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, path)| {
+                        (
+                            WorkspaceId::from_i64(index as i64),
+                            SerializedWorkspaceLocation::Local,
+                            PathList::new(&[path]),
+                            Utc::now(),
+                        )
+                    })
+                    .collect(),
+            );
+        });
+        workspace.update_in(cx, |workspace, window, cx| {
+            workspace.add_item_to_active_pane(
+                Box::new(welcome_page.clone()),
+                None,
+                true,
+                window,
+                cx,
+            );
+            window.focus(&welcome_page.focus_handle(cx), cx);
+        });
+        cx.run_until_parked();
+
+        let matching_indices = |cx: &mut VisualTestContext| {
+            welcome_page.read_with(cx, |welcome_page, cx| {
+                welcome_page
+                    .matching_recent_workspaces(cx)
+                    .map(|(index, _, _, _)| index)
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(matching_indices(cx), vec![0, 1, 2]);
+
+        cx.simulate_keystrokes("c o d e space");
+        assert_eq!(
+            matching_indices(cx),
+            vec![0, 1, 2],
+            "keys pressed while the filter isn't focused shouldn't filter"
+        );
+
+        let filter = welcome_page.read_with(cx, |welcome_page, _| {
+            welcome_page
+                .recent_projects_filter
+                .clone()
+                .expect("the filter should be shown with recent projects")
+        });
+        let set_filter = |text: &str, cx: &mut VisualTestContext| {
+            cx.update(|window, cx| {
+                let editor = filter.read(cx).editor().clone();
+                editor.set_text(text, window, cx);
+            });
+            cx.run_until_parked();
+        };
+        set_filter("code", cx);
+        assert_eq!(matching_indices(cx), vec![0, 1]);
+
+        set_filter("code/oth", cx);
+        assert_eq!(matching_indices(cx), vec![1]);
+
+        set_filter("", cx);
+        assert_eq!(matching_indices(cx), vec![0, 1, 2]);
+    }
+}
@@ -134,7 +134,7 @@ use std::{
     time::Duration,
 };
 use task::{DebugScenario, SharedTaskContext, SpawnInTerminal};
-use theme::{ActiveTheme, SystemAppearance};
+use theme::ActiveTheme;
 use theme_settings::ThemeSettings;
 pub use toolbar::{
     PaneSearchBarCallbacks, Toolbar, ToolbarItemEvent, ToolbarItemLocation, ToolbarItemView,
@@ -1717,12 +1717,7 @@ impl Workspace {
                 cx.notify();
             }),
             cx.observe_window_appearance(window, |_, window, cx| {
-                let window_appearance = window.appearance();
-
-                *SystemAppearance::global_mut(cx) = SystemAppearance(window_appearance.into());
-
-                theme_settings::reload_theme(cx);
-                theme_settings::reload_icon_theme(cx);
+                theme_settings::set_system_appearance(window.appearance().into(), cx);
             }),
             cx.on_release({
                 let weak_handle = weak_handle.clone();
@@ -26,7 +26,7 @@ use gpui_platform;
 
 use gpui_tokio::Tokio;
 use language::LanguageRegistry;
-use onboarding::{FIRST_OPEN, show_onboarding_view};
+use onboarding::{FIRST_OPEN, show_onboarding_modal};
 use project_panel::ProjectPanel;
 use prompt_store::PromptBuilder;
 use remote::RemoteConnectionOptions;
@@ -764,15 +764,6 @@ fn main() {
             let http = app_state.client.http_client();
             let client = app_state.client.clone();
             move |cx| {
-                for &mut window in cx.windows().iter_mut() {
-                    let background_appearance = cx.theme().window_background_appearance();
-                    window
-                        .update(cx, |_, window, _| {
-                            window.set_background_appearance(background_appearance)
-                        })
-                        .ok();
-                }
-
                 cx.set_text_rendering_mode(
                     match WorkspaceSettings::get_global(cx).text_rendering_mode {
                         settings::TextRenderingMode::PlatformDefault => {
@@ -800,6 +791,7 @@ fn main() {
             let languages = app_state.languages.clone();
             move |cx| {
                 languages.set_theme(cx.theme().clone());
+                update_window_backgrounds(cx);
             }
         })
         .detach();
@@ -1462,7 +1454,7 @@ pub(crate) async fn restore_or_create_workspace(
             }
         }
     } else if matches!(kvp.read_kvp(FIRST_OPEN), Ok(None)) {
-        cx.update(|cx| show_onboarding_view(app_state, cx)).await?;
+        cx.update(|cx| show_onboarding_modal(app_state, cx)).await?;
     } else {
         cx.update(|cx| {
             workspace::open_new(
@@ -1765,6 +1757,21 @@ fn load_embedded_fonts(cx: &App) {
         .unwrap();
 }
 
+/// Applies the active theme's background to every open window, including the color
+/// that opaque windows are cleared to before each frame.
+fn update_window_backgrounds(cx: &mut App) {
+    let background_appearance = cx.theme().window_background_appearance();
+    let background_color = cx.theme().colors().background;
+    for window in cx.windows() {
+        window
+            .update(cx, |_, window, _| {
+                window.set_background_appearance(background_appearance);
+                window.set_clear_color(background_color);
+            })
+            .ok();
+    }
+}
+
 /// Spawns a background task to load the user themes from the themes directory.
 fn load_user_themes_in_background(fs: Arc<dyn fs::Fs>, cx: &mut App) {
     cx.spawn({
@@ -2738,6 +2738,7 @@ fn run_multi_workspace_sidebar_visual_tests(
                             thinking_effort: None,
                             ui_scroll_position: None,
                             draft_prompt: None,
+                            interrupted_turn: None,
                         },
                         path_list,
                         cx,
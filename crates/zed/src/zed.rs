@@ -394,6 +394,10 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut App) {
             .detach();
         }
 
+        // Match the theme from the first frame, so that areas exposed while the
+        // window is first shown or resized aren't cleared to white.
+        window.set_clear_color(cx.theme().colors().background);
+
         let multi_workspace_handle = cx.entity().downgrade();
         window.on_window_should_close(cx, move |window, cx| {
             multi_workspace_handle